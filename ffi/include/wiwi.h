@@ -0,0 +1,140 @@
+#ifndef WIWI_H
+#define WIWI_H
+
+/* Generated by cbindgen from ffi/src/lib.rs, do not edit by hand */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// Result of a call into this library
+typedef enum WiwiStatus {
+  // Success
+  WIWI_STATUS_OK = 0,
+  // The output buffer is too small. `*out_len` is set to the size needed.
+  WIWI_STATUS_BUFFER_TOO_SMALL = 1,
+  // A pointer that can't be null was null
+  WIWI_STATUS_NULL_POINTER = 2,
+  // The input has a length that is not valid for the encoding
+  WIWI_STATUS_INVALID_LENGTH = 3,
+  // The input contains a character not in the encoding's alphabet
+  WIWI_STATUS_INVALID_CHAR = 4,
+  // The input's padding is invalid
+  WIWI_STATUS_INVALID_PADDING = 5,
+  // The input's unused bits are not zero
+  WIWI_STATUS_NON_CANONICAL = 6,
+  // (Z85) A frame in the input decodes to a value that doesn't fit in 4 bytes
+  WIWI_STATUS_FRAME_OVERFLOW = 7,
+} WiwiStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Encodes `len` bytes at `bytes` into lowercase hex
+enum WiwiStatus wiwi_hex_encode(const uint8_t *bytes,
+                                size_t len,
+                                uint8_t *out,
+                                size_t out_cap,
+                                size_t *out_len);
+
+// Encodes `len` bytes at `bytes` into uppercase hex
+enum WiwiStatus wiwi_hex_encode_upper(const uint8_t *bytes,
+                                      size_t len,
+                                      uint8_t *out,
+                                      size_t out_cap,
+                                      size_t *out_len);
+
+// Decodes `len` bytes of hex (upper or lower case) at `bytes`
+enum WiwiStatus wiwi_hex_decode(const uint8_t *bytes,
+                                size_t len,
+                                uint8_t *out,
+                                size_t out_cap,
+                                size_t *out_len);
+
+// Exact length of encoding `len` bytes into hex
+size_t wiwi_hex_encoded_len(size_t len);
+
+// Maximum length of decoding `len` bytes of hex
+size_t wiwi_hex_decoded_len_max(size_t len);
+
+// Encodes `len` bytes at `bytes` into base32 (RFC 4648 alphabet), with padding
+enum WiwiStatus wiwi_base32_encode(const uint8_t *bytes,
+                                   size_t len,
+                                   uint8_t *out,
+                                   size_t out_cap,
+                                   size_t *out_len);
+
+// Decodes `len` bytes of base32 (RFC 4648 alphabet, with padding) at `bytes`
+enum WiwiStatus wiwi_base32_decode(const uint8_t *bytes,
+                                   size_t len,
+                                   uint8_t *out,
+                                   size_t out_cap,
+                                   size_t *out_len);
+
+// Exact length of encoding `len` bytes into base32
+size_t wiwi_base32_encoded_len(size_t len);
+
+// Maximum length of decoding `len` bytes of base32
+size_t wiwi_base32_decoded_len_max(size_t len);
+
+// Encodes `len` bytes at `bytes` into base64 (standard alphabet), with padding
+enum WiwiStatus wiwi_base64_encode(const uint8_t *bytes,
+                                   size_t len,
+                                   uint8_t *out,
+                                   size_t out_cap,
+                                   size_t *out_len);
+
+// Decodes `len` bytes of base64 (standard alphabet, with padding) at `bytes`
+enum WiwiStatus wiwi_base64_decode(const uint8_t *bytes,
+                                   size_t len,
+                                   uint8_t *out,
+                                   size_t out_cap,
+                                   size_t *out_len);
+
+// Encodes `len` bytes at `bytes` into base64 (URL and filename safe alphabet),
+// with padding
+enum WiwiStatus wiwi_base64url_encode(const uint8_t *bytes,
+                                      size_t len,
+                                      uint8_t *out,
+                                      size_t out_cap,
+                                      size_t *out_len);
+
+// Decodes `len` bytes of base64 (URL and filename safe alphabet, with padding)
+// at `bytes`
+enum WiwiStatus wiwi_base64url_decode(const uint8_t *bytes,
+                                      size_t len,
+                                      uint8_t *out,
+                                      size_t out_cap,
+                                      size_t *out_len);
+
+// Exact length of encoding `len` bytes into base64 (either alphabet)
+size_t wiwi_base64_encoded_len(size_t len);
+
+// Maximum length of decoding `len` bytes of base64 (either alphabet)
+size_t wiwi_base64_decoded_len_max(size_t len);
+
+// Encodes `len` bytes at `bytes` into Z85, adding padding if necessary
+enum WiwiStatus wiwi_z85_encode(const uint8_t *bytes,
+                                size_t len,
+                                uint8_t *out,
+                                size_t out_cap,
+                                size_t *out_len);
+
+// Decodes `len` bytes of Z85 at `bytes`
+enum WiwiStatus wiwi_z85_decode(const uint8_t *bytes,
+                                size_t len,
+                                uint8_t *out,
+                                size_t out_cap,
+                                size_t *out_len);
+
+// Exact length of encoding `len` bytes into Z85
+size_t wiwi_z85_encoded_len(size_t len);
+
+// Maximum length of decoding `len` bytes of Z85
+size_t wiwi_z85_decoded_len_max(size_t len);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* WIWI_H */
@@ -0,0 +1,472 @@
+//! C API for wiwi's hex, base32, base64, and Z85 codecs
+//!
+//! Every function writes into a buffer provided by the caller, and returns a
+//! [`WiwiStatus`]. The amount of bytes written is stored in `*out_len`. If the
+//! buffer is too small, nothing is written to it,
+//! [`WiwiStatus::BufferTooSmall`] is returned, and `*out_len` is set to the
+//! size the buffer needs to be instead, so passing a null `out` with an
+//! `out_cap` of 0 can be used to query the length. The `*_encoded_len` and
+//! `*_decoded_len_max` functions can also be used to size buffers up front.
+//!
+//! Output is written straight into `out`. The length it needs is worked out
+//! before encoding or decoding anything: from the input length when encoding,
+//! and from the input's length and padding when decoding. So querying the
+//! length is cheap, and decoding input with invalid chars only reports
+//! [`WiwiStatus::InvalidChar`] once the buffer is big enough. If decoding
+//! fails for any reason other than the buffer being too small, `*out_len`
+//! is set to 0.
+//!
+//! Output is not NUL terminated. Input and output buffers must not overlap.
+//!
+//! The header for this API is at `ffi/include/wiwi.h`, and is generated with
+//! [cbindgen](https://github.com/mozilla/cbindgen) (from the `ffi` directory):
+//!
+//! ```sh
+//! cbindgen --config cbindgen.toml --output include/wiwi.h
+//! ```
+
+#![allow(clippy::missing_safety_doc)]
+
+use ::std::slice;
+use ::wiwi::{ base32, base64, hex, z85 };
+use ::wiwi::base64::Config;
+
+/// Result of a call into this library
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WiwiStatus {
+	/// Success
+	Ok = 0,
+	/// The output buffer is too small. `*out_len` is set to the size needed.
+	BufferTooSmall = 1,
+	/// A pointer that can't be null was null
+	NullPointer = 2,
+	/// The input has a length that is not valid for the encoding
+	InvalidLength = 3,
+	/// The input contains a character not in the encoding's alphabet
+	InvalidChar = 4,
+	/// The input's padding is invalid
+	InvalidPadding = 5,
+	/// The input's unused bits are not zero
+	NonCanonical = 6,
+	/// (Z85) A frame in the input decodes to a value that doesn't fit in 4 bytes
+	FrameOverflow = 7
+}
+
+impl From<hex::DecodeError> for WiwiStatus {
+	fn from(e: hex::DecodeError) -> Self {
+		match e {
+			hex::DecodeError::InvalidLength => { Self::InvalidLength }
+			hex::DecodeError::InvalidChar => { Self::InvalidChar }
+		}
+	}
+}
+
+impl From<base32::DecodeError> for WiwiStatus {
+	fn from(e: base32::DecodeError) -> Self {
+		match e {
+			base32::DecodeError::InvalidLength => { Self::InvalidLength }
+			base32::DecodeError::InvalidChar => { Self::InvalidChar }
+			base32::DecodeError::InvalidPadding => { Self::InvalidPadding }
+			base32::DecodeError::NonCanonical => { Self::NonCanonical }
+		}
+	}
+}
+
+impl From<base64::DecodeError> for WiwiStatus {
+	fn from(e: base64::DecodeError) -> Self {
+		match e {
+			base64::DecodeError::InvalidLength => { Self::InvalidLength }
+			base64::DecodeError::InvalidChar => { Self::InvalidChar }
+			base64::DecodeError::InvalidPadding => { Self::InvalidPadding }
+			base64::DecodeError::NonCanonical => { Self::NonCanonical }
+		}
+	}
+}
+
+impl From<z85::DecodeError> for WiwiStatus {
+	fn from(e: z85::DecodeError) -> Self {
+		match e {
+			z85::DecodeError::InvalidLength => { Self::InvalidLength }
+			z85::DecodeError::InvalidChar => { Self::InvalidChar }
+			z85::DecodeError::FrameOverflow => { Self::FrameOverflow }
+			z85::DecodeError::NonCanonical => { Self::NonCanonical }
+		}
+	}
+}
+
+/// Encodes `len` bytes at `bytes` into lowercase hex
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_hex_encode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	encode(bytes, len, out, out_cap, out_len, wiwi_hex_encoded_len(len), hex::encode_hex_to_slice)
+}
+
+/// Encodes `len` bytes at `bytes` into uppercase hex
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_hex_encode_upper(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	encode(bytes, len, out, out_cap, out_len, wiwi_hex_encoded_len(len), hex::encode_hex_upper_to_slice)
+}
+
+/// Decodes `len` bytes of hex (upper or lower case) at `bytes`
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_hex_decode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	decode(bytes, len, out, out_cap, out_len, hex::decoded_len_hex, hex::decode_hex_to_slice)
+}
+
+/// Exact length of encoding `len` bytes into hex
+#[no_mangle]
+pub extern "C" fn wiwi_hex_encoded_len(len: usize) -> usize {
+	len * 2
+}
+
+/// Maximum length of decoding `len` bytes of hex
+#[no_mangle]
+pub extern "C" fn wiwi_hex_decoded_len_max(len: usize) -> usize {
+	len / 2
+}
+
+/// Encodes `len` bytes at `bytes` into base32 (RFC 4648 alphabet), with padding
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_base32_encode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	encode(bytes, len, out, out_cap, out_len, wiwi_base32_encoded_len(len), base32::encode_base32_to_slice)
+}
+
+/// Decodes `len` bytes of base32 (RFC 4648 alphabet, with padding) at `bytes`
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_base32_decode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	decode(bytes, len, out, out_cap, out_len, base32::decoded_len_base32, base32::decode_base32_to_slice)
+}
+
+/// Exact length of encoding `len` bytes into base32
+#[no_mangle]
+pub extern "C" fn wiwi_base32_encoded_len(len: usize) -> usize {
+	len.div_ceil(base32::BINARY_FRAME_LEN) * base32::STRING_FRAME_LEN
+}
+
+/// Maximum length of decoding `len` bytes of base32
+#[no_mangle]
+pub extern "C" fn wiwi_base32_decoded_len_max(len: usize) -> usize {
+	len / base32::STRING_FRAME_LEN * base32::BINARY_FRAME_LEN
+}
+
+/// Encodes `len` bytes at `bytes` into base64 (standard alphabet), with padding
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_base64_encode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	encode(bytes, len, out, out_cap, out_len, wiwi_base64_encoded_len(len), base64::encode_base64_to_slice)
+}
+
+/// Decodes `len` bytes of base64 (standard alphabet, with padding) at `bytes`
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_base64_decode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	decode(
+		bytes, len, out, out_cap, out_len,
+		|bytes| base64::decoded_len_base64(bytes, &Config::STANDARD),
+		base64::decode_base64_to_slice
+	)
+}
+
+/// Encodes `len` bytes at `bytes` into base64 (URL and filename safe alphabet),
+/// with padding
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_base64url_encode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	encode(
+		bytes, len, out, out_cap, out_len,
+		wiwi_base64_encoded_len(len),
+		|bytes, out| base64::encode_base64_to_slice_with(bytes, out, &Config::URL_SAFE)
+	)
+}
+
+/// Decodes `len` bytes of base64 (URL and filename safe alphabet, with padding)
+/// at `bytes`
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_base64url_decode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	decode(
+		bytes, len, out, out_cap, out_len,
+		|bytes| base64::decoded_len_base64(bytes, &Config::URL_SAFE),
+		|bytes, out| base64::decode_base64_to_slice_with(bytes, out, &Config::URL_SAFE)
+	)
+}
+
+/// Exact length of encoding `len` bytes into base64 (either alphabet)
+#[no_mangle]
+pub extern "C" fn wiwi_base64_encoded_len(len: usize) -> usize {
+	len.div_ceil(base64::BINARY_FRAME_LEN) * base64::STRING_FRAME_LEN
+}
+
+/// Maximum length of decoding `len` bytes of base64 (either alphabet)
+#[no_mangle]
+pub extern "C" fn wiwi_base64_decoded_len_max(len: usize) -> usize {
+	len / base64::STRING_FRAME_LEN * base64::BINARY_FRAME_LEN
+}
+
+/// Encodes `len` bytes at `bytes` into Z85, adding padding if necessary
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_z85_encode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	encode(bytes, len, out, out_cap, out_len, wiwi_z85_encoded_len(len), z85::encode_z85_to_slice)
+}
+
+/// Decodes `len` bytes of Z85 at `bytes`
+#[no_mangle]
+pub unsafe extern "C" fn wiwi_z85_decode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> WiwiStatus {
+	decode(bytes, len, out, out_cap, out_len, z85::decoded_len_z85, z85::decode_z85_to_slice)
+}
+
+/// Exact length of encoding `len` bytes into Z85
+#[no_mangle]
+pub extern "C" fn wiwi_z85_encoded_len(len: usize) -> usize {
+	// the padded frame also gets a char recording the amount of padding
+	let frames = len.div_ceil(z85::BINARY_FRAME_LEN);
+	let marker = if len % z85::BINARY_FRAME_LEN == 0 { 0 } else { 1 };
+	frames * z85::STRING_FRAME_LEN + marker
+}
+
+/// Maximum length of decoding `len` bytes of Z85
+#[no_mangle]
+pub extern "C" fn wiwi_z85_decoded_len_max(len: usize) -> usize {
+	len / z85::STRING_FRAME_LEN * z85::BINARY_FRAME_LEN
+}
+
+/// Encodes `bytes` directly into `out` with `f`, if `out` has room for the
+/// `encoded_len` bytes it'll write
+unsafe fn encode(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize,
+	encoded_len: usize,
+	f: fn(&[u8], &mut [u8]) -> usize
+) -> WiwiStatus {
+	let Some(bytes) = input(bytes, len) else { return WiwiStatus::NullPointer };
+	let out = match output(encoded_len, out, out_cap, out_len) {
+		Ok(out) => { out }
+		Err(status) => { return status }
+	};
+
+	f(bytes, out);
+	WiwiStatus::Ok
+}
+
+/// Decodes `bytes` directly into `out` with `f`, if `out` has room for the
+/// amount of bytes `decoded_len` works out from the length and padding of
+/// the input
+unsafe fn decode<E: Into<WiwiStatus>>(
+	bytes: *const u8,
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize,
+	decoded_len: fn(&[u8]) -> Result<usize, E>,
+	f: fn(&[u8], &mut [u8]) -> Result<usize, E>
+) -> WiwiStatus {
+	let Some(bytes) = input(bytes, len) else { return WiwiStatus::NullPointer };
+	let decoded_len = match decoded_len(bytes) {
+		Ok(decoded_len) => { decoded_len }
+		Err(e) => {
+			if !out_len.is_null() { *out_len = 0 }
+			return e.into()
+		}
+	};
+	let out = match output(decoded_len, out, out_cap, out_len) {
+		Ok(out) => { out }
+		Err(status) => { return status }
+	};
+
+	match f(bytes, out) {
+		Ok(_) => { WiwiStatus::Ok }
+		Err(e) => {
+			// whatever was written to `out` before the error isn't valid output
+			*out_len = 0;
+			e.into()
+		}
+	}
+}
+
+/// Makes a slice out of the input, allowing null if `len` is 0
+#[inline]
+unsafe fn input<'h>(bytes: *const u8, len: usize) -> Option<&'h [u8]> {
+	match (bytes.is_null(), len) {
+		(_, 0) => { Some(&[]) }
+		(true, _) => { None }
+		(false, len) => { Some(slice::from_raw_parts(bytes, len)) }
+	}
+}
+
+/// Stores `len` in `*out_len`, and makes a slice of `len` bytes out of `out`
+/// if it has room for them
+#[inline]
+unsafe fn output<'h>(
+	len: usize,
+	out: *mut u8,
+	out_cap: usize,
+	out_len: *mut usize
+) -> Result<&'h mut [u8], WiwiStatus> {
+	if out_len.is_null() { return Err(WiwiStatus::NullPointer) }
+	*out_len = len;
+
+	if len > out_cap { return Err(WiwiStatus::BufferTooSmall) }
+	if len == 0 { return Ok(&mut []) }
+	if out.is_null() { return Err(WiwiStatus::NullPointer) }
+
+	Ok(slice::from_raw_parts_mut(out, len))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::std::ptr;
+
+	type CodecFn = unsafe extern "C" fn(*const u8, usize, *mut u8, usize, *mut usize) -> WiwiStatus;
+	type LenFn = extern "C" fn(usize) -> usize;
+
+	fn call(f: CodecFn, bytes: &[u8], out_cap: usize) -> (WiwiStatus, Vec<u8>) {
+		let mut out = vec![0u8; out_cap];
+		let mut out_len = 0;
+		let status = unsafe { f(bytes.as_ptr(), bytes.len(), out.as_mut_ptr(), out_cap, &mut out_len) };
+		out.truncate(out_len.min(out_cap));
+		(status, out)
+	}
+
+	#[test]
+	fn round_trip() {
+		let codecs: [(CodecFn, CodecFn, LenFn, LenFn); 6] = [
+			(wiwi_hex_encode, wiwi_hex_decode, wiwi_hex_encoded_len, wiwi_hex_decoded_len_max),
+			(wiwi_hex_encode_upper, wiwi_hex_decode, wiwi_hex_encoded_len, wiwi_hex_decoded_len_max),
+			(wiwi_base32_encode, wiwi_base32_decode, wiwi_base32_encoded_len, wiwi_base32_decoded_len_max),
+			(wiwi_base64_encode, wiwi_base64_decode, wiwi_base64_encoded_len, wiwi_base64_decoded_len_max),
+			(wiwi_base64url_encode, wiwi_base64url_decode, wiwi_base64_encoded_len, wiwi_base64_decoded_len_max),
+			(wiwi_z85_encode, wiwi_z85_decode, wiwi_z85_encoded_len, wiwi_z85_decoded_len_max)
+		];
+
+		let bytes = (0..=255).collect::<Vec<u8>>();
+		for (encode, decode, encoded_len, decoded_len_max) in codecs {
+			for len in 0..20 {
+				let bytes = &bytes[..len * 13];
+
+				let (status, encoded) = call(encode, bytes, encoded_len(bytes.len()));
+				assert_eq!(status, WiwiStatus::Ok);
+				assert_eq!(encoded.len(), encoded_len(bytes.len()));
+
+				let (status, decoded) = call(decode, &encoded, decoded_len_max(encoded.len()));
+				assert_eq!(status, WiwiStatus::Ok);
+				assert_eq!(decoded, bytes);
+			}
+		}
+	}
+
+	#[test]
+	fn buffer_too_small() {
+		let mut out_len = 0;
+		let status = unsafe { wiwi_z85_encode(b"hello".as_ptr(), 5, ptr::null_mut(), 0, &mut out_len) };
+		assert_eq!(status, WiwiStatus::BufferTooSmall);
+		assert_eq!(out_len, wiwi_z85_encoded_len(5));
+
+		let (status, out) = call(wiwi_hex_decode, b"abcd", 1);
+		assert_eq!(status, WiwiStatus::BufferTooSmall);
+		assert!(out.iter().all(|b| *b == 0));
+
+		// the decoded length comes from the length and padding of the input,
+		// so querying it doesn't decode (or check) the rest
+		let mut out_len = 0;
+		let status = unsafe { wiwi_base64_decode(b"Zm9v*A==".as_ptr(), 8, ptr::null_mut(), 0, &mut out_len) };
+		assert_eq!(status, WiwiStatus::BufferTooSmall);
+		assert_eq!(out_len, 4);
+		assert_eq!(call(wiwi_base64_decode, b"Zm9v*A==", 4).0, WiwiStatus::InvalidChar);
+
+		let status = unsafe { wiwi_z85_decode(b"HelloWorld4".as_ptr(), 11, ptr::null_mut(), 0, &mut out_len) };
+		assert_eq!(status, WiwiStatus::InvalidChar);
+	}
+
+	#[test]
+	fn errors() {
+		let cases: [(CodecFn, &[u8], WiwiStatus); 5] = [
+			(wiwi_hex_decode, b"abc", WiwiStatus::InvalidLength),
+			(wiwi_hex_decode, b"abcx", WiwiStatus::InvalidChar),
+			(wiwi_base32_decode, b"MZXW6Y==", WiwiStatus::InvalidPadding),
+			(wiwi_base64_decode, b"Zm9v-_==", WiwiStatus::InvalidChar),
+			(wiwi_z85_decode, b"%%%%%", WiwiStatus::FrameOverflow)
+		];
+		for (f, bytes, expected) in cases {
+			let mut out = [0u8; 8];
+			let mut out_len = usize::MAX;
+			let status = unsafe { f(bytes.as_ptr(), bytes.len(), out.as_mut_ptr(), out.len(), &mut out_len) };
+			assert_eq!(status, expected);
+			assert_eq!(out_len, 0);
+		}
+
+		let mut out_len = 0;
+		let status = unsafe { wiwi_hex_encode(ptr::null(), 1, ptr::null_mut(), 0, &mut out_len) };
+		assert_eq!(status, WiwiStatus::NullPointer);
+		let status = unsafe { wiwi_hex_encode(ptr::null(), 0, ptr::null_mut(), 0, ptr::null_mut()) };
+		assert_eq!(status, WiwiStatus::NullPointer);
+		let status = unsafe { wiwi_hex_encode(ptr::null(), 0, ptr::null_mut(), 0, &mut out_len) };
+		assert_eq!(status, WiwiStatus::Ok);
+		assert_eq!(out_len, 0);
+	}
+}
@@ -0,0 +1,71 @@
+// Exercises the C API through the generated header, returning nonzero on
+// the first failed check. Built and run by `tests/harness.rs`.
+
+#include <stdio.h>
+#include <string.h>
+
+#include "wiwi.h"
+
+#define CHECK(cond) do { \
+	if (!(cond)) { \
+		fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+		return 1; \
+	} \
+} while (0)
+
+typedef WiwiStatus (*codec_fn)(const uint8_t *, size_t, uint8_t *, size_t, size_t *);
+typedef size_t (*len_fn)(size_t);
+
+static int round_trip(
+	codec_fn encode,
+	codec_fn decode,
+	len_fn encoded_len,
+	len_fn decoded_len_max,
+	const char *bytes,
+	const char *expected
+) {
+	uint8_t encoded[64];
+	uint8_t decoded[64];
+	size_t len = strlen(bytes);
+	size_t out_len = 0;
+
+	// length query, without a buffer
+	CHECK(encode((const uint8_t *) bytes, len, NULL, 0, &out_len) == WIWI_STATUS_BUFFER_TOO_SMALL);
+	CHECK(out_len == strlen(expected));
+	CHECK(out_len == encoded_len(len));
+
+	CHECK(encode((const uint8_t *) bytes, len, encoded, sizeof(encoded), &out_len) == WIWI_STATUS_OK);
+	CHECK(out_len == strlen(expected));
+	CHECK(memcmp(encoded, expected, out_len) == 0);
+
+	size_t encoded_len_ = out_len;
+	CHECK(decoded_len_max(encoded_len_) >= len);
+	CHECK(decode(encoded, encoded_len_, decoded, sizeof(decoded), &out_len) == WIWI_STATUS_OK);
+	CHECK(out_len == len);
+	CHECK(memcmp(decoded, bytes, len) == 0);
+
+	return 0;
+}
+
+int main(void) {
+	CHECK(round_trip(wiwi_hex_encode, wiwi_hex_decode, wiwi_hex_encoded_len, wiwi_hex_decoded_len_max, "wiwi", "77697769") == 0);
+	CHECK(round_trip(wiwi_hex_encode_upper, wiwi_hex_decode, wiwi_hex_encoded_len, wiwi_hex_decoded_len_max, "\xde\xad", "DEAD") == 0);
+	CHECK(round_trip(wiwi_base32_encode, wiwi_base32_decode, wiwi_base32_encoded_len, wiwi_base32_decoded_len_max, "foobar", "MZXW6YTBOI======") == 0);
+	CHECK(round_trip(wiwi_base64_encode, wiwi_base64_decode, wiwi_base64_encoded_len, wiwi_base64_decoded_len_max, "\xfb\xff", "+/8=") == 0);
+	CHECK(round_trip(wiwi_base64url_encode, wiwi_base64url_decode, wiwi_base64_encoded_len, wiwi_base64_decoded_len_max, "\xfb\xff", "-_8=") == 0);
+	CHECK(round_trip(wiwi_z85_encode, wiwi_z85_decode, wiwi_z85_encoded_len, wiwi_z85_decoded_len_max, "\x86\x4f\xd2\x6f\xb5\x59\xf7\x5b", "HelloWorld") == 0);
+
+	uint8_t out[16];
+	size_t out_len = 0;
+
+	CHECK(wiwi_hex_decode((const uint8_t *) "abc", 3, out, sizeof(out), &out_len) == WIWI_STATUS_INVALID_LENGTH);
+	CHECK(wiwi_hex_decode((const uint8_t *) "abcx", 4, out, sizeof(out), &out_len) == WIWI_STATUS_INVALID_CHAR);
+	CHECK(wiwi_base32_decode((const uint8_t *) "MZXW6Y==", 8, out, sizeof(out), &out_len) == WIWI_STATUS_INVALID_PADDING);
+	CHECK(wiwi_z85_decode((const uint8_t *) "%%%%%", 5, out, sizeof(out), &out_len) == WIWI_STATUS_FRAME_OVERFLOW);
+	CHECK(wiwi_hex_decode((const uint8_t *) "abcd", 4, out, 1, &out_len) == WIWI_STATUS_BUFFER_TOO_SMALL);
+	CHECK(out_len == 2);
+	CHECK(wiwi_hex_encode(NULL, 1, out, sizeof(out), &out_len) == WIWI_STATUS_NULL_POINTER);
+
+	puts("ok");
+	return 0;
+}
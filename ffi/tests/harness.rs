@@ -0,0 +1,42 @@
+//! Builds the library as a static library, then compiles and runs
+//! `harness.c` against it, to make sure the C API works through the header
+
+use ::std::env;
+use ::std::path::{ Path, PathBuf };
+use ::std::process::Command;
+
+#[test]
+fn c_harness() {
+	let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+	let release = !cfg!(debug_assertions);
+
+	// `cargo test` only builds the rlib, so build the staticlib ourselves
+	let mut build = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+	build.args(["build", "-p", "wiwi-ffi"]).current_dir(manifest_dir);
+	if release { build.arg("--release"); }
+	assert!(build.status().unwrap().success(), "building staticlib failed");
+
+	// tests are at `target/<profile>/deps/<test>`
+	let exe = env::current_exe().unwrap();
+	let profile_dir = exe.parent().and_then(Path::parent).unwrap();
+	let lib = profile_dir.join("libwiwi_ffi.a");
+	assert!(lib.exists(), "{} exists", lib.display());
+
+	let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("wiwi-ffi-harness");
+	let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".into()))
+		.arg("-std=c99")
+		.arg("-Wall")
+		.arg("-Werror")
+		.arg("-I").arg(manifest_dir.join("include"))
+		.arg(manifest_dir.join("tests/harness.c"))
+		.arg(&lib)
+		.args(["-lpthread", "-ldl", "-lm"])
+		.arg("-o").arg(&out)
+		.status()
+		.unwrap();
+	assert!(status.success(), "compiling harness failed");
+
+	let output = Command::new(&out).output().unwrap();
+	assert!(output.status.success(), "harness failed: {}", String::from_utf8_lossy(&output.stderr));
+	assert_eq!(output.stdout, b"ok\n");
+}
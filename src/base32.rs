@@ -1,10 +1,25 @@
-use crate::encoding_utils::{ ChunkedSlice, UnsafeBufWriteGuard };
+use crate::encoding_utils::{ available_threads, for_each_frame_run, parallel_chunks, ChunkedSlice, UnsafeBufWrite, UnsafeBufWriteDiscard, UnsafeBufWriteGuard, UnsafeBufWriteSlice };
 use ::std::{ hint, ptr };
 
-// // table unused, for ref only, cause it can be calculated
-// pub const TABLE_ENCODER_LEN: usize = 32;
-// pub static TABLE_ENCODER: &[u8; TABLE_ENCODER_LEN] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
-// pub static TABLE_ENCODER_BASE32HEX: &[u8; TABLE_ENCODER_LEN] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+pub use crate::encoding_utils::VectoredInput;
+
+// encoding calculates the chars instead of using these tables, but they're
+// used to generate the decoding tables at compile time
+pub const TABLE_ENCODER_LEN: usize = 32;
+pub const TABLE_ENCODER: [u8; TABLE_ENCODER_LEN] = *b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+pub const TABLE_ENCODER_BASE32HEX: [u8; TABLE_ENCODER_LEN] = *b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+pub const TABLE_DECODER_LEN: usize = 256;
+pub static TABLE_DECODER: [Option<u8>; TABLE_DECODER_LEN] = decoder_table(&TABLE_ENCODER);
+pub static TABLE_DECODER_BASE32HEX: [Option<u8>; TABLE_DECODER_LEN] = decoder_table(&TABLE_ENCODER_BASE32HEX);
+
+/// [Crockford's base32] alphabet, as used by ULIDs
+///
+/// [Crockford's base32]: https://www.crockford.com/base32.html
+pub const TABLE_ENCODER_CROCKFORD: [u8; TABLE_ENCODER_LEN] = *b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// Decoding Crockford's base32 is case insensitive, and also accepts `I` and
+/// `L` as `1`, and `O` as `0`
+pub static TABLE_DECODER_CROCKFORD: [Option<u8>; TABLE_DECODER_LEN] = crockford_decoder_table();
 
 pub const BINARY_FRAME_LEN: usize = 5;
 pub const STRING_FRAME_LEN: usize = 8;
@@ -27,6 +42,299 @@ pub fn encode_base32hex(bytes: &[u8]) -> String {
 	_encode::<9, b'0', { b'A' - 10 }>(bytes)
 }
 
+/// Encodes the given bytes into base32, writing it to the start of `dest`
+/// instead of allocating, and returning the amount of bytes written.
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than the encoded length (`bytes.len()` rounded
+/// up to a multiple of 5, divided by 5, times 8).
+#[inline]
+pub fn encode_base32_to_slice(bytes: &[u8], dest: &mut [u8]) -> usize {
+	_encode_to_slice::<25, b'A', { b'2' - 26 }>(bytes, dest)
+}
+
+/// Encodes the given bytes into base32hex, writing it to the start of `dest`
+/// instead of allocating, and returning the amount of bytes written.
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than the encoded length (see
+/// [`encode_base32_to_slice`]).
+#[inline]
+pub fn encode_base32hex_to_slice(bytes: &[u8], dest: &mut [u8]) -> usize {
+	_encode_to_slice::<9, b'0', { b'A' - 10 }>(bytes, dest)
+}
+
+/// Encodes the concatenation of several slices (ex. `&[IoSlice]`) into one
+/// base32 [`String`], without joining them first
+#[inline]
+pub fn encode_base32_vectored<V: VectoredInput + ?Sized>(input: &V) -> String {
+	_encode_vectored::<25, b'A', { b'2' - 26 }, V>(input)
+}
+
+/// Encodes the concatenation of several slices (ex. `&[IoSlice]`) into one
+/// base32hex [`String`], without joining them first
+#[inline]
+pub fn encode_base32hex_vectored<V: VectoredInput + ?Sized>(input: &V) -> String {
+	_encode_vectored::<9, b'0', { b'A' - 10 }, V>(input)
+}
+
+/// Encodes the given bytes into a base32 [`String`], splitting the work across
+/// multiple threads for large inputs. Smaller inputs are encoded on the
+/// calling thread, the same as [`encode_base32`].
+#[inline]
+pub fn encode_base32_parallel(bytes: &[u8]) -> String {
+	_encode_parallel::<25, b'A', { b'2' - 26 }>(bytes, available_threads())
+}
+
+/// Encodes the given bytes into a base32hex [`String`], splitting the work
+/// across multiple threads for large inputs. Smaller inputs are encoded on
+/// the calling thread, the same as [`encode_base32hex`].
+#[inline]
+pub fn encode_base32hex_parallel(bytes: &[u8]) -> String {
+	_encode_parallel::<9, b'0', { b'A' - 10 }>(bytes, available_threads())
+}
+
+/// Decodes a base32 string (with padding) back into the source bytes
+#[inline]
+pub fn decode_base32(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode(bytes, &TABLE_DECODER)
+}
+
+/// Decodes a base32hex string (with padding) back into the source bytes
+#[inline]
+pub fn decode_base32hex(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode(bytes, &TABLE_DECODER_BASE32HEX)
+}
+
+/// Decodes a base32 string back into the source bytes, splitting the work
+/// across multiple threads for large inputs. Smaller inputs are decoded on
+/// the calling thread, the same as [`decode_base32`].
+#[inline]
+pub fn decode_base32_parallel(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode_parallel(bytes, &TABLE_DECODER, available_threads())
+}
+
+/// Decodes a base32hex string back into the source bytes, splitting the work
+/// across multiple threads for large inputs. Smaller inputs are decoded on
+/// the calling thread, the same as [`decode_base32hex`].
+#[inline]
+pub fn decode_base32hex_parallel(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode_parallel(bytes, &TABLE_DECODER_BASE32HEX, available_threads())
+}
+
+/// Decodes a base32 string (with padding) in place, writing the decoded bytes
+/// over the start of the input buffer, and returning the part of the buffer
+/// they were written to.
+///
+/// If an error is returned, the contents of the buffer are unspecified (part
+/// of it may have already been overwritten).
+#[inline]
+pub fn decode_base32_in_place(bytes: &mut [u8]) -> Result<&mut [u8], DecodeError> {
+	_decode_in_place(bytes, &TABLE_DECODER)
+}
+
+/// Decodes a base32hex string (with padding) in place, writing the decoded
+/// bytes over the start of the input buffer, and returning the part of the
+/// buffer they were written to.
+///
+/// If an error is returned, the contents of the buffer are unspecified (part
+/// of it may have already been overwritten).
+#[inline]
+pub fn decode_base32hex_in_place(bytes: &mut [u8]) -> Result<&mut [u8], DecodeError> {
+	_decode_in_place(bytes, &TABLE_DECODER_BASE32HEX)
+}
+
+/// Amount of bytes the given base32 or base32hex string (with padding)
+/// decodes to. Only the length and padding of the input are checked, not its
+/// chars, so this doesn't go through the whole input.
+pub fn decoded_len_base32(bytes: &[u8]) -> Result<usize, DecodeError> {
+	if bytes.len() % STRING_FRAME_LEN != 0 { return Err(DecodeError::InvalidLength) }
+	if bytes.is_empty() { return Ok(0) }
+
+	let (_, last_frame_bytes) = last_frame_info(&bytes[bytes.len() - STRING_FRAME_LEN..])?;
+	Ok((bytes.len() / STRING_FRAME_LEN - 1) * BINARY_FRAME_LEN + last_frame_bytes)
+}
+
+/// Decodes a base32 string (with padding), writing the decoded bytes to the
+/// start of `dest` instead of allocating, and returning the amount of bytes
+/// written.
+///
+/// If an error is returned, the contents of `dest` are unspecified (part of it
+/// may have already been written to).
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than [`decoded_len_base32`].
+#[inline]
+pub fn decode_base32_to_slice(bytes: &[u8], dest: &mut [u8]) -> Result<usize, DecodeError> {
+	_decode_to_slice(bytes, dest, &TABLE_DECODER)
+}
+
+/// Decodes a base32hex string (with padding), writing the decoded bytes to
+/// the start of `dest` instead of allocating, and returning the amount of
+/// bytes written.
+///
+/// If an error is returned, the contents of `dest` are unspecified (part of it
+/// may have already been written to).
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than [`decoded_len_base32`].
+#[inline]
+pub fn decode_base32hex_to_slice(bytes: &[u8], dest: &mut [u8]) -> Result<usize, DecodeError> {
+	_decode_to_slice(bytes, dest, &TABLE_DECODER_BASE32HEX)
+}
+
+/// Checks that the given bytes are valid base32 (with padding), returning the
+/// same error [`decode_base32`] would, but without allocating anywhere to put
+/// the decoded bytes.
+///
+/// Like [`decode_base32`], this ignores unused bits in the last char. To also
+/// check those are zero, use [`is_canonical_base32`].
+#[inline]
+pub fn validate_base32(bytes: &[u8]) -> Result<(), DecodeError> {
+	_validate(bytes, &TABLE_DECODER)
+}
+
+/// Checks that the given bytes are valid base32hex (with padding), returning
+/// the same error [`decode_base32hex`] would, but without allocating anywhere
+/// to put the decoded bytes. Unused bits in the last char are ignored (see
+/// [`is_canonical_base32hex`]).
+#[inline]
+pub fn validate_base32hex(bytes: &[u8]) -> Result<(), DecodeError> {
+	_validate(bytes, &TABLE_DECODER_BASE32HEX)
+}
+
+/// Decodes a base32 string (with padding) back into the source bytes, only
+/// accepting the canonical encoding. Input that [`decode_base32`] would accept
+/// but with any of the unused bits in the last char set (so it isn't what
+/// [`encode_base32`] would produce for the same bytes) is rejected with
+/// [`DecodeError::NonCanonical`].
+#[inline]
+pub fn decode_base32_strict(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	check_canonical(bytes, &TABLE_DECODER)?;
+	_decode(bytes, &TABLE_DECODER)
+}
+
+/// Decodes a base32hex string (with padding) back into the source bytes, only
+/// accepting the canonical encoding (see [`decode_base32_strict`])
+#[inline]
+pub fn decode_base32hex_strict(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	check_canonical(bytes, &TABLE_DECODER_BASE32HEX)?;
+	_decode(bytes, &TABLE_DECODER_BASE32HEX)
+}
+
+/// Whether the given bytes are exactly what [`encode_base32`] produces for the
+/// bytes they decode to. Returns `false` for invalid base32.
+#[inline]
+pub fn is_canonical_base32(bytes: &[u8]) -> bool {
+	check_canonical(bytes, &TABLE_DECODER).is_ok() && validate_base32(bytes).is_ok()
+}
+
+/// Whether the given bytes are exactly what [`encode_base32hex`] produces for
+/// the bytes they decode to. Returns `false` for invalid base32hex.
+#[inline]
+pub fn is_canonical_base32hex(bytes: &[u8]) -> bool {
+	check_canonical(bytes, &TABLE_DECODER_BASE32HEX).is_ok() && validate_base32hex(bytes).is_ok()
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError {
+	#[error("invalid length")]
+	InvalidLength,
+	#[error("invalid character")]
+	InvalidChar,
+	#[error("invalid padding")]
+	InvalidPadding,
+	#[error("non canonical encoding (unused bits are not zero)")]
+	NonCanonical
+}
+
+#[cfg(feature = "bytes")]
+pub use self::bytes::*;
+
+/// Encoding and decoding from any [`Buf`](::bytes::Buf) (which doesn't have
+/// to be contiguous), appending the output to a [`BytesMut`](::bytes::BytesMut)
+#[cfg(feature = "bytes")]
+mod bytes {
+	use crate::encoding_utils::bytes::{ decode_buf, encode_buf, BufCodec };
+	use ::bytes::{ Buf, BytesMut };
+	use super::*;
+
+	/// Base32 encodes everything remaining in `src`, appending it to `dest`
+	#[inline]
+	pub fn encode_base32_buf<B: Buf>(src: B, dest: &mut BytesMut) {
+		encode_buf(&Base32BufCodec::<25, b'A', { b'2' - 26 }>(&TABLE_DECODER), src, dest)
+	}
+
+	/// Base32hex encodes everything remaining in `src`, appending it to `dest`
+	#[inline]
+	pub fn encode_base32hex_buf<B: Buf>(src: B, dest: &mut BytesMut) {
+		encode_buf(&Base32BufCodec::<9, b'0', { b'A' - 10 }>(&TABLE_DECODER_BASE32HEX), src, dest)
+	}
+
+	/// Decodes the base32 (with padding) remaining in `src`, appending the
+	/// decoded bytes to `dest`. If an error is returned, `dest` is left as it
+	/// was, but an unspecified amount of `src` will have been consumed.
+	#[inline]
+	pub fn decode_base32_buf<B: Buf>(src: B, dest: &mut BytesMut) -> Result<(), DecodeError> {
+		decode_buf(&Base32BufCodec::<25, b'A', { b'2' - 26 }>(&TABLE_DECODER), src, dest)
+	}
+
+	/// Decodes the base32hex (with padding) remaining in `src`, appending the
+	/// decoded bytes to `dest` (see [`decode_base32_buf`])
+	#[inline]
+	pub fn decode_base32hex_buf<B: Buf>(src: B, dest: &mut BytesMut) -> Result<(), DecodeError> {
+		decode_buf(&Base32BufCodec::<9, b'0', { b'A' - 10 }>(&TABLE_DECODER_BASE32HEX), src, dest)
+	}
+
+	/// The const params are the same as for `_encode`, and the table is
+	/// the decoder for the same alphabet
+	struct Base32BufCodec<
+		const BREAKPOINT: u8,
+		const LOWER: u8,
+		const UPPER_ADJUSTED: u8
+	>(&'static [Option<u8>; TABLE_DECODER_LEN]);
+
+	impl<
+		const BREAKPOINT: u8,
+		const LOWER: u8,
+		const UPPER_ADJUSTED: u8
+	> BufCodec for Base32BufCodec<BREAKPOINT, LOWER, UPPER_ADJUSTED> {
+		type Error = DecodeError;
+		const BINARY_FRAME_LEN: usize = BINARY_FRAME_LEN;
+		const STRING_FRAME_LEN: usize = STRING_FRAME_LEN;
+
+		#[inline]
+		fn encoded_len(&self, bytes_len: usize) -> usize {
+			encoded_len(bytes_len)
+		}
+
+		#[inline]
+		unsafe fn encode_into(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) {
+			encode_into::<BREAKPOINT, LOWER, UPPER_ADJUSTED, _>(bytes, dest)
+		}
+
+		#[inline]
+		unsafe fn decode_frames(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) -> Result<(), DecodeError> {
+			decode_frames(bytes, self.0, dest)
+		}
+
+		#[inline]
+		fn decode_end(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			_decode(bytes, self.0)
+		}
+	}
+}
+
+#[inline]
+fn encoded_len(bytes_len: usize) -> usize {
+	// 5 bytes per group of 8 output chars, rounding up for the padded frame
+	((bytes_len + BINARY_FRAME_LEN - 1) / BINARY_FRAME_LEN) * STRING_FRAME_LEN
+}
+
 /// - `BREAKPOINT`: the gt comparison against this number to determin when to use
 ///   LOWER or UPPER_ADJUSTED
 /// - `LOWER`: the amount to add to a section when it is lt than `BREAKPOINT`.
@@ -39,24 +347,95 @@ fn _encode<
 	const LOWER: u8,
 	const UPPER_ADJUSTED: u8
 >(bytes: &[u8]) -> String {
-	// 5 bytes per group of 8 output chars
-	let frames = bytes.len() / BINARY_FRAME_LEN;
-	let remainder = bytes.len() % BINARY_FRAME_LEN;
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(bytes.len()));
 
-	let capacity = if remainder == 0 {
-		frames * STRING_FRAME_LEN
-	} else {
-		(frames + 1) * STRING_FRAME_LEN
+	unsafe { encode_into::<BREAKPOINT, LOWER, UPPER_ADJUSTED, _>(bytes, &mut dest) }
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes valid utf-8");
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+fn _encode_to_slice<
+	const BREAKPOINT: u8,
+	const LOWER: u8,
+	const UPPER_ADJUSTED: u8
+>(bytes: &[u8], dest: &mut [u8]) -> usize {
+	let len = encoded_len(bytes.len());
+	assert!(dest.len() >= len, "dest is too short for the encoded bytes");
+
+	unsafe {
+		// SAFETY: we checked dest has enough space
+		let mut dest = UnsafeBufWriteSlice::new(dest.as_mut_ptr(), len);
+		encode_into::<BREAKPOINT, LOWER, UPPER_ADJUSTED, _>(bytes, &mut dest);
+		dest.assert_is_full();
+	}
+
+	len
+}
+
+fn _encode_vectored<
+	const BREAKPOINT: u8,
+	const LOWER: u8,
+	const UPPER_ADJUSTED: u8,
+	V: VectoredInput + ?Sized
+>(input: &V) -> String {
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(input.total_len()));
+
+	// only the last run can have a partial frame, so
+	// padding is still only added at the very end
+	for_each_frame_run::<BINARY_FRAME_LEN, _, _>(input, |run| unsafe {
+		encode_into::<BREAKPOINT, LOWER, UPPER_ADJUSTED, _>(run, &mut dest);
+	});
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes valid utf-8");
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+fn _encode_parallel<
+	const BREAKPOINT: u8,
+	const LOWER: u8,
+	const UPPER_ADJUSTED: u8
+>(bytes: &[u8], threads: usize) -> String {
+	let Some(chunks) = parallel_chunks(bytes, BINARY_FRAME_LEN, threads) else {
+		return _encode::<BREAKPOINT, LOWER, UPPER_ADJUSTED>(bytes)
+	};
+
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(bytes.len()));
+
+	let result = unsafe {
+		// only the last chunk can have a partial frame, so
+		// padding is still only added at the very end
+		dest.write_parallel(&chunks, |chunk| encoded_len(chunk.len()), |chunk, dest| {
+			encode_into::<BREAKPOINT, LOWER, UPPER_ADJUSTED, _>(chunk, dest);
+			Ok::<_, ()>(())
+		})
 	};
+	debug_assert!(result.is_ok(), "encoding doesn't fail");
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes valid utf-8");
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// # Safety
+///
+/// `dest` must have `encoded_len(bytes.len())` bytes of capacity left
+unsafe fn encode_into<
+	const BREAKPOINT: u8,
+	const LOWER: u8,
+	const UPPER_ADJUSTED: u8,
+	W: UnsafeBufWrite
+>(bytes: &[u8], dest: &mut W) {
+	let frames = bytes.len() / BINARY_FRAME_LEN;
+	let remainder = bytes.len() % BINARY_FRAME_LEN;
 
 	let mut frames_iter = ChunkedSlice::<BINARY_FRAME_LEN>::new(bytes);
-	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
 
 	for _ in 0..frames {
-		unsafe {
-			let frame = frames_iter.next_frame_unchecked();
-			encode_frame::<BREAKPOINT, LOWER, UPPER_ADJUSTED>(frame, &mut dest);
-		}
+		let frame = frames_iter.next_frame_unchecked();
+		encode_frame::<BREAKPOINT, LOWER, UPPER_ADJUSTED, _>(frame, dest);
 	}
 
 	if remainder > 0 {
@@ -66,7 +445,7 @@ fn _encode<
 			2 => { 4 }
 			3 => { 3 }
 			4 => { 1 }
-			_ => unsafe {
+			_ => {
 				// SAFETY: `remainder` is calculated by mod 5, so it cannot be 5 or
 				// more. and we just checked in an if statement that `remainder` is
 				// greater than 0. therefore, `remainder` can only be 1, 2, 3, or 4,
@@ -75,51 +454,279 @@ fn _encode<
 			}
 		};
 
-		unsafe {
-			frames_iter.with_remainder_unchecked(|frame| {
-				encode_frame::<BREAKPOINT, LOWER, UPPER_ADJUSTED>(frame, &mut dest);
-				let ptr = dest.as_ptr().sub(padding_amount);
-				static PADDING: &[u8; 6] = b"======";
-				ptr::copy_nonoverlapping(PADDING as *const u8, ptr, padding_amount);
-			});
+		frames_iter.with_remainder_unchecked(|frame| {
+			encode_frame::<BREAKPOINT, LOWER, UPPER_ADJUSTED, _>(frame, dest);
+			let ptr = dest.as_ptr().sub(padding_amount);
+			static PADDING: &[u8; 6] = b"======";
+			ptr::copy_nonoverlapping(PADDING as *const u8, ptr, padding_amount);
+		});
+	}
+}
+
+/// Returns the amount of padding chars in the last frame, and the
+/// amount of bytes that frame decodes to
+#[inline]
+fn last_frame_info(last_frame: &[u8]) -> Result<(usize, usize), DecodeError> {
+	let padding = last_frame.iter().rev().take_while(|b| **b == b'=').count();
+
+	let bytes = match padding {
+		0 => { 5 }
+		1 => { 4 }
+		3 => { 3 }
+		4 => { 2 }
+		6 => { 1 }
+		_ => { return Err(DecodeError::InvalidPadding) }
+	};
+
+	Ok((padding, bytes))
+}
+
+/// Checks that the unused bits in the last char before the padding are all
+/// zero. Padding that isn't a valid amount is left for decoding to report,
+/// the same as an invalid char there.
+fn check_canonical(bytes: &[u8], table: &[Option<u8>; TABLE_DECODER_LEN]) -> Result<(), DecodeError> {
+	if bytes.len() % STRING_FRAME_LEN != 0 || bytes.is_empty() { return Ok(()) }
+
+	let padding = bytes.iter().rev().take_while(|b| **b == b'=').count();
+	let unused_bits = match padding {
+		// 2 chars (10 bits) for 1 byte
+		6 => { 2 }
+		// 4 chars (20 bits) for 2 bytes
+		4 => { 4 }
+		// 5 chars (25 bits) for 3 bytes
+		3 => { 1 }
+		// 7 chars (35 bits) for 4 bytes
+		1 => { 3 }
+		_ => { return Ok(()) }
+	};
+
+	let last_char = bytes[bytes.len() - padding - 1];
+	match table[last_char as usize] {
+		Some(value) if value & ((1 << unused_bits) - 1) != 0 => { Err(DecodeError::NonCanonical) }
+		_ => { Ok(()) }
+	}
+}
+
+fn _decode(bytes: &[u8], table: &[Option<u8>; TABLE_DECODER_LEN]) -> Result<Vec<u8>, DecodeError> {
+	if bytes.len() % STRING_FRAME_LEN != 0 { return Err(DecodeError::InvalidLength) }
+	if bytes.is_empty() { return Ok(Vec::new()) }
+
+	let (whole_frames, last_frame) = bytes.split_at(bytes.len() - STRING_FRAME_LEN);
+	let (padding, last_frame_bytes) = last_frame_info(last_frame)?;
+
+	let capacity = (whole_frames.len() / STRING_FRAME_LEN * BINARY_FRAME_LEN) + last_frame_bytes;
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe {
+		decode_frames(whole_frames, table, &mut dest)?;
+		decode_last_frame(last_frame, padding, last_frame_bytes, table, &mut dest)?;
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+fn _decode_to_slice(
+	bytes: &[u8],
+	dest: &mut [u8],
+	table: &[Option<u8>; TABLE_DECODER_LEN]
+) -> Result<usize, DecodeError> {
+	let len = decoded_len_base32(bytes)?;
+	assert!(dest.len() >= len, "dest is too short for the decoded bytes");
+	if bytes.is_empty() { return Ok(0) }
+
+	let (whole_frames, last_frame) = bytes.split_at(bytes.len() - STRING_FRAME_LEN);
+	let (padding, last_frame_bytes) = last_frame_info(last_frame)?;
+
+	unsafe {
+		// SAFETY: we checked dest has enough space
+		let mut dest = UnsafeBufWriteSlice::new(dest.as_mut_ptr(), len);
+		decode_frames(whole_frames, table, &mut dest)?;
+		decode_last_frame(last_frame, padding, last_frame_bytes, table, &mut dest)?;
+		dest.assert_is_full();
+	}
+
+	Ok(len)
+}
+
+fn _decode_in_place<'h>(
+	bytes: &'h mut [u8],
+	table: &[Option<u8>; TABLE_DECODER_LEN]
+) -> Result<&'h mut [u8], DecodeError> {
+	if bytes.len() % STRING_FRAME_LEN != 0 { return Err(DecodeError::InvalidLength) }
+	if bytes.is_empty() { return Ok(bytes) }
+
+	let frames = bytes.len() / STRING_FRAME_LEN;
+	let (padding, last_frame_bytes) = last_frame_info(&bytes[bytes.len() - STRING_FRAME_LEN..])?;
+	let len = ((frames - 1) * BINARY_FRAME_LEN) + last_frame_bytes;
+
+	let bytes_ptr = bytes.as_mut_ptr();
+
+	unsafe {
+		// SAFETY: frame `n` is decoded to index `n * 5`, which is always before
+		// the start of the next frame at `(n + 1) * 8`. Each frame is copied out
+		// before it's decoded, since the output of the first frame overlaps
+		// itself. All reads and writes go through `bytes_ptr`.
+		let mut dest = UnsafeBufWriteSlice::new(bytes_ptr, len);
+
+		for i in 0..frames - 1 {
+			let frame = ptr::read(bytes_ptr.add(i * STRING_FRAME_LEN) as *const [u8; STRING_FRAME_LEN]);
+			let groups = decode_groups(&frame, STRING_FRAME_LEN, table)?;
+			dest.write_bytes_const::<BINARY_FRAME_LEN>(&join_frame(&groups) as *const u8);
 		}
+
+		let last_frame = ptr::read(bytes_ptr.add((frames - 1) * STRING_FRAME_LEN) as *const [u8; STRING_FRAME_LEN]);
+		decode_last_frame(&last_frame, padding, last_frame_bytes, table, &mut dest)?;
+
+		dest.assert_is_full();
 	}
 
-	let vec = unsafe { dest.into_full_vec() };
-	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes valid utf-8");
-	unsafe { String::from_utf8_unchecked(vec) }
+	Ok(&mut bytes[..len])
 }
 
-unsafe fn encode_frame<
-	const BREAKPOINT: u8,
-	const LOWER: u8,
-	const UPPER_ADJUSTED: u8
->(frame: &[u8; BINARY_FRAME_LEN], dest: &mut UnsafeBufWriteGuard) {
-	let frame = frame as *const [u8] as *const u8;
+fn _validate(bytes: &[u8], table: &[Option<u8>; TABLE_DECODER_LEN]) -> Result<(), DecodeError> {
+	if bytes.len() % STRING_FRAME_LEN != 0 { return Err(DecodeError::InvalidLength) }
+	if bytes.is_empty() { return Ok(()) }
 
-	// keep first 5 bits from byte 0, leaving 3 bits left
-	let byte1 = *frame >> 3;
+	let (whole_frames, last_frame) = bytes.split_at(bytes.len() - STRING_FRAME_LEN);
+	let (padding, last_frame_bytes) = last_frame_info(last_frame)?;
 
-	// take remaining 3 from byte 0, then 2 from byte 1, leaving 6 bits left
-	let byte2 = ((*frame << 2) & 0b11100) | (*frame.add(1) >> 6);
+	unsafe {
+		// SAFETY: the discarding writer doesn't need any capacity
+		decode_frames(whole_frames, table, &mut UnsafeBufWriteDiscard)?;
+		decode_last_frame(last_frame, padding, last_frame_bytes, table, &mut UnsafeBufWriteDiscard)
+	}
+}
 
-	// take 5 in middle of byte 1, leaving 1 bit left
-	let byte3 = (*frame.add(1) >> 1) & 0b11111;
+fn _decode_parallel(
+	bytes: &[u8],
+	table: &[Option<u8>; TABLE_DECODER_LEN],
+	threads: usize
+) -> Result<Vec<u8>, DecodeError> {
+	if bytes.len() % STRING_FRAME_LEN != 0 { return Err(DecodeError::InvalidLength) }
+	if bytes.is_empty() { return Ok(Vec::new()) }
 
-	// take last bit from byte 1, then 4 from byte 2, leaving 4 bits left
-	let byte4 = ((*frame.add(1) << 4) & 0b10000) | (*frame.add(2) >> 4);
+	let (whole_frames, last_frame) = bytes.split_at(bytes.len() - STRING_FRAME_LEN);
+	let Some(chunks) = parallel_chunks(whole_frames, STRING_FRAME_LEN, threads) else {
+		return _decode(bytes, table)
+	};
 
-	// take last 4 bits from byte 2, then 1 from byte 3, leaving 7 bits left
-	let byte5 = ((*frame.add(2) << 1) & 0b11110) | (*frame.add(3) >> 7);
+	let (padding, last_frame_bytes) = last_frame_info(last_frame)?;
 
-	// take 5 from byte 3, leaving 2 bits left
-	let byte6 = (*frame.add(3) >> 2) & 0b11111;
+	let capacity = (whole_frames.len() / STRING_FRAME_LEN * BINARY_FRAME_LEN) + last_frame_bytes;
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe {
+		let output_len = |chunk: &[u8]| chunk.len() / STRING_FRAME_LEN * BINARY_FRAME_LEN;
+		dest.write_parallel(&chunks, output_len, |chunk, dest| decode_frames(chunk, table, dest))?;
+		decode_last_frame(last_frame, padding, last_frame_bytes, table, &mut dest)?;
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+/// # Safety
+///
+/// `bytes` must only contain whole frames, and `dest` must have enough
+/// capacity left to hold all of them decoded
+unsafe fn decode_frames<W: UnsafeBufWrite>(
+	bytes: &[u8],
+	table: &[Option<u8>; TABLE_DECODER_LEN],
+	dest: &mut W
+) -> Result<(), DecodeError> {
+	let frames = bytes.len() / STRING_FRAME_LEN;
+
+	let mut frames_iter = ChunkedSlice::<STRING_FRAME_LEN>::new(bytes);
+
+	for _ in 0..frames {
+		let frame = frames_iter.next_frame_unchecked();
+		let groups = decode_groups(frame, STRING_FRAME_LEN, table)?;
+		dest.write_bytes_const::<BINARY_FRAME_LEN>(&join_frame(&groups) as *const u8);
+	}
+
+	Ok(())
+}
+
+/// # Safety
+///
+/// `padding` and `last_frame_bytes` must be from [`last_frame_info`], and
+/// `dest` must have `last_frame_bytes` of capacity left
+unsafe fn decode_last_frame<W: UnsafeBufWrite>(
+	last_frame: &[u8],
+	padding: usize,
+	last_frame_bytes: usize,
+	table: &[Option<u8>; TABLE_DECODER_LEN],
+	dest: &mut W
+) -> Result<(), DecodeError> {
+	let last_frame = &*(last_frame as *const [u8] as *const [u8; STRING_FRAME_LEN]);
+	let groups = decode_groups(last_frame, STRING_FRAME_LEN - padding, table)?;
+	dest.write_bytes(&join_frame(&groups) as *const u8, last_frame_bytes);
+
+	Ok(())
+}
+
+/// Decodes the first `len` chars in the frame into their 5 bit values,
+/// leaving the rest as 0
+#[inline(always)]
+fn decode_groups(
+	frame: &[u8; STRING_FRAME_LEN],
+	len: usize,
+	table: &[Option<u8>; TABLE_DECODER_LEN]
+) -> Result<[u8; STRING_FRAME_LEN], DecodeError> {
+	let mut groups = [0u8; STRING_FRAME_LEN];
+
+	for (group, char) in groups.iter_mut().zip(&frame[..len]) {
+		// SAFETY: 0 <= n < 256 is always true for a u8, and the table is
+		// len 256, so this is safe
+		let Some(value) = (unsafe { *table.get_unchecked(*char as usize) }) else {
+			return Err(DecodeError::InvalidChar)
+		};
+		*group = value;
+	}
+
+	Ok(groups)
+}
+
+const fn crockford_decoder_table() -> [Option<u8>; TABLE_DECODER_LEN] {
+	let mut table = decoder_table(&TABLE_ENCODER_CROCKFORD);
 
-	// take remaining 2 bits from byte 3, then 3 bits from byte 4, leaving 5 bits left
-	let byte7 = ((*frame.add(3) << 3) & 0b11000) | (*frame.add(4) >> 5);
+	let mut i = 0;
+	while i < TABLE_ENCODER_LEN {
+		let char = TABLE_ENCODER_CROCKFORD[i];
+		table[char.to_ascii_lowercase() as usize] = Some(i as u8);
+		i += 1;
+	}
+
+	let aliases = [(b'I', 1), (b'L', 1), (b'O', 0)];
+	let mut i = 0;
+	while i < aliases.len() {
+		let (char, value) = aliases[i];
+		table[char as usize] = Some(value);
+		table[char.to_ascii_lowercase() as usize] = Some(value);
+		i += 1;
+	}
+
+	table
+}
 
-	// take remaining 5 bits
-	let byte8 = *frame.add(4) & 0b11111;
+const fn decoder_table(encoder: &[u8; TABLE_ENCODER_LEN]) -> [Option<u8>; TABLE_DECODER_LEN] {
+	let mut table = [None; TABLE_DECODER_LEN];
+
+	let mut i = 0;
+	while i < TABLE_ENCODER_LEN {
+		table[encoder[i] as usize] = Some(i as u8);
+		i += 1;
+	}
+
+	table
+}
+
+unsafe fn encode_frame<
+	const BREAKPOINT: u8,
+	const LOWER: u8,
+	const UPPER_ADJUSTED: u8,
+	W: UnsafeBufWrite
+>(frame: &[u8; BINARY_FRAME_LEN], dest: &mut W) {
+	let [byte1, byte2, byte3, byte4, byte5, byte6, byte7, byte8] = split_frame(frame);
 
 	let bytes = [
 		// multi cursor editing is great
@@ -136,6 +743,47 @@ unsafe fn encode_frame<
 	dest.write_bytes_const::<8>(&bytes as *const u8);
 }
 
+/// Splits a frame of 5 bytes into 8 groups of 5 bits, most significant bits
+/// first. Every returned value is in the range `0..32`.
+#[inline(always)]
+pub(crate) fn split_frame(frame: &[u8; BINARY_FRAME_LEN]) -> [u8; STRING_FRAME_LEN] {
+	let [f1, f2, f3, f4, f5] = *frame;
+
+	[
+		// keep first 5 bits from byte 0, leaving 3 bits left
+		f1 >> 3,
+		// take remaining 3 from byte 0, then 2 from byte 1, leaving 6 bits left
+		((f1 << 2) & 0b11100) | (f2 >> 6),
+		// take 5 in middle of byte 1, leaving 1 bit left
+		(f2 >> 1) & 0b11111,
+		// take last bit from byte 1, then 4 from byte 2, leaving 4 bits left
+		((f2 << 4) & 0b10000) | (f3 >> 4),
+		// take last 4 bits from byte 2, then 1 from byte 3, leaving 7 bits left
+		((f3 << 1) & 0b11110) | (f4 >> 7),
+		// take 5 from byte 3, leaving 2 bits left
+		(f4 >> 2) & 0b11111,
+		// take remaining 2 bits from byte 3, then 3 bits from byte 4, leaving 5 bits left
+		((f4 << 3) & 0b11000) | (f5 >> 5),
+		// take remaining 5 bits
+		f5 & 0b11111
+	]
+}
+
+/// Joins 8 groups of 5 bits back into a frame of 5 bytes; the inverse of
+/// [`split_frame`]. Only the lower 5 bits of every group are used.
+#[inline(always)]
+pub(crate) fn join_frame(groups: &[u8; STRING_FRAME_LEN]) -> [u8; BINARY_FRAME_LEN] {
+	let [g1, g2, g3, g4, g5, g6, g7, g8] = groups.map(|g| g & 0b11111);
+
+	[
+		(g1 << 3) | (g2 >> 2),
+		(g2 << 6) | (g3 << 1) | (g4 >> 4),
+		(g4 << 4) | (g5 >> 1),
+		(g5 << 7) | (g6 << 2) | (g7 >> 3),
+		(g7 << 5) | g8
+	]
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -155,6 +803,7 @@ mod tests {
 
 		for (bytes, encoded) in examples {
 			assert_eq!(encoded, encode_base32(bytes.as_bytes()));
+			assert_eq!(bytes.as_bytes(), decode_base32(encoded.as_bytes()).expect("rfc example decodes"));
 		}
 	}
 
@@ -172,6 +821,240 @@ mod tests {
 
 		for (bytes, encoded) in examples {
 			assert_eq!(encoded, encode_base32hex(bytes.as_bytes()));
+			assert_eq!(bytes.as_bytes(), decode_base32hex(encoded.as_bytes()).expect("rfc example decodes"));
+		}
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..50).chain([100, 1000, 100_000]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let encoded = encode_base32(&bytes);
+			let decoded = decode_base32(encoded.as_bytes())
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+			assert_eq!(decoded.len(), decoded.capacity());
+
+			let encoded = encode_base32hex(&bytes);
+			let decoded = decode_base32hex(encoded.as_bytes())
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+		}
+	}
+
+	#[test]
+	fn invalid() {
+		assert!(matches!(decode_base32(b"MZXW6"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base32(b"MZXW6YQ"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base32(b"MZXW6Y1="), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base32(b"mzxw6yq="), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base32(b"MZ=W6YQ="), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base32(b"MZXW6Y=="), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decode_base32(b"M======="), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decode_base32(b"MY======MZXW6YTB"), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn in_place() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..20).chain([1000, 1001]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let mut encoded = encode_base32(&bytes).into_bytes();
+			let decoded = decode_base32_in_place(&mut encoded)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+
+			let mut encoded = encode_base32hex(&bytes).into_bytes();
+			let decoded = decode_base32hex_in_place(&mut encoded)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+		}
+
+		assert!(matches!(decode_base32_in_place(&mut b"MZXW6".to_vec()), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base32_in_place(&mut b"MZXW6Y1=".to_vec()), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base32_in_place(&mut b"MZXW6Y==".to_vec()), Err(DecodeError::InvalidPadding)));
+	}
+
+	#[test]
+	fn to_slice() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..20).chain([1000, 1001]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let mut encoded = vec![0u8; encoded_len(len) + 3];
+			assert_eq!(encode_base32_to_slice(&bytes, &mut encoded), encoded_len(len));
+			assert_eq!(&encoded[..encoded_len(len)], encode_base32(&bytes).as_bytes());
+			assert_eq!(encoded[encoded_len(len)..], [0, 0, 0]);
+
+			let encoded = &encoded[..encoded_len(len)];
+			assert_eq!(decoded_len_base32(encoded).unwrap(), len);
+			let mut decoded = vec![0u8; len];
+			assert_eq!(decode_base32_to_slice(encoded, &mut decoded).unwrap(), len);
+			assert_eq!(decoded, bytes);
+
+			let mut encoded = vec![0u8; encoded_len(len)];
+			encode_base32hex_to_slice(&bytes, &mut encoded);
+			assert_eq!(encoded, encode_base32hex(&bytes).as_bytes());
+			assert_eq!(decode_base32hex_to_slice(&encoded, &mut decoded).unwrap(), len);
+			assert_eq!(decoded, bytes);
+		}
+
+		assert!(matches!(decoded_len_base32(b"MZXW6"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decoded_len_base32(b"MZXW6Y=="), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decode_base32_to_slice(b"MZXW6Y1=", &mut [0u8; 4]), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn validate() {
+		let mut rng = thread_rng();
+
+		for len in 0usize..50 {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+			assert!(validate_base32(encode_base32(&bytes).as_bytes()).is_ok());
+			assert!(validate_base32hex(encode_base32hex(&bytes).as_bytes()).is_ok());
+		}
+
+		assert!(matches!(validate_base32(b"MZXW6"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(validate_base32(b"MZXW6Y1="), Err(DecodeError::InvalidChar)));
+		assert!(matches!(validate_base32(b"MZXW6Y=="), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(validate_base32hex(b"MZXW6YQ="), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn parallel() {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 10, 5 * 1024 * 1024, 5 * 1024 * 1024 + 3] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			// always split as if there are 4 cores, so the parallel
+			// path gets exercised on single core machines too
+			let encoded = _encode_parallel::<25, b'A', { b'2' - 26 }>(&bytes, 4);
+			assert_eq!(encoded, encode_base32(&bytes));
+			assert_eq!(encoded, encode_base32_parallel(&bytes));
+			assert_eq!(encoded.len(), encoded.capacity());
+
+			let decoded = _decode_parallel(encoded.as_bytes(), &TABLE_DECODER, 4)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+			assert_eq!(bytes, decode_base32_parallel(encoded.as_bytes()).unwrap());
+			assert_eq!(decoded.len(), decoded.capacity());
+
+			let encoded = _encode_parallel::<9, b'0', { b'A' - 10 }>(&bytes, 4);
+			assert_eq!(encoded, encode_base32hex(&bytes));
+			assert_eq!(encoded, encode_base32hex_parallel(&bytes));
+			let decoded = _decode_parallel(encoded.as_bytes(), &TABLE_DECODER_BASE32HEX, 4)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+			assert_eq!(bytes, decode_base32hex_parallel(encoded.as_bytes()).unwrap());
+		}
+	}
+
+	#[test]
+	fn canonical() {
+		let mut rng = thread_rng();
+
+		for len in 0usize..30 {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let encoded = encode_base32(&bytes);
+			assert!(is_canonical_base32(encoded.as_bytes()));
+			assert_eq!(decode_base32_strict(encoded.as_bytes()).unwrap(), bytes);
+
+			let encoded = encode_base32hex(&bytes);
+			assert!(is_canonical_base32hex(encoded.as_bytes()));
+			assert_eq!(decode_base32hex_strict(encoded.as_bytes()).unwrap(), bytes);
+		}
+
+		// "f" is MY======, Y being 0b11000, with the last 2 bits unused
+		for non_canonical in ["MZ======", "MZXR====", "MZXW7===", "MZXW6YR="] {
+			let canonical_bytes = decode_base32(non_canonical.as_bytes()).unwrap();
+			assert!(!is_canonical_base32(non_canonical.as_bytes()), "{non_canonical}");
+			assert!(matches!(decode_base32_strict(non_canonical.as_bytes()), Err(DecodeError::NonCanonical)));
+			assert_ne!(encode_base32(&canonical_bytes), non_canonical);
+		}
+		assert!(matches!(decode_base32hex_strict(b"CP======"), Err(DecodeError::NonCanonical)));
+
+		// invalid input is not canonical, and strict decoding reports why
+		assert!(!is_canonical_base32(b"MY====="));
+		assert!(!is_canonical_base32(b"MZXW6Y=="));
+		assert!(!is_canonical_base32(b"my======"));
+		assert!(matches!(decode_base32_strict(b"MZXW6Y=="), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decode_base32_strict(b"MZXW6"), Err(DecodeError::InvalidLength)));
+	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn bytes() {
+		use crate::encoding_utils::bytes::test_utils::Chunked;
+		use ::bytes::BytesMut;
+
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 5, 9, 1000, 1001] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+			let encoded = encode_base32(&bytes);
+			let encoded_hex = encode_base32hex(&bytes);
+
+			for n in [1, 3, 7, 1000] {
+				let mut dest = BytesMut::from(&b"prefix"[..]);
+				encode_base32_buf(Chunked::new(&bytes, n), &mut dest);
+				assert_eq!(&dest[..6], b"prefix");
+				assert_eq!(&dest[6..], encoded.as_bytes());
+
+				let mut dest = BytesMut::new();
+				encode_base32hex_buf(Chunked::new(&bytes, n), &mut dest);
+				assert_eq!(&dest[..], encoded_hex.as_bytes());
+
+				let mut dest = BytesMut::from(&b"prefix"[..]);
+				decode_base32_buf(Chunked::new(encoded.as_bytes(), n), &mut dest).unwrap();
+				assert_eq!(&dest[..6], b"prefix");
+				assert_eq!(&dest[6..], &*bytes);
+
+				let mut dest = BytesMut::new();
+				decode_base32hex_buf(Chunked::new(encoded_hex.as_bytes(), n), &mut dest).unwrap();
+				assert_eq!(&dest[..], &*bytes);
+			}
+		}
+
+		let mut dest = BytesMut::from(&b"prefix"[..]);
+		assert!(matches!(decode_base32_buf(Chunked::new(b"MZXW6YTBOI=====", 3), &mut dest), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base32_buf(Chunked::new(b"MZXW6YTBO=======", 3), &mut dest), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decode_base32_buf(Chunked::new(b"MZXW1YTBOI======", 3), &mut dest), Err(DecodeError::InvalidChar)));
+		assert_eq!(&dest[..], b"prefix");
+	}
+
+	#[test]
+	fn vectored() {
+		use crate::encoding_utils::test_utils::random_slices;
+		use ::std::io::IoSlice;
+
+		let mut rng = thread_rng();
+
+		for len in (0usize..40).chain([1000]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let slices = random_slices(&bytes, &mut rng);
+			let io_slices = slices.iter().map(|s| IoSlice::new(s)).collect::<Vec<_>>();
+
+			let encoded = encode_base32_vectored(&*io_slices);
+			assert_eq!(encoded, encode_base32(&bytes));
+			assert_eq!(encoded.len(), encoded.capacity());
+			assert_eq!(encode_base32hex_vectored(&*slices), encode_base32hex(&bytes));
 		}
 	}
 }
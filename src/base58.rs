@@ -0,0 +1,431 @@
+//! Base58 and Base58Check encoding, using the Bitcoin alphabet by default,
+//! with the Ripple and Flickr alphabets also available.
+//!
+//! Unlike the other encodings in this crate, base58 doesn't have fixed size
+//! frames, since 58 isn't a power of 2. The whole input is treated as one
+//! big-endian big number, and converted to radix 58. Leading zero bytes don't
+//! affect the value of a number, so each of them is preserved by encoding it
+//! as one leading zero digit (`1` in the Bitcoin alphabet), and vice versa.
+
+use crate::encoding_utils::{ UnsafeBufWrite, UnsafeBufWriteGuard };
+use ::sha2::{ Digest, Sha256 };
+
+pub const TABLE_ENCODER_LEN: usize = 58;
+pub const TABLE_ENCODER_BITCOIN: [u8; TABLE_ENCODER_LEN] = *b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+pub const TABLE_ENCODER_RIPPLE: [u8; TABLE_ENCODER_LEN] = *b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz";
+pub const TABLE_ENCODER_FLICKR: [u8; TABLE_ENCODER_LEN] = *b"123456789abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ";
+
+pub const TABLE_DECODER_LEN: usize = 256;
+
+pub static ALPHABET_BITCOIN: Alphabet = Alphabet::new(&TABLE_ENCODER_BITCOIN);
+pub static ALPHABET_RIPPLE: Alphabet = Alphabet::new(&TABLE_ENCODER_RIPPLE);
+pub static ALPHABET_FLICKR: Alphabet = Alphabet::new(&TABLE_ENCODER_FLICKR);
+
+/// Length of the checksum appended by Base58Check, in bytes
+pub const CHECKSUM_LEN: usize = 4;
+
+/// 58^5, the largest power of 58 whose digits all fit in a u32. Encoding
+/// works with "limbs" of 5 base58 digits each, rather than one digit at a time
+const ENCODE_LIMB_BASE: u64 = 58 * 58 * 58 * 58 * 58;
+const ENCODE_LIMB_DIGITS: usize = 5;
+
+/// Bytes processed per round of encoding. Multiplying a limb (< 2^30) by
+/// 2^32 and adding the carry still fits comfortably in a u64
+const ENCODE_CHUNK_LEN: usize = 4;
+
+/// Digits processed per round of decoding. Multiplying a limb (< 2^32) by
+/// 58^5 (< 2^30) and adding the carry still fits comfortably in a u64
+const DECODE_CHUNK_LEN: usize = 5;
+
+/// An alphabet of 58 characters used to encode base58, with a decoding table
+/// generated from it at compile time.
+pub struct Alphabet {
+	encoder: [u8; TABLE_ENCODER_LEN],
+	decoder: [Option<u8>; TABLE_DECODER_LEN]
+}
+
+impl Alphabet {
+	/// Creates a new alphabet, panicking if it contains non-ASCII or
+	/// duplicate characters. This is a `const fn`, so if used to initialise
+	/// a `static` or `const`, any such panic happens at compile time.
+	pub const fn new(encoder: &[u8; TABLE_ENCODER_LEN]) -> Self {
+		let mut decoder = [None; TABLE_DECODER_LEN];
+
+		let mut i = 0;
+		while i < TABLE_ENCODER_LEN {
+			let char = encoder[i];
+			assert!(char.is_ascii(), "base58 alphabet contains a non-ASCII character");
+			assert!(decoder[char as usize].is_none(), "base58 alphabet contains a duplicate character");
+
+			decoder[char as usize] = Some(i as u8);
+			i += 1;
+		}
+
+		Self { encoder: *encoder, decoder }
+	}
+
+	/// Gets the characters of this alphabet, in order of the value they encode
+	#[inline]
+	pub const fn chars(&self) -> &[u8; TABLE_ENCODER_LEN] {
+		&self.encoder
+	}
+}
+
+/// Encodes the given bytes into a base58 [`String`], using the Bitcoin alphabet
+#[inline]
+pub fn encode_base58(bytes: &[u8]) -> String {
+	_encode(bytes, &ALPHABET_BITCOIN)
+}
+
+/// Encodes the given bytes into a base58 [`String`], using the provided alphabet
+#[inline]
+pub fn encode_base58_with(bytes: &[u8], alphabet: &Alphabet) -> String {
+	_encode(bytes, alphabet)
+}
+
+/// Decodes a base58 string encoded with the Bitcoin alphabet back into the
+/// source bytes
+#[inline]
+pub fn decode_base58(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode(bytes, &ALPHABET_BITCOIN)
+}
+
+/// Decodes a base58 string encoded with the provided alphabet back into the
+/// source bytes
+#[inline]
+pub fn decode_base58_with(bytes: &[u8], alphabet: &Alphabet) -> Result<Vec<u8>, DecodeError> {
+	_decode(bytes, alphabet)
+}
+
+/// Encodes the given bytes into a Base58Check [`String`], using the Bitcoin
+/// alphabet. Any version byte(s) should already be prepended to `bytes`.
+///
+/// Base58Check appends the first 4 bytes of the double SHA-256 hash of the
+/// data to the data, before encoding it as base58.
+#[inline]
+pub fn encode_base58check(bytes: &[u8]) -> String {
+	encode_base58check_with(bytes, &ALPHABET_BITCOIN)
+}
+
+/// Encodes the given bytes into a Base58Check [`String`], using the provided
+/// alphabet. See [`encode_base58check`] for more information.
+pub fn encode_base58check_with(bytes: &[u8], alphabet: &Alphabet) -> String {
+	let mut vec = Vec::with_capacity(bytes.len() + CHECKSUM_LEN);
+	vec.extend_from_slice(bytes);
+	vec.extend_from_slice(&checksum(bytes));
+
+	_encode(&vec, alphabet)
+}
+
+/// Decodes a Base58Check string encoded with the Bitcoin alphabet, verifies
+/// its checksum, and returns the data (including any version bytes) without
+/// the checksum
+#[inline]
+pub fn decode_base58check(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	decode_base58check_with(bytes, &ALPHABET_BITCOIN)
+}
+
+/// Decodes a Base58Check string encoded with the provided alphabet. See
+/// [`decode_base58check`] for more information.
+pub fn decode_base58check_with(bytes: &[u8], alphabet: &Alphabet) -> Result<Vec<u8>, DecodeError> {
+	let mut decoded = _decode(bytes, alphabet)?;
+
+	let Some(data_len) = decoded.len().checked_sub(CHECKSUM_LEN) else {
+		return Err(DecodeError::InvalidLength)
+	};
+
+	let (data, decoded_checksum) = decoded.split_at(data_len);
+	if checksum(data) != decoded_checksum {
+		return Err(DecodeError::InvalidChecksum)
+	}
+
+	decoded.truncate(data_len);
+	Ok(decoded)
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError {
+	#[error("invalid length")]
+	InvalidLength,
+	#[error("invalid character")]
+	InvalidChar,
+	#[error("invalid checksum")]
+	InvalidChecksum
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+	let hash = Sha256::digest(Sha256::digest(bytes));
+	[hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn _encode(bytes: &[u8], alphabet: &Alphabet) -> String {
+	let zero_char = alphabet.encoder[0];
+
+	let zeros = bytes.iter().take_while(|b| **b == 0).count();
+	let rest = &bytes[zeros..];
+
+	// a byte is about 1.37 base58 digits (log 256 / log 58)
+	let limbs_estimate = (rest.len() * 138 / 100) / ENCODE_LIMB_DIGITS + 1;
+	// little endian, each limb is 5 base58 digits (so 0 <= n < 58^5)
+	let mut limbs = Vec::<u32>::with_capacity(limbs_estimate);
+
+	// make the first chunk the uneven one, so the rest of them can be whole
+	let mut chunk_len = match rest.len() % ENCODE_CHUNK_LEN {
+		0 => { ENCODE_CHUNK_LEN }
+		n => { n }
+	};
+	let mut rest_iter = rest;
+
+	while !rest_iter.is_empty() {
+		let (chunk, next) = rest_iter.split_at(chunk_len);
+		rest_iter = next;
+
+		let multiplier = 1u64 << (chunk_len * 8);
+		let mut carry = chunk.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+
+		for limb in limbs.iter_mut() {
+			let int = (*limb as u64) * multiplier + carry;
+			*limb = (int % ENCODE_LIMB_BASE) as u32;
+			carry = int / ENCODE_LIMB_BASE;
+		}
+
+		while carry > 0 {
+			limbs.push((carry % ENCODE_LIMB_BASE) as u32);
+			carry /= ENCODE_LIMB_BASE;
+		}
+
+		chunk_len = ENCODE_CHUNK_LEN;
+	}
+
+	// rest starts with a non-zero byte (if it isn't empty), so the most
+	// significant limb is never zero, and only that one needs trimming
+	let (top_digits, top_len) = match limbs.last() {
+		Some(top) => {
+			let digits = limb_to_digits(*top, alphabet);
+			let len = ENCODE_LIMB_DIGITS - digits.iter().take_while(|d| **d == zero_char).count();
+			(digits, len)
+		}
+		None => { ([0u8; ENCODE_LIMB_DIGITS], 0) }
+	};
+
+	let full_limbs = limbs.len().saturating_sub(1);
+	let capacity = zeros + top_len + (full_limbs * ENCODE_LIMB_DIGITS);
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe {
+		// SAFETY: capacity was calculated above to include exactly the leading
+		// zeros, the trimmed top limb, and all the other limbs
+		for _ in 0..zeros {
+			dest.write_bytes_const::<1>(&zero_char);
+		}
+
+		let top_ptr = &top_digits as *const u8;
+		dest.write_bytes(top_ptr.add(ENCODE_LIMB_DIGITS - top_len), top_len);
+
+		for limb in limbs.iter().rev().skip(1) {
+			let digits = limb_to_digits(*limb, alphabet);
+			dest.write_bytes_const::<ENCODE_LIMB_DIGITS>(&digits as *const u8);
+		}
+	}
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+
+	// SAFETY: we only are pushing in chars in the alphabet, which are all ASCII chars
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Converts one limb into its 5 digits, most significant first
+#[inline]
+fn limb_to_digits(mut limb: u32, alphabet: &Alphabet) -> [u8; ENCODE_LIMB_DIGITS] {
+	let mut digits = [0u8; ENCODE_LIMB_DIGITS];
+
+	for digit in digits.iter_mut().rev() {
+		// SAFETY: mod TABLE_ENCODER_LEN keeps this in bounds of the encoder table
+		*digit = unsafe { *alphabet.encoder.get_unchecked(limb as usize % TABLE_ENCODER_LEN) };
+		limb /= TABLE_ENCODER_LEN as u32;
+	}
+
+	digits
+}
+
+fn _decode(bytes: &[u8], alphabet: &Alphabet) -> Result<Vec<u8>, DecodeError> {
+	let zero_char = alphabet.encoder[0];
+
+	let zeros = bytes.iter().take_while(|b| **b == zero_char).count();
+	let rest = &bytes[zeros..];
+
+	// a base58 digit is about 0.73 bytes (log 58 / log 256)
+	let limbs_estimate = (rest.len() * 733 / 1000) / 4 + 1;
+	// little endian, base 2^32
+	let mut limbs = Vec::<u32>::with_capacity(limbs_estimate);
+
+	let mut chunk_len = match rest.len() % DECODE_CHUNK_LEN {
+		0 => { DECODE_CHUNK_LEN }
+		n => { n }
+	};
+	let mut rest_iter = rest;
+
+	while !rest_iter.is_empty() {
+		let (chunk, next) = rest_iter.split_at(chunk_len);
+		rest_iter = next;
+
+		let mut multiplier = 1u64;
+		let mut carry = 0u64;
+
+		for char in chunk {
+			// SAFETY: 0 <= n < 256 is always true for a u8, and the decoder
+			// table is len 256, so this is safe
+			let Some(digit) = (unsafe { *alphabet.decoder.get_unchecked(*char as usize) }) else {
+				return Err(DecodeError::InvalidChar)
+			};
+
+			multiplier *= TABLE_ENCODER_LEN as u64;
+			carry = carry * TABLE_ENCODER_LEN as u64 + digit as u64;
+		}
+
+		for limb in limbs.iter_mut() {
+			let int = (*limb as u64) * multiplier + carry;
+			*limb = int as u32;
+			carry = int >> 32;
+		}
+
+		while carry > 0 {
+			limbs.push(carry as u32);
+			carry >>= 32;
+		}
+
+		chunk_len = DECODE_CHUNK_LEN;
+	}
+
+	// same as encoding, the most significant limb is the only one to trim
+	let (top_bytes, top_len) = match limbs.last() {
+		Some(top) => {
+			let bytes = top.to_be_bytes();
+			let len = 4 - bytes.iter().take_while(|b| **b == 0).count();
+			(bytes, len)
+		}
+		None => { ([0u8; 4], 0) }
+	};
+
+	let full_limbs = limbs.len().saturating_sub(1);
+	let capacity = zeros + top_len + (full_limbs * 4);
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe {
+		// SAFETY: capacity was calculated above to include exactly the leading
+		// zeros, the trimmed top limb, and all the other limbs
+		for _ in 0..zeros {
+			dest.write_bytes_const::<1>(&0);
+		}
+
+		let top_ptr = &top_bytes as *const u8;
+		dest.write_bytes(top_ptr.add(4 - top_len), top_len);
+
+		for limb in limbs.iter().rev().skip(1) {
+			dest.write_bytes_const::<4>(&limb.to_be_bytes() as *const u8);
+		}
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn bitcoin_core_vectors() {
+		// from bitcoin core's `base58_encode_decode.json`
+		let examples = [
+			("", ""),
+			("61", "2g"),
+			("626262", "a3gV"),
+			("636363", "aPEr"),
+			("73696d706c792061206c6f6e6720737472696e67", "2cFupjhnEsSn59qHXstmK2ffpLv2"),
+			("00eb15231dfceb60925886b67d065299925915aeb172c06647", "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L"),
+			("516b6fcd0f", "ABnLTmg"),
+			("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+			("572e4794", "3EFU7m"),
+			("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+			("10c8511e", "Rt5zm"),
+			("00000000000000000000", "1111111111"),
+			(
+				"000111d38e5fc9071ffcd20b4a763cc9ae4f252bb4e48fd66a835e252ada93ff480d6dd43dc62a641155a5",
+				"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz"
+			)
+		];
+
+		for (hex, encoded) in examples {
+			let bytes = ::hex::decode(hex).unwrap();
+			assert_eq!(encoded, encode_base58(&bytes));
+			assert_eq!(bytes, decode_base58(encoded.as_bytes()).expect("provided test case decodes properly"));
+		}
+	}
+
+	#[test]
+	fn other_alphabets() {
+		let examples = [
+			(&ALPHABET_BITCOIN, &b"\0Hello World!"[..], "12NEpo7TZRRrLZSi2U"),
+			(&ALPHABET_RIPPLE, b"\0\0Hello World!", "rrp4NFofTZRRiLZS5p7"),
+			(&ALPHABET_FLICKR, b"\0Hello World!", "12nePN7syqqRkyrH2t")
+		];
+
+		for (alphabet, bytes, encoded) in examples {
+			assert_eq!(encoded, encode_base58_with(bytes, alphabet));
+			assert_eq!(bytes, decode_base58_with(encoded.as_bytes(), alphabet).unwrap());
+		}
+	}
+
+	#[test]
+	fn base58check() {
+		let address = "1PMycacnJaSqwwJqjawXBErnLsZ7RkXUAs";
+		let payload = ::hex::decode("00f54a5851e9372b87810a8e60cdd2e7cfd80b6e31").unwrap();
+
+		assert_eq!(address, encode_base58check(&payload));
+		assert_eq!(payload, decode_base58check(address.as_bytes()).unwrap());
+
+		// last char changed
+		let err = decode_base58check(b"1PMycacnJaSqwwJqjawXBErnLsZ7RkXUAt").unwrap_err();
+		assert!(matches!(err, DecodeError::InvalidChecksum));
+
+		// too short to contain a checksum
+		let err = decode_base58check(b"2g").unwrap_err();
+		assert!(matches!(err, DecodeError::InvalidLength));
+	}
+
+	#[test]
+	fn invalid_chars() {
+		for invalid in ["0", "O", "I", "l", "abc+", "a b"] {
+			let err = decode_base58(invalid.as_bytes()).unwrap_err();
+			assert!(matches!(err, DecodeError::InvalidChar));
+		}
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..64).chain([100, 500, 1000]) {
+			for _ in 0usize..5 {
+				let mut original_input = vec![0u8; len];
+				rng.fill(&mut *original_input);
+
+				// sometimes exercise leading zeros
+				let zeros = rng.gen_range(0..=len.min(4));
+				original_input[..zeros].fill(0);
+
+				let encoded = encode_base58(&original_input);
+				let decoded = decode_base58(encoded.as_bytes())
+					.expect("can round trip decode just encoded data");
+				assert_eq!(original_input, decoded);
+
+				// this is enforced by debug_assert! in the code, so this already
+				// is validated if tests are run in debug, but still,
+				assert_eq!(encoded.len(), encoded.capacity());
+				assert_eq!(decoded.len(), decoded.capacity());
+			}
+		}
+	}
+}
@@ -0,0 +1,1447 @@
+//! Base64 encoding and decoding, as specified in [RFC 4648], as well as the
+//! [forgiving-base64 decode] algorithm from the WHATWG infra standard (used by
+//! browsers for `atob` and `data:` URLs).
+//!
+//! Besides the standard and URL safe variants, other systems use base64 with
+//! their own alphabets and padding rules. All of these are described by a
+//! [`Config`] (an [`Alphabet`], a [`Padding`] policy, and whether to reject
+//! non canonical encodings), which can be passed to [`encode_base64_with`]
+//! and [`decode_base64_with`]. Presets are available for the common ones, and
+//! custom alphabets can be built with [`Alphabet::new`].
+//!
+//! [RFC 4648]: https://datatracker.ietf.org/doc/html/rfc4648#section-4
+//! [forgiving-base64 decode]: https://infra.spec.whatwg.org/#forgiving-base64-decode
+
+use crate::encoding_utils::{ available_threads, for_each_frame_run, parallel_chunks, ChunkedSlice, UnsafeBufWrite, UnsafeBufWriteDiscard, UnsafeBufWriteGuard, UnsafeBufWriteSlice };
+use ::std::ptr;
+
+pub use crate::encoding_utils::VectoredInput;
+
+pub const TABLE_ENCODER_LEN: usize = 64;
+pub const TABLE_ENCODER: [u8; TABLE_ENCODER_LEN] = *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+pub const TABLE_ENCODER_URL: [u8; TABLE_ENCODER_LEN] = *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+/// Alphabet used by bcrypt hashes
+pub const TABLE_ENCODER_BCRYPT: [u8; TABLE_ENCODER_LEN] = *b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+/// Alphabet used by crypt(3) (and so by DES, MD5, and SHA crypt hashes)
+pub const TABLE_ENCODER_CRYPT: [u8; TABLE_ENCODER_LEN] = *b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+/// Alphabet used by IMAP's modified UTF-7 mailbox names ([RFC 3501])
+///
+/// [RFC 3501]: https://datatracker.ietf.org/doc/html/rfc3501#section-5.1.3
+pub const TABLE_ENCODER_IMAP: [u8; TABLE_ENCODER_LEN] = *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+,";
+
+pub const TABLE_DECODER_LEN: usize = 256;
+pub static TABLE_DECODER: [Option<u8>; TABLE_DECODER_LEN] = decoder_table(&TABLE_ENCODER);
+pub static TABLE_DECODER_URL: [Option<u8>; TABLE_DECODER_LEN] = decoder_table(&TABLE_ENCODER_URL);
+
+pub const BINARY_FRAME_LEN: usize = 3;
+pub const STRING_FRAME_LEN: usize = 4;
+
+/// The 64 chars used to encode, and the table to decode them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+	encoder: [u8; TABLE_ENCODER_LEN],
+	decoder: [Option<u8>; TABLE_DECODER_LEN]
+}
+
+impl Alphabet {
+	/// `A-Za-z0-9+/`
+	pub const STANDARD: Alphabet = Alphabet::new(&TABLE_ENCODER);
+	/// `A-Za-z0-9-_`
+	pub const URL_SAFE: Alphabet = Alphabet::new(&TABLE_ENCODER_URL);
+	/// `./A-Za-z0-9`
+	pub const BCRYPT: Alphabet = Alphabet::new(&TABLE_ENCODER_BCRYPT);
+	/// `./0-9A-Za-z`
+	pub const CRYPT: Alphabet = Alphabet::new(&TABLE_ENCODER_CRYPT);
+	/// `A-Za-z0-9+,`
+	pub const IMAP: Alphabet = Alphabet::new(&TABLE_ENCODER_IMAP);
+
+	/// Creates an alphabet from 64 chars, where the char at index n encodes
+	/// the value n.
+	///
+	/// # Panics
+	///
+	/// Panics if any char is not ASCII, is `=` (reserved for padding), or
+	/// appears more than once. See [`try_new`](Alphabet::try_new) for a non
+	/// panicking version.
+	pub const fn new(chars: &[u8; TABLE_ENCODER_LEN]) -> Self {
+		match Self::try_new(chars) {
+			Some(alphabet) => { alphabet }
+			None => { panic!("alphabet chars must be unique, ASCII, and not `=`") }
+		}
+	}
+
+	/// Creates an alphabet from 64 chars, returning `None` if any char is
+	/// not ASCII, is `=`, or appears more than once
+	pub const fn try_new(chars: &[u8; TABLE_ENCODER_LEN]) -> Option<Self> {
+		let mut i = 0;
+		while i < TABLE_ENCODER_LEN {
+			let char = chars[i];
+			if !char.is_ascii() || char == b'=' { return None }
+
+			let mut j = i + 1;
+			while j < TABLE_ENCODER_LEN {
+				if chars[j] == char { return None }
+				j += 1;
+			}
+
+			i += 1;
+		}
+
+		Some(Self { encoder: *chars, decoder: decoder_table(chars) })
+	}
+
+	#[inline]
+	pub const fn chars(&self) -> &[u8; TABLE_ENCODER_LEN] {
+		&self.encoder
+	}
+}
+
+/// Whether padding (`=`) is written when encoding, and accepted when decoding
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding {
+	/// Padding is written, and decoding requires it
+	Required,
+	/// Padding is written, and decoding accepts input both with and without
+	/// it (but if there is any, it must be correct)
+	Optional,
+	/// Padding is not written, and decoding rejects it
+	Forbidden
+}
+
+/// A base64 variant: alphabet, padding policy, and whether to reject non
+/// canonical input.
+///
+/// Base64 doesn't always fill the last char completely (1 trailing byte is
+/// encoded in 2 chars, which is 12 bits, so 4 bits are unused). Encoding
+/// always leaves unused bits as 0, but decoding ignores them by default, so
+/// more than one string can decode to the same bytes. With
+/// [`canonical`](Config::canonical) set, input with any unused bits set is
+/// rejected with [`DecodeError::NonCanonical`], so there's only one accepted
+/// encoding for any given bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+	alphabet: Alphabet,
+	padding: Padding,
+	canonical: bool
+}
+
+impl Config {
+	/// Standard base64 ([RFC 4648 section 4]), with required padding
+	///
+	/// [RFC 4648 section 4]: https://datatracker.ietf.org/doc/html/rfc4648#section-4
+	pub const STANDARD: Config = Config::new(Alphabet::STANDARD);
+	/// Standard base64 without padding
+	pub const STANDARD_NO_PAD: Config = Config::new(Alphabet::STANDARD).padding(Padding::Forbidden);
+	/// URL and filename safe base64 ([RFC 4648 section 5]), with required padding
+	///
+	/// [RFC 4648 section 5]: https://datatracker.ietf.org/doc/html/rfc4648#section-5
+	pub const URL_SAFE: Config = Config::new(Alphabet::URL_SAFE);
+	/// URL and filename safe base64 without padding, as used by JWTs among others
+	pub const URL_SAFE_NO_PAD: Config = Config::new(Alphabet::URL_SAFE).padding(Padding::Forbidden);
+	/// bcrypt's base64, without padding. Only the alphabet is different from
+	/// standard base64, so this can be used for the salt and hash parts of
+	/// bcrypt hashes.
+	pub const BCRYPT: Config = Config::new(Alphabet::BCRYPT).padding(Padding::Forbidden);
+	/// crypt(3)'s alphabet, without padding. Note that some crypt schemes
+	/// (ex. MD5 and SHA crypt) also shuffle their bytes around and pack bits
+	/// in a different order before encoding; that is not done by this config.
+	pub const CRYPT: Config = Config::new(Alphabet::CRYPT).padding(Padding::Forbidden);
+	/// Modified base64 from IMAP's mailbox name encoding, without padding.
+	/// This only handles the base64 part, not the UTF-16 and shifting in
+	/// and out with `&` and `-`.
+	pub const IMAP: Config = Config::new(Alphabet::IMAP).padding(Padding::Forbidden);
+
+	/// Creates a config with the given alphabet, with padding required,
+	/// and unused bits not checked
+	#[inline]
+	pub const fn new(alphabet: Alphabet) -> Self {
+		Self { alphabet, padding: Padding::Required, canonical: false }
+	}
+
+	#[inline]
+	pub const fn padding(mut self, padding: Padding) -> Self {
+		self.padding = padding;
+		self
+	}
+
+	/// Sets whether decoding rejects input with any unused bits set
+	#[inline]
+	pub const fn canonical(mut self, canonical: bool) -> Self {
+		self.canonical = canonical;
+		self
+	}
+
+	#[inline]
+	pub const fn alphabet(&self) -> &Alphabet {
+		&self.alphabet
+	}
+
+	#[inline]
+	pub const fn padding_policy(&self) -> Padding {
+		self.padding
+	}
+
+	#[inline]
+	pub const fn is_canonical(&self) -> bool {
+		self.canonical
+	}
+
+	/// Whether encoding writes padding
+	#[inline]
+	const fn encode_padding(&self) -> bool {
+		!matches!(self.padding, Padding::Forbidden)
+	}
+}
+
+/// Encodes the given bytes into a base64 [`String`], with padding
+#[inline]
+pub fn encode_base64(bytes: &[u8]) -> String {
+	encode_base64_with(bytes, &Config::STANDARD)
+}
+
+/// Encodes the given bytes into a base64 [`String`] using the URL and filename
+/// safe alphabet (`-` and `_` instead of `+` and `/`), with padding
+#[inline]
+pub fn encode_base64url(bytes: &[u8]) -> String {
+	encode_base64_with(bytes, &Config::URL_SAFE)
+}
+
+/// Encodes the given bytes into a base64 [`String`] using the given config
+pub fn encode_base64_with(bytes: &[u8], config: &Config) -> String {
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(bytes.len(), config.encode_padding()));
+
+	unsafe { encode_into(bytes, config, &mut dest) }
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes valid utf-8");
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Encodes the given bytes into base64 (with padding), writing it to the start
+/// of `dest` instead of allocating, and returning the amount of bytes written.
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than the encoded length (`bytes.len()` rounded
+/// up to a multiple of 3, divided by 3, times 4).
+#[inline]
+pub fn encode_base64_to_slice(bytes: &[u8], dest: &mut [u8]) -> usize {
+	encode_base64_to_slice_with(bytes, dest, &Config::STANDARD)
+}
+
+/// Encodes the given bytes into base64 using the given config, writing it to
+/// the start of `dest` instead of allocating, and returning the amount of
+/// bytes written.
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than the encoded length.
+pub fn encode_base64_to_slice_with(bytes: &[u8], dest: &mut [u8], config: &Config) -> usize {
+	let len = encoded_len(bytes.len(), config.encode_padding());
+	assert!(dest.len() >= len, "dest is too short for the encoded bytes");
+
+	unsafe {
+		// SAFETY: we checked dest has enough space
+		let mut dest = UnsafeBufWriteSlice::new(dest.as_mut_ptr(), len);
+		encode_into(bytes, config, &mut dest);
+		dest.assert_is_full();
+	}
+
+	len
+}
+
+/// Encodes the concatenation of several slices (ex. `&[IoSlice]`) into one
+/// base64 [`String`] using the given config, without joining them first
+pub fn encode_base64_vectored_with<V: VectoredInput + ?Sized>(input: &V, config: &Config) -> String {
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(input.total_len(), config.encode_padding()));
+
+	// only the last run can have a partial frame, so
+	// padding is still only added at the very end
+	for_each_frame_run::<BINARY_FRAME_LEN, _, _>(input, |run| unsafe { encode_into(run, config, &mut dest) });
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes valid utf-8");
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Encodes the concatenation of several slices (ex. `&[IoSlice]`) into one
+/// base64 [`String`], with padding
+#[inline]
+pub fn encode_base64_vectored<V: VectoredInput + ?Sized>(input: &V) -> String {
+	encode_base64_vectored_with(input, &Config::STANDARD)
+}
+
+/// Encodes the given bytes into a base64 [`String`] (with padding), splitting
+/// the work across multiple threads for large inputs. Smaller inputs are
+/// encoded on the calling thread, the same as [`encode_base64`].
+#[inline]
+pub fn encode_base64_parallel(bytes: &[u8]) -> String {
+	encode_base64_parallel_with(bytes, &Config::STANDARD)
+}
+
+/// Encodes the given bytes into a base64 [`String`] using the given config,
+/// splitting the work across multiple threads for large inputs. Smaller
+/// inputs are encoded on the calling thread, the same as
+/// [`encode_base64_with`].
+#[inline]
+pub fn encode_base64_parallel_with(bytes: &[u8], config: &Config) -> String {
+	_encode_parallel(bytes, config, available_threads())
+}
+
+/// Decodes a base64 string (with padding) back into the source bytes
+#[inline]
+pub fn decode_base64(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	decode_base64_with(bytes, &Config::STANDARD)
+}
+
+/// Decodes a base64 string using the URL and filename safe alphabet (with
+/// padding) back into the source bytes
+#[inline]
+pub fn decode_base64url(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	decode_base64_with(bytes, &Config::URL_SAFE)
+}
+
+/// Decodes a base64 string using the given config back into the source bytes
+pub fn decode_base64_with(bytes: &[u8], config: &Config) -> Result<Vec<u8>, DecodeError> {
+	_decode_unpadded(strip_padding(bytes, config)?, &config.alphabet.decoder, config.canonical)
+}
+
+/// Decodes a base64 string (with padding) back into the source bytes,
+/// splitting the work across multiple threads for large inputs. Smaller
+/// inputs are decoded on the calling thread, the same as [`decode_base64`].
+#[inline]
+pub fn decode_base64_parallel(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	decode_base64_parallel_with(bytes, &Config::STANDARD)
+}
+
+/// Decodes a base64 string using the given config back into the source
+/// bytes, splitting the work across multiple threads for large inputs.
+/// Smaller inputs are decoded on the calling thread, the same as
+/// [`decode_base64_with`].
+#[inline]
+pub fn decode_base64_parallel_with(bytes: &[u8], config: &Config) -> Result<Vec<u8>, DecodeError> {
+	_decode_parallel(bytes, config, available_threads())
+}
+
+/// Decodes a base64 string (with padding) in place, writing the decoded bytes
+/// over the start of the input buffer, and returning the part of the buffer
+/// they were written to.
+///
+/// If an error is returned, the contents of the buffer are unspecified (part
+/// of it may have already been overwritten).
+#[inline]
+pub fn decode_base64_in_place(bytes: &mut [u8]) -> Result<&mut [u8], DecodeError> {
+	decode_base64_in_place_with(bytes, &Config::STANDARD)
+}
+
+/// Decodes a base64 string using the URL and filename safe alphabet (with
+/// padding) in place, writing the decoded bytes over the start of the input
+/// buffer, and returning the part of the buffer they were written to.
+///
+/// If an error is returned, the contents of the buffer are unspecified (part
+/// of it may have already been overwritten).
+#[inline]
+pub fn decode_base64url_in_place(bytes: &mut [u8]) -> Result<&mut [u8], DecodeError> {
+	decode_base64_in_place_with(bytes, &Config::URL_SAFE)
+}
+
+/// Decodes a base64 string using the given config in place, writing the
+/// decoded bytes over the start of the input buffer, and returning the part
+/// of the buffer they were written to.
+///
+/// If an error is returned, the contents of the buffer are unspecified (part
+/// of it may have already been overwritten).
+pub fn decode_base64_in_place_with<'h>(bytes: &'h mut [u8], config: &Config) -> Result<&'h mut [u8], DecodeError> {
+	let (table, canonical) = (&config.alphabet.decoder, config.canonical);
+	let unpadded_len = strip_padding(bytes, config)?.len();
+
+	let frames = unpadded_len / STRING_FRAME_LEN;
+	let remainder = unpadded_len % STRING_FRAME_LEN;
+	if remainder == 1 { return Err(DecodeError::InvalidLength) }
+	let len = (frames * BINARY_FRAME_LEN) + remainder.saturating_sub(1);
+
+	let bytes_ptr = bytes.as_mut_ptr();
+
+	unsafe {
+		// SAFETY: frame `n` is decoded to index `n * 3`, which is always before
+		// the start of the next frame at `(n + 1) * 4`. Each frame is copied out
+		// before it's decoded, since the output of the first frame overlaps
+		// itself. All reads and writes go through `bytes_ptr`.
+		let mut dest = UnsafeBufWriteSlice::new(bytes_ptr, len);
+
+		for i in 0..frames {
+			let frame = ptr::read(bytes_ptr.add(i * STRING_FRAME_LEN) as *const [u8; STRING_FRAME_LEN]);
+			let groups = decode_groups(&frame, STRING_FRAME_LEN, table)?;
+			dest.write_bytes_const::<BINARY_FRAME_LEN>(&join_frame(&groups) as *const u8);
+		}
+
+		let mut last_frame = [0u8; STRING_FRAME_LEN];
+		ptr::copy_nonoverlapping(bytes_ptr.add(frames * STRING_FRAME_LEN), last_frame.as_mut_ptr(), remainder);
+		decode_remainder(ChunkedSlice::new(&last_frame[..remainder]), remainder, table, canonical, &mut dest)?;
+
+		dest.assert_is_full();
+	}
+
+	Ok(&mut bytes[..len])
+}
+
+/// Amount of bytes the given base64 string decodes to with the given config.
+/// Only the length and padding of the input are checked, not its chars, so
+/// this doesn't go through the whole input.
+pub fn decoded_len_base64(bytes: &[u8], config: &Config) -> Result<usize, DecodeError> {
+	let unpadded_len = strip_padding(bytes, config)?.len();
+
+	let remainder = unpadded_len % STRING_FRAME_LEN;
+	if remainder == 1 { return Err(DecodeError::InvalidLength) }
+
+	Ok((unpadded_len / STRING_FRAME_LEN * BINARY_FRAME_LEN) + remainder.saturating_sub(1))
+}
+
+/// Decodes a base64 string (with padding), writing the decoded bytes to the
+/// start of `dest` instead of allocating, and returning the amount of bytes
+/// written.
+///
+/// If an error is returned, the contents of `dest` are unspecified (part of it
+/// may have already been written to).
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than [`decoded_len_base64`].
+#[inline]
+pub fn decode_base64_to_slice(bytes: &[u8], dest: &mut [u8]) -> Result<usize, DecodeError> {
+	decode_base64_to_slice_with(bytes, dest, &Config::STANDARD)
+}
+
+/// Decodes a base64 string using the given config, writing the decoded bytes
+/// to the start of `dest` instead of allocating, and returning the amount of
+/// bytes written.
+///
+/// If an error is returned, the contents of `dest` are unspecified (part of it
+/// may have already been written to).
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than [`decoded_len_base64`].
+pub fn decode_base64_to_slice_with(bytes: &[u8], dest: &mut [u8], config: &Config) -> Result<usize, DecodeError> {
+	let len = decoded_len_base64(bytes, config)?;
+	assert!(dest.len() >= len, "dest is too short for the decoded bytes");
+
+	let bytes = strip_padding(bytes, config)?;
+	let remainder = bytes.len() % STRING_FRAME_LEN;
+	let (whole_frames, last_frame) = bytes.split_at(bytes.len() - remainder);
+	let table = &config.alphabet.decoder;
+
+	unsafe {
+		// SAFETY: we checked dest has enough space
+		let mut dest = UnsafeBufWriteSlice::new(dest.as_mut_ptr(), len);
+		decode_frames(whole_frames, table, &mut dest)?;
+		decode_remainder(ChunkedSlice::new(last_frame), remainder, table, config.canonical, &mut dest)?;
+		dest.assert_is_full();
+	}
+
+	Ok(len)
+}
+
+/// Checks that the given bytes are valid base64 (with padding), returning the
+/// same error [`decode_base64`] would, but without allocating anywhere to put
+/// the decoded bytes.
+///
+/// Like [`decode_base64`], this ignores unused bits in the last char. To
+/// also check those are zero, use [`validate_base64_with`] with a config that
+/// has [`canonical`](Config::canonical) set.
+#[inline]
+pub fn validate_base64(bytes: &[u8]) -> Result<(), DecodeError> {
+	validate_base64_with(bytes, &Config::STANDARD)
+}
+
+/// Checks that the given bytes are valid base64 using the URL and filename
+/// safe alphabet (with padding), returning the same error [`decode_base64url`]
+/// would, but without allocating anywhere to put the decoded bytes.
+#[inline]
+pub fn validate_base64url(bytes: &[u8]) -> Result<(), DecodeError> {
+	validate_base64_with(bytes, &Config::URL_SAFE)
+}
+
+/// Checks that the given bytes are valid base64 using the given config,
+/// returning the same error [`decode_base64_with`] would, but without
+/// allocating anywhere to put the decoded bytes.
+pub fn validate_base64_with(bytes: &[u8], config: &Config) -> Result<(), DecodeError> {
+	let bytes = strip_padding(bytes, config)?;
+
+	let remainder = bytes.len() % STRING_FRAME_LEN;
+	if remainder == 1 { return Err(DecodeError::InvalidLength) }
+
+	let (whole_frames, last_frame) = bytes.split_at(bytes.len() - remainder);
+	let table = &config.alphabet.decoder;
+
+	unsafe {
+		// SAFETY: the discarding writer doesn't need any capacity
+		decode_frames(whole_frames, table, &mut UnsafeBufWriteDiscard)?;
+		decode_remainder(ChunkedSlice::new(last_frame), remainder, table, config.canonical, &mut UnsafeBufWriteDiscard)
+	}
+}
+
+/// Whether the given bytes are exactly what [`encode_base64_with`] produces
+/// with the given config for the bytes they decode to: unused bits are zero,
+/// and padding is there if (and only if) the config writes it. Returns `false`
+/// for input the config can't decode.
+pub fn is_canonical_base64(bytes: &[u8], config: &Config) -> bool {
+	let padded = bytes.last() == Some(&b'=');
+	let padding_ok = match config.padding {
+		// only unpadded input is accepted anyway
+		Padding::Forbidden => { true }
+		// when encoding, a partial last frame is always padded
+		Padding::Required | Padding::Optional => {
+			padded || bytes.len() % STRING_FRAME_LEN == 0
+		}
+	};
+
+	padding_ok && decode_base64_with(bytes, &config.canonical(true)).is_ok()
+}
+
+/// Decodes base64 with the [forgiving-base64 decode] algorithm from the
+/// WHATWG infra standard, which is what browsers use. ASCII whitespace
+/// anywhere in the input is ignored, padding is optional (but if there is
+/// any, it must be correct), and the unused bits in the last char are allowed
+/// to be non zero.
+///
+/// The standard alphabet is used; the URL safe alphabet is not accepted.
+///
+/// [forgiving-base64 decode]: https://infra.spec.whatwg.org/#forgiving-base64-decode
+pub fn decode_base64_forgiving(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	let mut stripped = bytes.iter()
+		.copied()
+		// same set of whitespace as the infra standard (no vertical tab)
+		.filter(|b| !b.is_ascii_whitespace())
+		.collect::<Vec<_>>();
+
+	if stripped.len() % STRING_FRAME_LEN == 0 {
+		if stripped.ends_with(b"==") {
+			stripped.truncate(stripped.len() - 2);
+		} else if stripped.ends_with(b"=") {
+			stripped.truncate(stripped.len() - 1);
+		}
+	}
+
+	_decode_unpadded(&stripped, &TABLE_DECODER, false)
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError {
+	#[error("invalid length")]
+	InvalidLength,
+	#[error("invalid character")]
+	InvalidChar,
+	#[error("invalid padding")]
+	InvalidPadding,
+	#[error("non canonical encoding (unused bits are not zero)")]
+	NonCanonical
+}
+
+#[cfg(feature = "bytes")]
+pub use self::bytes::*;
+
+/// Encoding and decoding from any [`Buf`](::bytes::Buf) (which doesn't have
+/// to be contiguous), appending the output to a [`BytesMut`](::bytes::BytesMut)
+#[cfg(feature = "bytes")]
+mod bytes {
+	use crate::encoding_utils::bytes::{ decode_buf, encode_buf, BufCodec };
+	use ::bytes::{ Buf, BytesMut };
+	use super::*;
+
+	/// Base64 encodes everything remaining in `src` (with padding), appending
+	/// it to `dest`
+	#[inline]
+	pub fn encode_base64_buf<B: Buf>(src: B, dest: &mut BytesMut) {
+		encode_base64_buf_with(src, dest, &Config::STANDARD)
+	}
+
+	/// Base64 encodes everything remaining in `src` using the given config,
+	/// appending it to `dest`
+	#[inline]
+	pub fn encode_base64_buf_with<B: Buf>(src: B, dest: &mut BytesMut, config: &Config) {
+		encode_buf(&Base64BufCodec(config), src, dest)
+	}
+
+	/// Decodes the base64 (with padding) remaining in `src`, appending the
+	/// decoded bytes to `dest`. If an error is returned, `dest` is left as it
+	/// was, but an unspecified amount of `src` will have been consumed.
+	#[inline]
+	pub fn decode_base64_buf<B: Buf>(src: B, dest: &mut BytesMut) -> Result<(), DecodeError> {
+		decode_base64_buf_with(src, dest, &Config::STANDARD)
+	}
+
+	/// Decodes the base64 remaining in `src` using the given config, appending
+	/// the decoded bytes to `dest` (see [`decode_base64_buf`])
+	#[inline]
+	pub fn decode_base64_buf_with<B: Buf>(src: B, dest: &mut BytesMut, config: &Config) -> Result<(), DecodeError> {
+		decode_buf(&Base64BufCodec(config), src, dest)
+	}
+
+	struct Base64BufCodec<'h>(&'h Config);
+
+	impl BufCodec for Base64BufCodec<'_> {
+		type Error = DecodeError;
+		const BINARY_FRAME_LEN: usize = BINARY_FRAME_LEN;
+		const STRING_FRAME_LEN: usize = STRING_FRAME_LEN;
+
+		#[inline]
+		fn encoded_len(&self, bytes_len: usize) -> usize {
+			encoded_len(bytes_len, self.0.encode_padding())
+		}
+
+		#[inline]
+		unsafe fn encode_into(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) {
+			encode_into(bytes, self.0, dest)
+		}
+
+		#[inline]
+		unsafe fn decode_frames(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) -> Result<(), DecodeError> {
+			decode_frames(bytes, &self.0.alphabet.decoder, dest)
+		}
+
+		#[inline]
+		fn decode_end(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			decode_base64_with(bytes, self.0)
+		}
+	}
+}
+
+#[cfg(feature = "tokio")]
+pub use self::tokio::*;
+
+/// Adapters for tokio's [`AsyncRead`](::tokio::io::AsyncRead) and
+/// [`AsyncWrite`](::tokio::io::AsyncWrite) that base64 encode or decode data
+/// as it passes through. Decode errors are returned as [`io::Error`](::std::io::Error)s
+/// of kind [`InvalidData`](::std::io::ErrorKind::InvalidData), wrapping a [`DecodeError`].
+///
+/// `new` uses [`Config::STANDARD`]; for any other config, pass a
+/// [`Base64Codec`] to `with_codec`.
+#[cfg(feature = "tokio")]
+mod tokio {
+	use crate::encoding_utils::stream::{ DecodeReader, DecodeWriter, EncodeReader, EncodeWriter, StreamCodec };
+	use super::*;
+
+	pub type Base64EncodeReader<R> = EncodeReader<R, Base64Codec>;
+	pub type Base64DecodeReader<R> = DecodeReader<R, Base64Codec>;
+	pub type Base64EncodeWriter<W> = EncodeWriter<W, Base64Codec>;
+	pub type Base64DecodeWriter<W> = DecodeWriter<W, Base64Codec>;
+
+	/// Base64 codec for the stream adapters, encoding and decoding with the
+	/// given config (the same as [`encode_base64_with`] and
+	/// [`decode_base64_with`]). Padding is only accepted at the very end of
+	/// the input.
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub struct Base64Codec {
+		config: Config
+	}
+
+	impl Base64Codec {
+		#[inline]
+		pub const fn new(config: Config) -> Self {
+			Self { config }
+		}
+
+		#[inline]
+		pub const fn config(&self) -> &Config {
+			&self.config
+		}
+	}
+
+	impl Default for Base64Codec {
+		#[inline]
+		fn default() -> Self {
+			Self::new(Config::STANDARD)
+		}
+	}
+
+	impl StreamCodec for Base64Codec {
+		type Error = DecodeError;
+		const BINARY_FRAME_LEN: usize = BINARY_FRAME_LEN;
+		const STRING_FRAME_LEN: usize = STRING_FRAME_LEN;
+		// the last frame can be padded
+		const HOLD_LAST_FRAME: bool = true;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			encode_base64_with(bytes, &self.config)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			// only ever whole frames, so any `=` in here is an invalid char
+			_decode_unpadded(bytes, &self.config.alphabet.decoder, false)
+		}
+
+		#[inline]
+		fn decode_end(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			decode_base64_with(bytes, &self.config)
+		}
+	}
+}
+
+#[inline]
+fn encoded_len(bytes_len: usize, padding: bool) -> usize {
+	if padding {
+		// 3 bytes per group of 4 output chars, rounding up for the padded frame
+		bytes_len.div_ceil(BINARY_FRAME_LEN) * STRING_FRAME_LEN
+	} else {
+		// 4 chars for every whole frame, then 1 more char than the amount
+		// of bytes left over, if any
+		let remainder = bytes_len % BINARY_FRAME_LEN;
+		(bytes_len / BINARY_FRAME_LEN * STRING_FRAME_LEN) + if remainder > 0 { remainder + 1 } else { 0 }
+	}
+}
+
+fn _encode_parallel(bytes: &[u8], config: &Config, threads: usize) -> String {
+	let Some(chunks) = parallel_chunks(bytes, BINARY_FRAME_LEN, threads) else {
+		return encode_base64_with(bytes, config)
+	};
+
+	let padding = config.encode_padding();
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(bytes.len(), padding));
+
+	let result = unsafe {
+		// only the last chunk can have a partial frame, so
+		// padding is still only added at the very end
+		dest.write_parallel(&chunks, |chunk| encoded_len(chunk.len(), padding), |chunk, dest| {
+			encode_into(chunk, config, dest);
+			Ok::<_, ()>(())
+		})
+	};
+	debug_assert!(result.is_ok(), "encoding doesn't fail");
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes valid utf-8");
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// # Safety
+///
+/// `dest` must have `encoded_len(bytes.len(), config.encode_padding())` bytes
+/// of capacity left
+unsafe fn encode_into<W: UnsafeBufWrite>(
+	bytes: &[u8],
+	config: &Config,
+	dest: &mut W
+) {
+	let table = &config.alphabet.encoder;
+	let frames = bytes.len() / BINARY_FRAME_LEN;
+	let remainder = bytes.len() % BINARY_FRAME_LEN;
+
+	let mut frames_iter = ChunkedSlice::<BINARY_FRAME_LEN>::new(bytes);
+
+	for _ in 0..frames {
+		let frame = frames_iter.next_frame_unchecked();
+		dest.write_bytes_const::<STRING_FRAME_LEN>(&encode_frame(frame, table) as *const u8);
+	}
+
+	if remainder > 0 {
+		let padding = config.encode_padding();
+		frames_iter.with_remainder_unchecked(|frame| {
+			let mut chars = encode_frame(frame, table);
+			if padding {
+				// 1 byte leaves 2 padding chars, 2 bytes leaves 1
+				for char in &mut chars[remainder + 1..] {
+					*char = b'=';
+				}
+				dest.write_bytes_const::<STRING_FRAME_LEN>(&chars as *const u8);
+			} else {
+				dest.write_bytes(&chars as *const u8, remainder + 1);
+			}
+		});
+	}
+}
+
+#[inline(always)]
+fn encode_frame(frame: &[u8; BINARY_FRAME_LEN], table: &[u8; TABLE_ENCODER_LEN]) -> [u8; STRING_FRAME_LEN] {
+	let [b1, b2, b3] = *frame;
+
+	[
+		table[(b1 >> 2) as usize],
+		table[(((b1 << 4) | (b2 >> 4)) & 0b111111) as usize],
+		table[(((b2 << 2) | (b3 >> 6)) & 0b111111) as usize],
+		table[(b3 & 0b111111) as usize]
+	]
+}
+
+/// Joins 4 groups of 6 bits back into a frame of 3 bytes
+#[inline(always)]
+fn join_frame(groups: &[u8; STRING_FRAME_LEN]) -> [u8; BINARY_FRAME_LEN] {
+	let [g1, g2, g3, g4] = *groups;
+
+	[
+		(g1 << 2) | (g2 >> 4),
+		(g2 << 4) | (g3 >> 2),
+		(g3 << 6) | g4
+	]
+}
+
+/// Decodes base64 without any padding. Any amount of chars except for one
+/// more than a multiple of 4 is a valid length. Unused bits in the last char
+/// are ignored, unless `canonical` is set.
+fn _decode_unpadded(
+	bytes: &[u8],
+	table: &[Option<u8>; TABLE_DECODER_LEN],
+	canonical: bool
+) -> Result<Vec<u8>, DecodeError> {
+	let frames = bytes.len() / STRING_FRAME_LEN;
+	let remainder = bytes.len() % STRING_FRAME_LEN;
+	// 2 chars decode to 1 byte, 3 chars to 2 bytes, 1 char is not enough
+	if remainder == 1 { return Err(DecodeError::InvalidLength) }
+	let last_frame_bytes = remainder.saturating_sub(1);
+
+	let mut dest = UnsafeBufWriteGuard::with_capacity((frames * BINARY_FRAME_LEN) + last_frame_bytes);
+	let (whole_frames, last_frame) = bytes.split_at(frames * STRING_FRAME_LEN);
+
+	unsafe {
+		// SAFETY: we allocated exactly enough capacity for every whole frame,
+		// plus the bytes the last partial frame decodes to
+		decode_frames(whole_frames, table, &mut dest)?;
+		decode_remainder(ChunkedSlice::new(last_frame), remainder, table, canonical, &mut dest)?;
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+fn _decode_parallel(bytes: &[u8], config: &Config, threads: usize) -> Result<Vec<u8>, DecodeError> {
+	let (table, canonical) = (&config.alphabet.decoder, config.canonical);
+	let bytes = strip_padding(bytes, config)?;
+
+	let remainder = bytes.len() % STRING_FRAME_LEN;
+	if remainder == 1 { return Err(DecodeError::InvalidLength) }
+
+	let (whole_frames, last_frame) = bytes.split_at(bytes.len() - remainder);
+	let Some(chunks) = parallel_chunks(whole_frames, STRING_FRAME_LEN, threads) else {
+		return _decode_unpadded(bytes, table, canonical)
+	};
+
+	let capacity = (whole_frames.len() / STRING_FRAME_LEN * BINARY_FRAME_LEN) + remainder.saturating_sub(1);
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe {
+		let output_len = |chunk: &[u8]| chunk.len() / STRING_FRAME_LEN * BINARY_FRAME_LEN;
+		dest.write_parallel(&chunks, output_len, |chunk, dest| decode_frames(chunk, table, dest))?;
+		decode_remainder(ChunkedSlice::new(last_frame), remainder, table, canonical, &mut dest)?;
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+/// Checks the padding at the end of `bytes` against the config's padding
+/// policy, returning the rest of the input without it
+fn strip_padding<'h>(bytes: &'h [u8], config: &Config) -> Result<&'h [u8], DecodeError> {
+	let padding = bytes.iter().rev().take_while(|b| **b == b'=').count();
+
+	match (config.padding, padding) {
+		(Padding::Forbidden, 1..) => { return Err(DecodeError::InvalidPadding) }
+		(Padding::Required, _) | (Padding::Optional, 1..) => {
+			if bytes.len() % STRING_FRAME_LEN != 0 { return Err(DecodeError::InvalidLength) }
+			if padding > 2 { return Err(DecodeError::InvalidPadding) }
+		}
+		_ => {}
+	}
+
+	Ok(&bytes[..bytes.len() - padding])
+}
+
+/// # Safety
+///
+/// `bytes` must only contain whole frames, and `dest` must have enough
+/// capacity left to hold all of them decoded
+#[inline(always)]
+unsafe fn decode_frames<W: UnsafeBufWrite>(
+	bytes: &[u8],
+	table: &[Option<u8>; TABLE_DECODER_LEN],
+	dest: &mut W
+) -> Result<(), DecodeError> {
+	let frames = bytes.len() / STRING_FRAME_LEN;
+	let mut frames_iter = ChunkedSlice::<STRING_FRAME_LEN>::new(bytes);
+
+	for _ in 0..frames {
+		let frame = frames_iter.next_frame_unchecked();
+		let groups = decode_groups(frame, STRING_FRAME_LEN, table)?;
+		dest.write_bytes_const::<BINARY_FRAME_LEN>(&join_frame(&groups) as *const u8);
+	}
+
+	Ok(())
+}
+
+/// Decodes the last partial frame of `remainder` chars left in
+/// `frames_iter`, if there is one. Unused bits in its last char are ignored,
+/// unless `canonical` is set.
+///
+/// # Safety
+///
+/// `frames_iter` must have exactly `remainder` chars left (less than a whole
+/// frame), and `dest` must have enough capacity left for the
+/// `remainder.saturating_sub(1)` bytes they decode to
+#[inline(always)]
+unsafe fn decode_remainder<W: UnsafeBufWrite>(
+	frames_iter: ChunkedSlice<STRING_FRAME_LEN>,
+	remainder: usize,
+	table: &[Option<u8>; TABLE_DECODER_LEN],
+	canonical: bool,
+	dest: &mut W
+) -> Result<(), DecodeError> {
+	if remainder == 0 { return Ok(()) }
+
+	let mut result = Ok(());
+	frames_iter.with_remainder_unchecked(|frame| {
+		result = decode_groups(frame, remainder, table).and_then(|groups| {
+			if canonical && !unused_bits_are_zero(&groups, remainder) {
+				return Err(DecodeError::NonCanonical)
+			}
+			dest.write_bytes(&join_frame(&groups) as *const u8, remainder - 1);
+			Ok(())
+		});
+	});
+	result
+}
+
+/// Decodes the first `len` chars in the frame into their 6 bit values,
+/// leaving the rest as 0
+#[inline(always)]
+fn decode_groups(
+	frame: &[u8; STRING_FRAME_LEN],
+	len: usize,
+	table: &[Option<u8>; TABLE_DECODER_LEN]
+) -> Result<[u8; STRING_FRAME_LEN], DecodeError> {
+	let mut groups = [0u8; STRING_FRAME_LEN];
+
+	for (group, char) in groups.iter_mut().zip(&frame[..len]) {
+		let Some(value) = table[*char as usize] else {
+			return Err(DecodeError::InvalidChar)
+		};
+		*group = value;
+	}
+
+	Ok(groups)
+}
+
+/// Whether the bits in the last char of a partial frame of `len` chars that
+/// don't make it into a whole byte are all zero
+#[inline]
+fn unused_bits_are_zero(groups: &[u8; STRING_FRAME_LEN], len: usize) -> bool {
+	match len {
+		// 12 bits, 4 unused
+		2 => { groups[1] & 0b1111 == 0 }
+		// 18 bits, 2 unused
+		3 => { groups[2] & 0b11 == 0 }
+		_ => { true }
+	}
+}
+
+const fn decoder_table(encoder: &[u8; TABLE_ENCODER_LEN]) -> [Option<u8>; TABLE_DECODER_LEN] {
+	let mut table = [None; TABLE_DECODER_LEN];
+
+	let mut i = 0;
+	while i < TABLE_ENCODER_LEN {
+		table[encoder[i] as usize] = Some(i as u8);
+		i += 1;
+	}
+
+	table
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn rfc_provided_examples() {
+		let examples = [
+			("", ""),
+			("f", "Zg=="),
+			("fo", "Zm8="),
+			("foo", "Zm9v"),
+			("foob", "Zm9vYg=="),
+			("fooba", "Zm9vYmE="),
+			("foobar", "Zm9vYmFy")
+		];
+
+		for (bytes, encoded) in examples {
+			assert_eq!(encoded, encode_base64(bytes.as_bytes()));
+			assert_eq!(bytes.as_bytes(), decode_base64(encoded.as_bytes()).expect("rfc example decodes"));
+			assert_eq!(bytes.as_bytes(), decode_base64_forgiving(encoded.as_bytes()).expect("rfc example decodes"));
+		}
+	}
+
+	#[test]
+	fn url_alphabet() {
+		let bytes = [0xfb, 0xff, 0xbf];
+		assert_eq!(encode_base64(&bytes), "+/+/");
+		assert_eq!(encode_base64url(&bytes), "-_-_");
+		assert_eq!(decode_base64url(b"-_-_").unwrap(), bytes);
+		assert!(matches!(decode_base64url(b"+/+/"), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base64(b"-_-_"), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..50).chain([100, 1000, 100_000]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let encoded = encode_base64(&bytes);
+			assert_eq!(encoded.len(), encoded.capacity());
+			let decoded = decode_base64(encoded.as_bytes())
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+			assert_eq!(decoded.len(), decoded.capacity());
+
+			let encoded = encode_base64url(&bytes);
+			let decoded = decode_base64url(encoded.as_bytes())
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+		}
+	}
+
+	#[test]
+	fn invalid() {
+		assert!(matches!(decode_base64(b"Zm9"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64(b"Zm9vY"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64(b"Z==="), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decode_base64(b"Zg=a"), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base64(b"Zg==Zg=="), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base64(b"Zm9v Yg=="), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64(b"Zm9*"), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn forgiving() {
+		let cases: [(&[u8], Option<&[u8]>); 16] = [
+			(b"", Some(b"")),
+			(b"  ", Some(b"")),
+			(b"Zg", Some(b"f")),
+			(b"Zg=", None),
+			(b"Zg==", Some(b"f")),
+			(b"Zg===", None),
+			(b"Zm8", Some(b"fo")),
+			(b"Zm8=", Some(b"fo")),
+			(b"Zm9v\tYg\n==", Some(b"foob")),
+			(b" Z m 9 v Y m F y ", Some(b"foobar")),
+			(b"\x0cZm9vYmE\r\n", Some(b"fooba")),
+			// non zero trailing bits are allowed
+			(b"Zh", Some(b"f")),
+			(b"Zm9vY", None),
+			(b"Zg==Zg==", None),
+			(b"Zm9v\x0bYg==", None),
+			(b"-_8=", None)
+		];
+
+		for (input, expected) in cases {
+			let decoded = decode_base64_forgiving(input).ok();
+			assert_eq!(decoded.as_deref(), expected, "{:?}", String::from_utf8_lossy(input));
+		}
+	}
+
+	#[test]
+	fn configs() {
+		let bytes = b"\xfb\xff\xbf\x00hi";
+		let cases = [
+			(Config::STANDARD, "+/+/AGhp"),
+			(Config::URL_SAFE, "-_-_AGhp"),
+			(Config::BCRYPT, "8989.Efn"),
+			(Config::CRYPT, "yzyz.4Vd"),
+			(Config::IMAP, "+,+,AGhp")
+		];
+
+		for (config, encoded) in cases {
+			assert_eq!(encode_base64_with(bytes, &config), encoded);
+			assert_eq!(decode_base64_with(encoded.as_bytes(), &config).unwrap(), bytes);
+		}
+
+		// known bcrypt salt (from the hash of "U*U" with cost 5)
+		let salt = decode_base64_with(b"CCCCCCCCCCCCCCCCCCCCC.", &Config::BCRYPT).unwrap();
+		assert_eq!(salt, [0x10, 0x41, 0x04, 0x10, 0x41, 0x04, 0x10, 0x41, 0x04, 0x10, 0x41, 0x04, 0x10, 0x41, 0x04, 0x10]);
+	}
+
+	#[test]
+	fn padding_policies() {
+		let required = Config::STANDARD;
+		let optional = Config::STANDARD.padding(Padding::Optional);
+		let forbidden = Config::STANDARD_NO_PAD;
+
+		assert_eq!(encode_base64_with(b"f", &required), "Zg==");
+		assert_eq!(encode_base64_with(b"f", &optional), "Zg==");
+		assert_eq!(encode_base64_with(b"f", &forbidden), "Zg");
+		assert_eq!(encode_base64_with(b"fo", &forbidden), "Zm8");
+		assert_eq!(encode_base64_with(b"foo", &forbidden), "Zm9v");
+
+		for (input, required_ok, optional_ok, forbidden_ok) in [
+			(&b"Zg=="[..], true, true, false),
+			(b"Zg", false, true, true),
+			(b"Zm8=", true, true, false),
+			(b"Zm8", false, true, true),
+			(b"Zm9v", true, true, true),
+			(b"Zg=", false, false, false),
+			(b"Z", false, false, false)
+		] {
+			assert_eq!(decode_base64_with(input, &required).is_ok(), required_ok, "{input:?}");
+			assert_eq!(decode_base64_with(input, &optional).is_ok(), optional_ok, "{input:?}");
+			assert_eq!(decode_base64_with(input, &forbidden).is_ok(), forbidden_ok, "{input:?}");
+		}
+
+		assert!(matches!(decode_base64_with(b"Zg==", &forbidden), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decode_base64_with(b"Zg", &required), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64_with(b"Z===", &optional), Err(DecodeError::InvalidPadding)));
+	}
+
+	#[test]
+	fn canonical() {
+		let canonical = Config::STANDARD.canonical(true);
+
+		assert_eq!(decode_base64(b"Zh==").unwrap(), b"f");
+		assert!(matches!(decode_base64_with(b"Zh==", &canonical), Err(DecodeError::NonCanonical)));
+		assert_eq!(decode_base64_with(b"Zg==", &canonical).unwrap(), b"f");
+
+		assert_eq!(decode_base64(b"Zm9=").unwrap(), b"fo");
+		assert!(matches!(decode_base64_with(b"Zm9=", &canonical), Err(DecodeError::NonCanonical)));
+		assert_eq!(decode_base64_with(b"Zm8=", &canonical).unwrap(), b"fo");
+
+		let no_pad = Config::URL_SAFE_NO_PAD.canonical(true);
+		assert!(matches!(decode_base64_with(b"Zh", &no_pad), Err(DecodeError::NonCanonical)));
+	}
+
+	#[test]
+	fn in_place() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..20).chain([1000, 1001, 1002]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let mut encoded = encode_base64(&bytes).into_bytes();
+			let decoded = decode_base64_in_place(&mut encoded)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+
+			let mut encoded = encode_base64url(&bytes).into_bytes();
+			let decoded = decode_base64url_in_place(&mut encoded)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+
+			let mut encoded = encode_base64_with(&bytes, &Config::URL_SAFE_NO_PAD).into_bytes();
+			let decoded = decode_base64_in_place_with(&mut encoded, &Config::URL_SAFE_NO_PAD)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+		}
+
+		assert!(matches!(decode_base64_in_place(&mut b"Zm9".to_vec()), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64_in_place(&mut b"Z===".to_vec()), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decode_base64_in_place(&mut b"Zm9*".to_vec()), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base64_in_place_with(&mut b"Zm9vY".to_vec(), &Config::STANDARD_NO_PAD), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64_in_place_with(&mut b"Zh==".to_vec(), &Config::STANDARD.canonical(true)), Err(DecodeError::NonCanonical)));
+	}
+
+	#[test]
+	fn to_slice() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..20).chain([1000, 1001, 1002]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			for config in [Config::STANDARD, Config::URL_SAFE_NO_PAD] {
+				let expected = encode_base64_with(&bytes, &config);
+
+				let mut encoded = vec![0u8; expected.len() + 3];
+				assert_eq!(encode_base64_to_slice_with(&bytes, &mut encoded, &config), expected.len());
+				assert_eq!(&encoded[..expected.len()], expected.as_bytes());
+				assert_eq!(encoded[expected.len()..], [0, 0, 0]);
+
+				assert_eq!(decoded_len_base64(expected.as_bytes(), &config).unwrap(), len);
+				let mut decoded = vec![0u8; len];
+				assert_eq!(decode_base64_to_slice_with(expected.as_bytes(), &mut decoded, &config).unwrap(), len);
+				assert_eq!(decoded, bytes);
+			}
+
+			let mut encoded = vec![0u8; encode_base64(&bytes).len()];
+			encode_base64_to_slice(&bytes, &mut encoded);
+			let mut decoded = vec![0u8; len];
+			assert_eq!(decode_base64_to_slice(&encoded, &mut decoded).unwrap(), len);
+			assert_eq!(decoded, bytes);
+		}
+
+		assert!(matches!(decoded_len_base64(b"Zm9", &Config::STANDARD), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decoded_len_base64(b"Z===", &Config::STANDARD), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(decoded_len_base64(b"Zm9vY", &Config::STANDARD_NO_PAD), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64_to_slice(b"Zm9*", &mut [0u8; 3]), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn validate() {
+		let mut rng = thread_rng();
+
+		for len in 0usize..50 {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+			assert!(validate_base64(encode_base64(&bytes).as_bytes()).is_ok());
+			assert!(validate_base64url(encode_base64url(&bytes).as_bytes()).is_ok());
+
+			let no_pad = encode_base64_with(&bytes, &Config::STANDARD_NO_PAD);
+			assert!(validate_base64_with(no_pad.as_bytes(), &Config::STANDARD_NO_PAD).is_ok());
+		}
+
+		assert!(matches!(validate_base64(b"Zm9"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(validate_base64(b"Z==="), Err(DecodeError::InvalidPadding)));
+		assert!(matches!(validate_base64(b"Zm9*"), Err(DecodeError::InvalidChar)));
+		assert!(matches!(validate_base64(b"Zm9vYmFy-_8="), Err(DecodeError::InvalidChar)));
+		assert!(matches!(validate_base64url(b"Zm9vYmFy+/8="), Err(DecodeError::InvalidChar)));
+		assert!(matches!(validate_base64_with(b"Zm9vY", &Config::STANDARD_NO_PAD), Err(DecodeError::InvalidLength)));
+
+		// unused bits are only checked if the config asks for it
+		assert!(validate_base64(b"Zh==").is_ok());
+		assert!(matches!(validate_base64_with(b"Zh==", &Config::STANDARD.canonical(true)), Err(DecodeError::NonCanonical)));
+	}
+
+	#[test]
+	fn custom_alphabet() {
+		let mut chars = TABLE_ENCODER;
+		chars.reverse();
+		let config = Config::new(Alphabet::new(&chars)).padding(Padding::Optional);
+
+		let mut rng = thread_rng();
+		for len in 0usize..50 {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let encoded = encode_base64_with(&bytes, &config);
+			assert_eq!(encoded.len(), encoded.capacity());
+			assert_eq!(decode_base64_with(encoded.as_bytes(), &config).unwrap(), bytes);
+			assert_eq!(decode_base64_with(encoded.trim_end_matches('=').as_bytes(), &config).unwrap(), bytes);
+
+			for config in [Config::URL_SAFE_NO_PAD, Config::BCRYPT, Config::CRYPT, Config::IMAP] {
+				let encoded = encode_base64_with(&bytes, &config);
+				assert_eq!(encoded.len(), encoded.capacity());
+				assert!(!encoded.contains('='));
+				assert_eq!(decode_base64_with(encoded.as_bytes(), &config.canonical(true)).unwrap(), bytes);
+			}
+		}
+
+		let mut duplicate = TABLE_ENCODER;
+		duplicate[1] = b'A';
+		assert!(Alphabet::try_new(&duplicate).is_none());
+		let mut padding = TABLE_ENCODER;
+		padding[63] = b'=';
+		assert!(Alphabet::try_new(&padding).is_none());
+		let mut non_ascii = TABLE_ENCODER;
+		non_ascii[0] = 0xff;
+		assert!(Alphabet::try_new(&non_ascii).is_none());
+	}
+
+	#[test]
+	fn is_canonical() {
+		let mut rng = thread_rng();
+
+		for len in 0usize..30 {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			for config in [Config::STANDARD, Config::URL_SAFE_NO_PAD, Config::BCRYPT, Config::STANDARD.padding(Padding::Optional)] {
+				let encoded = encode_base64_with(&bytes, &config);
+				assert!(is_canonical_base64(encoded.as_bytes(), &config));
+			}
+		}
+
+		let optional = Config::STANDARD.padding(Padding::Optional);
+		assert!(is_canonical_base64(b"Zg==", &optional));
+		// decodes fine, but encoding would have padded it
+		assert!(decode_base64_with(b"Zg", &optional).is_ok());
+		assert!(!is_canonical_base64(b"Zg", &optional));
+
+		assert!(!is_canonical_base64(b"Zh==", &Config::STANDARD));
+		assert!(!is_canonical_base64(b"Zm9=", &Config::STANDARD));
+		assert!(!is_canonical_base64(b"Zh", &Config::STANDARD_NO_PAD));
+		assert!(!is_canonical_base64(b"Zg==", &Config::STANDARD_NO_PAD));
+		assert!(!is_canonical_base64(b"Zg", &Config::STANDARD));
+		assert!(!is_canonical_base64(b"Zg=*", &Config::STANDARD));
+	}
+
+	#[test]
+	fn parallel() {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 10, 5 * 1024 * 1024, 5 * 1024 * 1024 + 1, 5 * 1024 * 1024 + 2] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			for config in [Config::STANDARD, Config::URL_SAFE_NO_PAD, Config::STANDARD.canonical(true)] {
+				// always split as if there are 4 cores, so the parallel
+				// path gets exercised on single core machines too
+				let encoded = _encode_parallel(&bytes, &config, 4);
+				assert_eq!(encoded, encode_base64_with(&bytes, &config));
+				assert_eq!(encoded, encode_base64_parallel_with(&bytes, &config));
+				assert_eq!(encoded.len(), encoded.capacity());
+
+				let decoded = _decode_parallel(encoded.as_bytes(), &config, 4)
+					.expect("can round trip decode just encoded data");
+				assert_eq!(bytes, decoded);
+				assert_eq!(bytes, decode_base64_parallel_with(encoded.as_bytes(), &config).unwrap());
+				assert_eq!(decoded.len(), decoded.capacity());
+			}
+
+			assert_eq!(encode_base64_parallel(&bytes), encode_base64(&bytes));
+		}
+
+		let mut invalid = encode_base64(&vec![0u8; 5 * 1024 * 1024 + 1]).into_bytes();
+		invalid[3 * 1024 * 1024] = b'*';
+		assert!(matches!(_decode_parallel(&invalid, &Config::STANDARD, 4), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_base64_parallel(&invalid), Err(DecodeError::InvalidChar)));
+
+		// unused bits set in the last (partial) frame
+		let mut non_canonical = encode_base64(&vec![0u8; 5 * 1024 * 1024 + 2]).into_bytes();
+		let last_char = non_canonical.len() - 3;
+		non_canonical[last_char] = b'B';
+		let config = Config::STANDARD.canonical(true);
+		assert!(_decode_parallel(&non_canonical, &Config::STANDARD, 4).is_ok());
+		assert!(matches!(_decode_parallel(&non_canonical, &config, 4), Err(DecodeError::NonCanonical)));
+		assert!(matches!(_decode_parallel(b"QUJD=", &Config::STANDARD, 4), Err(DecodeError::InvalidLength)));
+	}
+
+	#[cfg(feature = "tokio")]
+	#[::tokio::test(crate = "::tokio")]
+	async fn stream() {
+		use crate::encoding_utils::stream::test_utils::Trickle;
+		use ::tokio::io::{ AsyncReadExt, AsyncWriteExt };
+
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 2, 3, 4, 5, 100_000, 100_001] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			for config in [Config::STANDARD, Config::URL_SAFE_NO_PAD, Config::BCRYPT.canonical(true)] {
+				let codec = Base64Codec::new(config);
+				let encoded = encode_base64_with(&bytes, &config);
+
+				for n in [1, 3, 7, 10_000] {
+					let mut reader = Base64EncodeReader::with_codec(Trickle::new(&bytes, n), codec);
+					let mut out = String::new();
+					reader.read_to_string(&mut out).await.unwrap();
+					assert_eq!(encoded, out);
+
+					let mut reader = Base64DecodeReader::with_codec(Trickle::new(encoded.as_bytes(), n), codec);
+					let mut out = Vec::new();
+					reader.read_to_end(&mut out).await.unwrap();
+					assert_eq!(bytes, out);
+
+					let mut writer = Base64EncodeWriter::with_codec(Vec::new(), codec);
+					for chunk in bytes.chunks(n) {
+						writer.write_all(chunk).await.unwrap();
+					}
+					writer.shutdown().await.unwrap();
+					assert_eq!(encoded.as_bytes(), writer.into_inner());
+
+					let mut writer = Base64DecodeWriter::with_codec(Vec::new(), codec);
+					for chunk in encoded.as_bytes().chunks(n) {
+						writer.write_all(chunk).await.unwrap();
+					}
+					writer.shutdown().await.unwrap();
+					assert_eq!(bytes, writer.into_inner());
+				}
+			}
+		}
+
+		let mut reader = Base64DecodeReader::new(Trickle::new(b"Zm9vYmFy", 3));
+		let mut out = Vec::new();
+		reader.read_to_end(&mut out).await.unwrap();
+		assert_eq!(out, b"foobar");
+
+		let mut reader = Base64DecodeReader::new(Trickle::new(b"Zm9vYm*y", 3));
+		let err = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+		assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidData);
+		assert!(matches!(err.get_ref().and_then(|e| e.downcast_ref()), Some(DecodeError::InvalidChar)));
+
+		// padding is only allowed at the end
+		let mut reader = Base64DecodeReader::new(Trickle::new(b"Zg==Zm9vYmFy", 3));
+		let err = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+		assert!(matches!(err.get_ref().and_then(|e| e.downcast_ref()), Some(DecodeError::InvalidChar)));
+
+		let mut writer = Base64DecodeWriter::new(Vec::new());
+		writer.write_all(b"Zm9vY").await.unwrap();
+		let err = writer.shutdown().await.unwrap_err();
+		assert!(matches!(err.get_ref().and_then(|e| e.downcast_ref()), Some(DecodeError::InvalidLength)));
+
+		let codec = Base64Codec::new(Config::STANDARD.canonical(true));
+		let mut writer = Base64DecodeWriter::with_codec(Vec::new(), codec);
+		writer.write_all(b"Zm9vYh==").await.unwrap();
+		let err = writer.shutdown().await.unwrap_err();
+		assert!(matches!(err.get_ref().and_then(|e| e.downcast_ref()), Some(DecodeError::NonCanonical)));
+	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn bytes() {
+		use crate::encoding_utils::bytes::test_utils::Chunked;
+		use ::bytes::BytesMut;
+
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 2, 3, 4, 1000, 1001] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			for config in [Config::STANDARD, Config::URL_SAFE_NO_PAD, Config::BCRYPT] {
+				let encoded = encode_base64_with(&bytes, &config);
+
+				for n in [1, 3, 7, 1000] {
+					let mut dest = BytesMut::from(&b"prefix"[..]);
+					encode_base64_buf_with(Chunked::new(&bytes, n), &mut dest, &config);
+					assert_eq!(&dest[..6], b"prefix");
+					assert_eq!(&dest[6..], encoded.as_bytes());
+
+					let mut dest = BytesMut::from(&b"prefix"[..]);
+					decode_base64_buf_with(Chunked::new(encoded.as_bytes(), n), &mut dest, &config).unwrap();
+					assert_eq!(&dest[..6], b"prefix");
+					assert_eq!(&dest[6..], &*bytes);
+				}
+			}
+
+			let mut dest = BytesMut::new();
+			encode_base64_buf(&*bytes, &mut dest);
+			assert_eq!(&dest[..], encode_base64(&bytes).as_bytes());
+			let mut decoded = BytesMut::new();
+			decode_base64_buf(dest.freeze(), &mut decoded).unwrap();
+			assert_eq!(&decoded[..], &*bytes);
+		}
+
+		let mut dest = BytesMut::from(&b"prefix"[..]);
+		assert!(matches!(decode_base64_buf(Chunked::new(b"Zm9vYmFyZg=", 3), &mut dest), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64_buf(Chunked::new(b"Zm9vYmFyZg===", 3), &mut dest), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_base64_buf(Chunked::new(b"Zm9vYmFy=g==", 3), &mut dest), Err(DecodeError::InvalidChar)));
+		assert!(matches!(
+			decode_base64_buf_with(Chunked::new(b"Zm9vYmFyZg==", 3), &mut dest, &Config::STANDARD_NO_PAD),
+			Err(DecodeError::InvalidPadding)
+		));
+		assert_eq!(&dest[..], b"prefix");
+	}
+
+	#[test]
+	fn vectored() {
+		use crate::encoding_utils::test_utils::random_slices;
+		use ::std::io::IoSlice;
+
+		let mut rng = thread_rng();
+
+		for len in (0usize..40).chain([1000]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let slices = random_slices(&bytes, &mut rng);
+			let io_slices = slices.iter().map(|s| IoSlice::new(s)).collect::<Vec<_>>();
+
+			let encoded = encode_base64_vectored(&*io_slices);
+			assert_eq!(encoded, encode_base64(&bytes));
+			assert_eq!(encoded.len(), encoded.capacity());
+
+			let encoded = encode_base64_vectored_with(&*slices, &Config::URL_SAFE_NO_PAD);
+			assert_eq!(encoded, encode_base64_with(&bytes, &Config::URL_SAFE_NO_PAD));
+			assert_eq!(encoded.len(), encoded.capacity());
+		}
+	}
+}
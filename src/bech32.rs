@@ -0,0 +1,571 @@
+//! Bech32 ([BIP-173]) and Bech32m ([BIP-350]) encoding.
+//!
+//! A bech32 string is made of a human-readable part (HRP), the separator `1`,
+//! and a data part of 5-bit values, the last 6 of which are a BCH checksum
+//! over the whole thing. Bech32 and Bech32m only differ in the constant the
+//! checksum is XOR'd with.
+//!
+//! Byte data is split into 5-bit groups the same way as [`base32`](crate::base32)
+//! does, zero-padding the last group. If the data needs to include values that
+//! aren't whole bytes (for example a segwit witness version), the `_u5` functions
+//! work with the 5-bit values directly, and [`bytes_to_u5s`]/[`u5s_to_bytes`]
+//! are available to convert the rest.
+//!
+//! [BIP-173]: https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki
+//! [BIP-350]: https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki
+
+use crate::base32::{ self, BINARY_FRAME_LEN, STRING_FRAME_LEN };
+use crate::encoding_utils::ChunkedSlice;
+
+pub const TABLE_ENCODER_LEN: usize = 32;
+pub const TABLE_ENCODER: [u8; TABLE_ENCODER_LEN] = *b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+pub const TABLE_DECODER_LEN: usize = 256;
+/// Decodes both lower and upper case characters. Case mixing is checked
+/// for separately.
+pub static TABLE_DECODER: [Option<u8>; TABLE_DECODER_LEN] = {
+	let mut table = [None; TABLE_DECODER_LEN];
+
+	let mut i = 0;
+	while i < TABLE_ENCODER_LEN {
+		let char = TABLE_ENCODER[i];
+		table[char as usize] = Some(i as u8);
+		table[char.to_ascii_uppercase() as usize] = Some(i as u8);
+		i += 1;
+	}
+
+	table
+};
+
+pub const SEPARATOR: u8 = b'1';
+pub const CHECKSUM_LEN: usize = 6;
+pub const MAX_HRP_LEN: usize = 83;
+/// Max length of a whole bech32 string, including HRP, separator, and checksum
+pub const MAX_LEN: usize = 90;
+
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc830a3;
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+	/// Original variant specified in BIP-173
+	Bech32,
+	/// Variant with the modified checksum constant, specified in BIP-350
+	Bech32m
+}
+
+impl Variant {
+	#[inline]
+	fn checksum_const(self) -> u32 {
+		match self {
+			Self::Bech32 => { BECH32_CONST }
+			Self::Bech32m => { BECH32M_CONST }
+		}
+	}
+}
+
+/// A successfully decoded bech32 string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decoded {
+	hrp: String,
+	data: Vec<u8>,
+	variant: Variant
+}
+
+impl Decoded {
+	/// The human-readable part, in lowercase
+	#[inline]
+	pub fn hrp(&self) -> &str {
+		&self.hrp
+	}
+
+	/// The data part as 5-bit values, excluding the checksum
+	#[inline]
+	pub fn data_u5(&self) -> &[u8] {
+		&self.data
+	}
+
+	/// Which variant's checksum the string was encoded with
+	#[inline]
+	pub fn variant(&self) -> Variant {
+		self.variant
+	}
+
+	/// Converts the data part back into bytes. See [`u5s_to_bytes`].
+	#[inline]
+	pub fn to_bytes(&self) -> Result<Vec<u8>, DecodeError> {
+		u5s_to_bytes(&self.data)
+	}
+
+	/// Consumes self, returning the HRP, 5-bit data, and variant
+	#[inline]
+	pub fn into_parts(self) -> (String, Vec<u8>, Variant) {
+		let Self { hrp, data, variant } = self;
+		(hrp, data, variant)
+	}
+}
+
+/// Encodes the given HRP and bytes into a Bech32 [`String`]
+///
+/// An all uppercase HRP is lowercased in the output, and an HRP with both
+/// lower and upper case characters is rejected, the same as when decoding.
+#[inline]
+pub fn encode_bech32(hrp: &str, bytes: &[u8]) -> Result<String, EncodeError> {
+	_encode(hrp, &bytes_to_u5s(bytes), Variant::Bech32)
+}
+
+/// Encodes the given HRP and bytes into a Bech32m [`String`]
+///
+/// An all uppercase HRP is lowercased in the output, and an HRP with both
+/// lower and upper case characters is rejected, the same as when decoding.
+#[inline]
+pub fn encode_bech32m(hrp: &str, bytes: &[u8]) -> Result<String, EncodeError> {
+	_encode(hrp, &bytes_to_u5s(bytes), Variant::Bech32m)
+}
+
+/// Encodes the given HRP and 5-bit values into a [`String`] using the
+/// checksum of the provided variant.
+///
+/// Returns an error if any value in `u5s` is 32 or more. The HRP is handled
+/// the same as in [`encode_bech32`].
+pub fn encode_bech32_u5(hrp: &str, u5s: &[u8], variant: Variant) -> Result<String, EncodeError> {
+	if u5s.iter().any(|v| *v as usize >= TABLE_ENCODER_LEN) {
+		return Err(EncodeError::InvalidU5)
+	}
+
+	_encode(hrp, u5s, variant)
+}
+
+/// Decodes a Bech32 or Bech32m string, detecting the variant from its
+/// checksum. Both all lowercase and all uppercase strings are accepted.
+///
+/// If the checksum doesn't match and a single substituted character would
+/// make it match, the position of that character in `bytes` is returned with
+/// the error.
+pub fn decode_bech32(bytes: &[u8]) -> Result<Decoded, DecodeError> {
+	if bytes.len() > MAX_LEN { return Err(DecodeError::InvalidLength) }
+
+	let mut has_lower = false;
+	let mut has_upper = false;
+
+	for (position, byte) in bytes.iter().enumerate() {
+		match *byte {
+			b'a'..=b'z' => { has_lower = true }
+			b'A'..=b'Z' => { has_upper = true }
+			33..=126 => {}
+			_ => { return Err(DecodeError::InvalidChar { position }) }
+		}
+	}
+
+	if has_lower && has_upper { return Err(DecodeError::MixedCase) }
+
+	let Some(separator) = bytes.iter().rposition(|b| *b == SEPARATOR) else {
+		return Err(DecodeError::MissingSeparator)
+	};
+
+	let (hrp, data_with_separator) = bytes.split_at(separator);
+	let data = &data_with_separator[1..];
+
+	if hrp.is_empty() { return Err(DecodeError::InvalidHrp) }
+	if data.len() < CHECKSUM_LEN { return Err(DecodeError::InvalidLength) }
+
+	let hrp = hrp.to_ascii_lowercase();
+
+	let mut u5s = Vec::with_capacity(data.len());
+	for (i, byte) in data.iter().enumerate() {
+		// SAFETY: 0 <= n < 256 is always true for a u8, and TABLE_DECODER is len 256,
+		// so this is safe
+		let Some(u5) = (unsafe { *TABLE_DECODER.get_unchecked(*byte as usize) }) else {
+			return Err(DecodeError::InvalidChar { position: separator + 1 + i })
+		};
+		u5s.push(u5);
+	}
+
+	let residue = polymod(polymod_hrp(&hrp), &u5s);
+	let variant = match residue {
+		BECH32_CONST => { Variant::Bech32 }
+		BECH32M_CONST => { Variant::Bech32m }
+		_ => {
+			let position = locate_error(residue, u5s.len())
+				.map(|i| separator + 1 + i);
+			return Err(DecodeError::InvalidChecksum { position })
+		}
+	};
+
+	u5s.truncate(u5s.len() - CHECKSUM_LEN);
+
+	// SAFETY: every char in hrp was checked above to be ASCII (33..=126)
+	let hrp = unsafe { String::from_utf8_unchecked(hrp) };
+	Ok(Decoded { hrp, data: u5s, variant })
+}
+
+/// Splits bytes into 5-bit values the same way as base32 does, zero-padding
+/// the last value if the amount of bits isn't a multiple of 5.
+pub fn bytes_to_u5s(bytes: &[u8]) -> Vec<u8> {
+	let frames = bytes.len() / BINARY_FRAME_LEN;
+	let remainder = bytes.len() % BINARY_FRAME_LEN;
+
+	// round up, for the padded last value
+	let capacity = ((bytes.len() * 8) + 4) / 5;
+	let mut u5s = Vec::with_capacity(capacity);
+
+	let mut frames_iter = ChunkedSlice::<BINARY_FRAME_LEN>::new(bytes);
+
+	for _ in 0..frames {
+		// SAFETY: we calculated the amount of whole frames above
+		let frame = unsafe { frames_iter.next_frame_unchecked() };
+		u5s.extend_from_slice(&base32::split_frame(frame));
+	}
+
+	if remainder > 0 {
+		let remainder_u5s = capacity - u5s.len();
+
+		// SAFETY: remainder is 0 < n < BINARY_FRAME_LEN
+		unsafe {
+			frames_iter.with_remainder_unchecked(|frame| {
+				u5s.extend_from_slice(&base32::split_frame(frame)[..remainder_u5s]);
+			});
+		}
+	}
+
+	debug_assert_eq!(u5s.len(), capacity);
+	u5s
+}
+
+/// Joins 5-bit values back into bytes, the inverse of [`bytes_to_u5s`].
+///
+/// As required by BIP-173, returns an error if there are 5 or more bits of
+/// padding left over, or if the padding bits are not zero. Also returns an
+/// error if any value is 32 or more.
+pub fn u5s_to_bytes(u5s: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	if let Some(position) = u5s.iter().position(|v| *v as usize >= TABLE_ENCODER_LEN) {
+		return Err(DecodeError::InvalidChar { position })
+	}
+
+	let frames = u5s.len() / STRING_FRAME_LEN;
+	let remainder = u5s.len() % STRING_FRAME_LEN;
+
+	let remainder_bits = remainder * 5;
+	let remainder_bytes = remainder_bits / 8;
+	if remainder_bits % 8 >= 5 { return Err(DecodeError::InvalidPadding) }
+
+	let mut bytes = Vec::with_capacity((frames * BINARY_FRAME_LEN) + remainder_bytes);
+	let mut frames_iter = ChunkedSlice::<STRING_FRAME_LEN>::new(u5s);
+
+	for _ in 0..frames {
+		// SAFETY: we calculated the amount of whole frames above
+		let frame = unsafe { frames_iter.next_frame_unchecked() };
+		bytes.extend_from_slice(&base32::join_frame(frame));
+	}
+
+	if remainder > 0 {
+		let mut padding_ok = true;
+
+		// SAFETY: remainder is 0 < n < STRING_FRAME_LEN
+		unsafe {
+			frames_iter.with_remainder_unchecked(|frame| {
+				let joined = base32::join_frame(frame);
+				bytes.extend_from_slice(&joined[..remainder_bytes]);
+
+				// the temp frame is filled with zeros, so only the byte with
+				// the leftover bits in it can be nonzero
+				padding_ok = joined[remainder_bytes] == 0;
+			});
+		}
+
+		if !padding_ok { return Err(DecodeError::InvalidPadding) }
+	}
+
+	Ok(bytes)
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum EncodeError {
+	#[error("invalid human-readable part")]
+	InvalidHrp,
+	#[error("human-readable part contains both lower and upper case characters")]
+	MixedCase,
+	#[error("data value out of range for 5 bits")]
+	InvalidU5,
+	#[error("encoded string would be too long")]
+	TooLong
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError {
+	#[error("invalid length")]
+	InvalidLength,
+	#[error("invalid character at position {position}")]
+	InvalidChar { position: usize },
+	#[error("string contains both lower and upper case characters")]
+	MixedCase,
+	#[error("missing separator")]
+	MissingSeparator,
+	#[error("invalid human-readable part")]
+	InvalidHrp,
+	#[error("invalid checksum")]
+	InvalidChecksum {
+		/// Position of the character that, if substituted, would make the
+		/// checksum valid. `None` if the error couldn't be narrowed down to one
+		/// character (for example, there was more than one error)
+		position: Option<usize>
+	},
+	#[error("invalid padding")]
+	InvalidPadding
+}
+
+fn _encode(hrp: &str, u5s: &[u8], variant: Variant) -> Result<String, EncodeError> {
+	let hrp = hrp.as_bytes();
+
+	if hrp.is_empty() || hrp.len() > MAX_HRP_LEN { return Err(EncodeError::InvalidHrp) }
+	if hrp.iter().any(|b| !(33..=126).contains(b)) { return Err(EncodeError::InvalidHrp) }
+	if hrp.iter().any(u8::is_ascii_lowercase) && hrp.iter().any(u8::is_ascii_uppercase) {
+		return Err(EncodeError::MixedCase)
+	}
+
+	let capacity = hrp.len() + 1 + u5s.len() + CHECKSUM_LEN;
+	if capacity > MAX_LEN { return Err(EncodeError::TooLong) }
+
+	let hrp = hrp.to_ascii_lowercase();
+
+	let chk = polymod(polymod_hrp(&hrp), u5s);
+	let chk = polymod(chk, &[0; CHECKSUM_LEN]) ^ variant.checksum_const();
+
+	let mut string = Vec::with_capacity(capacity);
+	string.extend_from_slice(&hrp);
+	string.push(SEPARATOR);
+
+	// SAFETY: every u5 was checked by the caller to be less than TABLE_ENCODER_LEN
+	string.extend(u5s.iter().map(|u5| unsafe { *TABLE_ENCODER.get_unchecked(*u5 as usize) }));
+	string.extend((0..CHECKSUM_LEN).rev().map(|i| {
+		TABLE_ENCODER[((chk >> (i * 5)) & 0b11111) as usize]
+	}));
+
+	debug_assert_eq!(string.len(), capacity);
+	debug_assert!(String::from_utf8(string.clone()).is_ok(), "output bytes are valid utf-8");
+
+	// SAFETY: hrp was checked to be ASCII, and the rest are chars from the table
+	Ok(unsafe { String::from_utf8_unchecked(string) })
+}
+
+#[inline]
+fn polymod_step(chk: u32, value: u8) -> u32 {
+	let top = chk >> 25;
+	let mut chk = ((chk & 0x1ffffff) << 5) ^ value as u32;
+
+	for (i, generator) in GENERATOR.iter().enumerate() {
+		if (top >> i) & 1 != 0 { chk ^= generator }
+	}
+
+	chk
+}
+
+#[inline]
+fn polymod(chk: u32, values: &[u8]) -> u32 {
+	values.iter().fold(chk, |chk, value| polymod_step(chk, *value))
+}
+
+/// Runs the expanded HRP through polymod, starting from the initial value of 1.
+/// `hrp` should be lowercase.
+fn polymod_hrp(hrp: &[u8]) -> u32 {
+	let chk = hrp.iter().fold(1, |chk, c| polymod_step(chk, c >> 5));
+	let chk = polymod_step(chk, 0);
+	hrp.iter().fold(chk, |chk, c| polymod_step(chk, c & 0b11111))
+}
+
+/// Tries to find a single substitution in the data part that would result in
+/// a valid checksum for either variant, returning its index into the data part.
+///
+/// The checksum is linear, so the difference in residue caused by an error
+/// at some position is the same no matter what the rest of the string is,
+/// and can be calculated by running polymod over just the error (starting
+/// at 0 instead of 1) followed by zeros for the rest of the string.
+fn locate_error(residue: u32, data_len: usize) -> Option<usize> {
+	let targets = [residue ^ BECH32_CONST, residue ^ BECH32M_CONST];
+
+	// residue differences for each of the 5 bits, for an error at `position`.
+	// starting at the last position, working towards the front
+	let mut bit_diffs = [0u32; 5];
+	for (bit, diff) in bit_diffs.iter_mut().enumerate() {
+		*diff = polymod_step(0, 1 << bit);
+	}
+
+	for position in (0..data_len).rev() {
+		for error in 1..TABLE_ENCODER_LEN {
+			let diff = bit_diffs.iter()
+				.enumerate()
+				.filter(|(bit, _)| (error >> bit) & 1 != 0)
+				.fold(0, |acc, (_, diff)| acc ^ diff);
+
+			if targets.contains(&diff) { return Some(position) }
+		}
+
+		bit_diffs.iter_mut().for_each(|diff| *diff = polymod_step(*diff, 0));
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn bip173_valid() {
+		let valid = [
+			"A12UEL5L",
+			"a12uel5l",
+			"an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+			"abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+			"11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j",
+			"split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+			"?1ezyfcl"
+		];
+
+		for string in valid {
+			let decoded = decode_bech32(string.as_bytes()).expect("valid test vector decodes");
+			assert_eq!(decoded.variant(), Variant::Bech32);
+
+			let encoded = encode_bech32_u5(decoded.hrp(), decoded.data_u5(), Variant::Bech32).unwrap();
+			assert_eq!(encoded, string.to_ascii_lowercase());
+		}
+	}
+
+	#[test]
+	fn bip350_valid() {
+		let valid = [
+			"A1LQFN3A",
+			"a1lqfn3a",
+			"an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6",
+			"abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+			"11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8",
+			"split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+			"?1v759aa"
+		];
+
+		for string in valid {
+			let decoded = decode_bech32(string.as_bytes()).expect("valid test vector decodes");
+			assert_eq!(decoded.variant(), Variant::Bech32m);
+
+			let encoded = encode_bech32_u5(decoded.hrp(), decoded.data_u5(), Variant::Bech32m).unwrap();
+			assert_eq!(encoded, string.to_ascii_lowercase());
+		}
+	}
+
+	#[test]
+	fn bip173_bip350_invalid() {
+		let invalid: &[&[u8]] = &[
+			// bip 173
+			b"\x201nwldj5",
+			b"\x7f1axkwrx",
+			b"\x801eym55h",
+			b"an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx",
+			b"pzry9x0s0muk",
+			b"1pzry9x0s0muk",
+			b"x1b4n0q5v",
+			b"li1dgmt3",
+			b"de1lg7wt\xff",
+			b"A1G7SGD8",
+			b"10a06t8",
+			b"1qzzfhee",
+
+			// bip 350
+			b"\x201xj0phk",
+			b"\x7f1g6xzxy",
+			b"\x801vctc34",
+			b"an84characterslonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11d6pts4",
+			b"qyrz8wqd2c9m",
+			b"1qyrz8wqd2c9m",
+			b"y1b0jsk6g",
+			b"lt1igcx5c0",
+			b"in1muywd",
+			b"mm1crxm3i",
+			b"au1s5cgom",
+			b"M1VUXWEZ",
+			b"16plkw9",
+			b"1p2gdwpf"
+		];
+
+		for string in invalid {
+			assert!(decode_bech32(string).is_err(), "{string:?} should fail to decode");
+		}
+
+		assert!(matches!(decode_bech32(b"x1b4n0q5v"), Err(DecodeError::InvalidChar { position: 2 })));
+		assert!(matches!(decode_bech32(b"pzry9x0s0muk"), Err(DecodeError::MissingSeparator)));
+		assert!(matches!(decode_bech32(b"10a06t8"), Err(DecodeError::InvalidHrp)));
+		assert!(matches!(decode_bech32(b"A1g7sgd8"), Err(DecodeError::MixedCase)));
+	}
+
+	#[test]
+	fn error_location() {
+		let valid = "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw";
+
+		for position in 7..valid.len() {
+			let mut string = valid.as_bytes().to_vec();
+			string[position] = if string[position] == b'q' { b'p' } else { b'q' };
+
+			let err = decode_bech32(&string).unwrap_err();
+			assert!(
+				matches!(err, DecodeError::InvalidChecksum { position: Some(p) } if p == position),
+				"single error at {position} located, got {err:?}"
+			);
+		}
+	}
+
+	#[test]
+	fn segwit_address() {
+		// from BIP-173: P2WPKH, witness version 0
+		let address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+		let program = ::hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+
+		let decoded = decode_bech32(address.as_bytes()).unwrap();
+		assert_eq!(decoded.hrp(), "bc");
+		assert_eq!(decoded.variant(), Variant::Bech32);
+
+		let (version, data) = decoded.data_u5().split_first().unwrap();
+		assert_eq!(*version, 0);
+		assert_eq!(u5s_to_bytes(data).unwrap(), program);
+
+		let mut u5s = vec![0];
+		u5s.extend(bytes_to_u5s(&program));
+		assert_eq!(encode_bech32_u5("bc", &u5s, Variant::Bech32).unwrap(), address);
+	}
+
+	#[test]
+	fn out_of_range_u5s() {
+		assert!(matches!(u5s_to_bytes(&[1, 2, 32, 4]), Err(DecodeError::InvalidChar { position: 2 })));
+		assert!(matches!(u5s_to_bytes(&[255; 8]), Err(DecodeError::InvalidChar { position: 0 })));
+		assert!(matches!(encode_bech32_u5("hrp", &[0, 32], Variant::Bech32), Err(EncodeError::InvalidU5)));
+	}
+
+	#[test]
+	fn hrp_case() {
+		assert_eq!(encode_bech32("HRP", &[]).unwrap(), encode_bech32("hrp", &[]).unwrap());
+		assert!(matches!(encode_bech32("Hrp", &[]), Err(EncodeError::MixedCase)));
+		assert!(matches!(encode_bech32m("hRp", &[1, 2]), Err(EncodeError::MixedCase)));
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		// 90 - "hrp1".len() - checksum, as bytes
+		for len in 0usize..=(80 * 5 / 8) {
+			let mut original_input = vec![0u8; len];
+			rng.fill(&mut *original_input);
+
+			for (encoded, variant) in [
+				(encode_bech32("hrp", &original_input).unwrap(), Variant::Bech32),
+				(encode_bech32m("hrp", &original_input).unwrap(), Variant::Bech32m)
+			] {
+				let decoded = decode_bech32(encoded.as_bytes())
+					.expect("can round trip decode just encoded data");
+				assert_eq!(decoded.hrp(), "hrp");
+				assert_eq!(decoded.variant(), variant);
+				assert_eq!(decoded.to_bytes().unwrap(), original_input);
+			}
+		}
+	}
+}
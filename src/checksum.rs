@@ -0,0 +1,546 @@
+//! Encoding with a checksum added, so typos in copied identifiers can be
+//! caught when decoding.
+//!
+//! Works with any of the codecs in this crate whose features are enabled (see
+//! [`Codec`]), with one of two kinds of [`Checksum`]:
+//!
+//! - [`Crc32`](Checksum::Crc32): the CRC-32 (the same one as zlib, PNG, etc)
+//!   of the data is appended to it as 4 big endian bytes, and it's all
+//!   encoded together.
+//! - [`LuhnModN`](Checksum::LuhnModN): the data is encoded, and a check char
+//!   from the codec's alphabet, calculated with the [Luhn mod N algorithm] over
+//!   the encoded chars, is appended. This only adds one char, and always
+//!   catches a single mistyped char, and almost all swaps of two adjacent
+//!   chars. Chars that aren't in the alphabet (ex. padding) are skipped over.
+//!
+//! When decoding, chars that the codec can't decode are reported as the codec's
+//! error ([`DecodeError::Codec`]), and only input that decodes fine but has the
+//! wrong checksum is reported as [`DecodeError::ChecksumMismatch`].
+//!
+//! [Luhn mod N algorithm]: https://en.wikipedia.org/wiki/Luhn_mod_N_algorithm
+
+/// Length of a CRC-32 checksum in bytes
+pub const CRC32_LEN: usize = 4;
+
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+	Crc32,
+	LuhnModN
+}
+
+/// A codec that checksummed data can be encoded with
+pub trait Codec {
+	type Error: ::std::error::Error;
+
+	fn encode(&self, bytes: &[u8]) -> String;
+
+	fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, Self::Error>;
+
+	/// The chars the codec encodes with, where the char at index n
+	/// encodes the value n
+	fn alphabet(&self) -> &[u8];
+
+	/// The value of a char, or `None` if it isn't in the alphabet. Chars that
+	/// decode to the same value (ex. upper and lower case hex) should
+	/// return the same value.
+	#[inline]
+	fn char_value(&self, char: u8) -> Option<usize> {
+		self.alphabet().iter().position(|c| *c == char)
+	}
+
+	/// Whether `bytes` is what encoding `decoded` would output, for codecs where
+	/// more than one string can decode to the same bytes, and decoding doesn't
+	/// already reject the others. Otherwise, a typo that only changes bits that
+	/// are thrown away when decoding wouldn't be caught by a CRC-32.
+	#[inline]
+	fn is_canonical(&self, bytes: &[u8], decoded: &[u8]) -> bool {
+		let _ = (bytes, decoded);
+		true
+	}
+}
+
+/// Encodes the given bytes with `codec`, with the checksum added
+pub fn encode_checksummed<C: Codec + ?Sized>(bytes: &[u8], codec: &C, checksum: Checksum) -> String {
+	match checksum {
+		Checksum::Crc32 => {
+			let mut data = Vec::with_capacity(bytes.len() + CRC32_LEN);
+			data.extend_from_slice(bytes);
+			data.extend_from_slice(&crc32(bytes).to_be_bytes());
+			codec.encode(&data)
+		}
+		Checksum::LuhnModN => {
+			let mut encoded = codec.encode(bytes);
+			let check_char = luhn_check_char(encoded.as_bytes(), codec);
+			encoded.push(check_char as char);
+			encoded
+		}
+	}
+}
+
+/// Decodes the given bytes with `codec`, checking and removing the checksum
+pub fn decode_checksummed<C: Codec + ?Sized>(
+	bytes: &[u8],
+	codec: &C,
+	checksum: Checksum
+) -> Result<Vec<u8>, DecodeError<C::Error>> {
+	match checksum {
+		Checksum::Crc32 => {
+			let mut data = codec.decode(bytes).map_err(DecodeError::Codec)?;
+			if data.len() < CRC32_LEN { return Err(DecodeError::InvalidLength) }
+
+			let data_len = data.len() - CRC32_LEN;
+			let expected = u32::from_be_bytes(data[data_len..].try_into().unwrap());
+			if crc32(&data[..data_len]) != expected || !codec.is_canonical(bytes, &data) {
+				return Err(DecodeError::ChecksumMismatch)
+			}
+
+			data.truncate(data_len);
+			Ok(data)
+		}
+		Checksum::LuhnModN => {
+			let Some((check_char, encoded)) = bytes.split_last() else {
+				return Err(DecodeError::InvalidLength)
+			};
+			if codec.char_value(*check_char).is_none() { return Err(DecodeError::InvalidChar) }
+
+			let data = codec.decode(encoded).map_err(DecodeError::Codec)?;
+			if !luhn_is_valid(bytes, codec) { return Err(DecodeError::ChecksumMismatch) }
+
+			Ok(data)
+		}
+	}
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError<E> {
+	/// Input is too short to have a checksum
+	#[error("invalid length")]
+	InvalidLength,
+	/// The Luhn check char isn't in the codec's alphabet
+	#[error("invalid character")]
+	InvalidChar,
+	#[error(transparent)]
+	Codec(E),
+	#[error("checksum mismatch")]
+	ChecksumMismatch
+}
+
+/// CRC-32 (IEEE 802.3, reflected, polynomial `0x04c11db7`) of the given bytes
+pub fn crc32(bytes: &[u8]) -> u32 {
+	!bytes.iter().fold(!0u32, |crc, byte| {
+		CRC32_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+	})
+}
+
+const fn crc32_table() -> [u32; 256] {
+	// reversed 0x04c11db7
+	const POLY: u32 = 0xedb88320;
+
+	let mut table = [0u32; 256];
+
+	let mut i = 0;
+	while i < 256 {
+		let mut crc = i as u32;
+
+		let mut bit = 0;
+		while bit < 8 {
+			crc = if crc & 1 == 1 { (crc >> 1) ^ POLY } else { crc >> 1 };
+			bit += 1;
+		}
+
+		table[i] = crc;
+		i += 1;
+	}
+
+	table
+}
+
+/// Luhn mod N sum of the chars in the alphabet, going from the end, with
+/// every other char (starting with the last) doubled if `double` is set
+fn luhn_sum<C: Codec + ?Sized>(chars: &[u8], codec: &C, mut double: bool) -> usize {
+	let n = codec.alphabet().len();
+
+	chars.iter()
+		.rev()
+		.filter_map(|c| codec.char_value(*c))
+		.map(|value| {
+			let factor = if double { 2 } else { 1 };
+			double = !double;
+
+			let addend = value * factor;
+			if n % 2 == 0 {
+				// doubling isn't one to one mod an even number, so the "digits"
+				// (in base n) are summed like in the original Luhn algorithm
+				(addend / n) + (addend % n)
+			} else {
+				// but it is for odd numbers, so no need
+				addend % n
+			}
+		})
+		.sum::<usize>() % n
+}
+
+fn luhn_check_char<C: Codec + ?Sized>(encoded: &[u8], codec: &C) -> u8 {
+	let n = codec.alphabet().len();
+	let sum = luhn_sum(encoded, codec, true);
+	codec.alphabet()[(n - sum) % n]
+}
+
+fn luhn_is_valid<C: Codec + ?Sized>(encoded: &[u8], codec: &C) -> bool {
+	luhn_sum(encoded, codec, false) == 0
+}
+
+#[cfg(feature = "base32")]
+pub use self::base32::*;
+
+#[cfg(feature = "base32")]
+mod base32 {
+	use crate::base32::{ self, TABLE_DECODER, TABLE_DECODER_BASE32HEX, TABLE_ENCODER, TABLE_ENCODER_BASE32HEX };
+	use super::Codec;
+
+	/// Base32 (with padding), see [`encode_base32`](base32::encode_base32).
+	/// Decoding only accepts the canonical encoding (see
+	/// [`decode_base32_strict`](base32::decode_base32_strict)), so a typo in
+	/// the unused bits of the last char can't go unnoticed.
+	pub struct Base32;
+
+	/// Base32hex (with padding), see [`encode_base32hex`](base32::encode_base32hex).
+	/// Decoding only accepts the canonical encoding, the same as [`Base32`].
+	pub struct Base32Hex;
+
+	impl Codec for Base32 {
+		type Error = base32::DecodeError;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			base32::encode_base32(bytes)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, base32::DecodeError> {
+			base32::decode_base32_strict(bytes)
+		}
+
+		#[inline]
+		fn alphabet(&self) -> &[u8] {
+			&TABLE_ENCODER
+		}
+
+		#[inline]
+		fn char_value(&self, char: u8) -> Option<usize> {
+			TABLE_DECODER[char as usize].map(usize::from)
+		}
+	}
+
+	impl Codec for Base32Hex {
+		type Error = base32::DecodeError;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			base32::encode_base32hex(bytes)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, base32::DecodeError> {
+			base32::decode_base32hex_strict(bytes)
+		}
+
+		#[inline]
+		fn alphabet(&self) -> &[u8] {
+			&TABLE_ENCODER_BASE32HEX
+		}
+
+		#[inline]
+		fn char_value(&self, char: u8) -> Option<usize> {
+			TABLE_DECODER_BASE32HEX[char as usize].map(usize::from)
+		}
+	}
+}
+
+#[cfg(feature = "base58")]
+pub use self::base58::*;
+
+#[cfg(feature = "base58")]
+mod base58 {
+	use crate::base58::{ self, Alphabet };
+	use super::Codec;
+
+	/// Base58 with the given alphabet, see
+	/// [`encode_base58_with`](base58::encode_base58_with)
+	pub struct Base58<'h>(pub &'h Alphabet);
+
+	impl Codec for Base58<'_> {
+		type Error = base58::DecodeError;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			base58::encode_base58_with(bytes, self.0)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, base58::DecodeError> {
+			base58::decode_base58_with(bytes, self.0)
+		}
+
+		#[inline]
+		fn alphabet(&self) -> &[u8] {
+			self.0.chars()
+		}
+	}
+}
+
+#[cfg(feature = "base64")]
+pub use self::base64::*;
+
+#[cfg(feature = "base64")]
+mod base64 {
+	use crate::base64::{ self, Config };
+	use super::Codec;
+
+	/// Base64 with the given config, see
+	/// [`encode_base64_with`](base64::encode_base64_with). Decoding always only
+	/// accepts the canonical encoding (as if [`canonical`](Config::canonical)
+	/// was set on the config), so a typo in the unused bits of the last char
+	/// can't go unnoticed.
+	pub struct Base64(pub Config);
+
+	impl Codec for Base64 {
+		type Error = base64::DecodeError;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			base64::encode_base64_with(bytes, &self.0)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, base64::DecodeError> {
+			base64::decode_base64_with(bytes, &self.0.canonical(true))
+		}
+
+		#[inline]
+		fn alphabet(&self) -> &[u8] {
+			self.0.alphabet().chars()
+		}
+	}
+}
+
+#[cfg(feature = "hex")]
+pub use self::hex::*;
+
+#[cfg(feature = "hex")]
+mod hex {
+	use crate::hex::{ self, TABLE_DECODER, TABLE_ENCODER_LOWER, TABLE_ENCODER_UPPER };
+	use super::Codec;
+
+	/// Lower hex. Decoding accepts both cases.
+	pub struct Hex;
+
+	/// Upper hex. Decoding accepts both cases.
+	pub struct HexUpper;
+
+	impl Codec for Hex {
+		type Error = hex::DecodeError;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			hex::encode_hex(bytes)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, hex::DecodeError> {
+			hex::decode_hex(bytes)
+		}
+
+		#[inline]
+		fn alphabet(&self) -> &[u8] {
+			&TABLE_ENCODER_LOWER
+		}
+
+		#[inline]
+		fn char_value(&self, char: u8) -> Option<usize> {
+			TABLE_DECODER[char as usize].map(usize::from)
+		}
+	}
+
+	impl Codec for HexUpper {
+		type Error = hex::DecodeError;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			hex::encode_hex_upper(bytes)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, hex::DecodeError> {
+			hex::decode_hex(bytes)
+		}
+
+		#[inline]
+		fn alphabet(&self) -> &[u8] {
+			&TABLE_ENCODER_UPPER
+		}
+
+		#[inline]
+		fn char_value(&self, char: u8) -> Option<usize> {
+			TABLE_DECODER[char as usize].map(usize::from)
+		}
+	}
+}
+
+#[cfg(feature = "z85")]
+pub use self::z85::*;
+
+#[cfg(feature = "z85")]
+mod z85 {
+	use crate::z85::{ self, TABLE_DECODER, TABLE_ENCODER };
+	use super::Codec;
+
+	/// Z85, with this crate's nonstandard padding (see [`z85`](crate::z85))
+	pub struct Z85;
+
+	impl Codec for Z85 {
+		type Error = z85::DecodeError;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			z85::encode_z85(bytes)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, z85::DecodeError> {
+			z85::decode_z85(bytes)
+		}
+
+		#[inline]
+		fn alphabet(&self) -> &[u8] {
+			&TABLE_ENCODER
+		}
+
+		#[inline]
+		fn char_value(&self, char: u8) -> Option<usize> {
+			TABLE_DECODER[char as usize].map(usize::from)
+		}
+
+		/// The padding bytes in the last frame are thrown away when decoding,
+		/// so this checks that they were zero
+		#[inline]
+		fn is_canonical(&self, bytes: &[u8], decoded: &[u8]) -> bool {
+			z85::encode_z85(decoded).as_bytes() == bytes
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn crc32_check_value() {
+		assert_eq!(crc32(b""), 0);
+		assert_eq!(crc32(b"123456789"), 0xcbf43926);
+		assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414fa339);
+	}
+
+	#[test]
+	fn luhn_mod_n() {
+		// worked out by hand: from the end, f (15) doubled is 30, plus e (14),
+		// d (13) doubled is 26, plus c (12), b (11) doubled is 22, plus a (10),
+		// is 114, which is 6 mod 36, so the check char is 30 (u)
+		struct Base36;
+		impl Codec for Base36 {
+			type Error = ::std::fmt::Error;
+			fn encode(&self, bytes: &[u8]) -> String { String::from_utf8(bytes.to_vec()).unwrap() }
+			fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, Self::Error> { Ok(bytes.to_vec()) }
+			fn alphabet(&self) -> &[u8] { b"0123456789abcdefghijklmnopqrstuvwxyz" }
+		}
+
+		assert_eq!(luhn_check_char(b"abcdef", &Base36), b'u');
+		assert_eq!(encode_checksummed(b"abcdef", &Base36, Checksum::LuhnModN), "abcdefu");
+		assert_eq!(decode_checksummed(b"abcdefu", &Base36, Checksum::LuhnModN).unwrap(), b"abcdef");
+		assert!(matches!(decode_checksummed(b"abcdefj", &Base36, Checksum::LuhnModN), Err(DecodeError::ChecksumMismatch)));
+		assert!(matches!(decode_checksummed(b"abcdef!", &Base36, Checksum::LuhnModN), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_checksummed(b"", &Base36, Checksum::LuhnModN), Err(DecodeError::InvalidLength)));
+	}
+
+	/// Round trips, and makes sure every single char substitution and every
+	/// swap of two different adjacent chars is caught
+	fn check_codec<C: Codec>(codec: &C, checksum: Checksum, catches_swaps: bool) {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 4, 5, 16] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let encoded = encode_checksummed(&bytes, codec, checksum).into_bytes();
+			assert_eq!(decode_checksummed(&encoded, codec, checksum).unwrap(), bytes);
+
+			for i in 0..encoded.len() {
+				let Some(value) = codec.char_value(encoded[i]) else { continue };
+
+				for replacement in codec.alphabet() {
+					if codec.char_value(*replacement) == Some(value) { continue }
+
+					let mut typo = encoded.clone();
+					typo[i] = *replacement;
+					assert!(decode_checksummed(&typo, codec, checksum).is_err());
+				}
+
+				if catches_swaps && i + 1 < encoded.len() && encoded[i] != encoded[i + 1] {
+					let mut typo = encoded.clone();
+					typo.swap(i, i + 1);
+					assert!(decode_checksummed(&typo, codec, checksum).is_err());
+				}
+			}
+		}
+	}
+
+	#[cfg(feature = "hex")]
+	#[test]
+	fn hex() {
+		check_codec(&Hex, Checksum::Crc32, true);
+		check_codec(&Hex, Checksum::LuhnModN, false);
+		check_codec(&HexUpper, Checksum::LuhnModN, false);
+
+		let encoded = encode_checksummed(b"wiwi", &Hex, Checksum::LuhnModN);
+		assert_eq!(decode_checksummed(encoded.to_uppercase().as_bytes(), &Hex, Checksum::LuhnModN).unwrap(), b"wiwi");
+		assert!(matches!(
+			decode_checksummed(b"7769776g0", &Hex, Checksum::LuhnModN),
+			Err(DecodeError::Codec(crate::hex::DecodeError::InvalidChar))
+		));
+		assert!(matches!(decode_checksummed(b"776977", &Hex, Checksum::Crc32), Err(DecodeError::InvalidLength)));
+	}
+
+	#[cfg(feature = "base32")]
+	#[test]
+	fn base32() {
+		check_codec(&Base32, Checksum::Crc32, true);
+		check_codec(&Base32, Checksum::LuhnModN, false);
+		check_codec(&Base32Hex, Checksum::LuhnModN, false);
+	}
+
+	#[cfg(feature = "base58")]
+	#[test]
+	fn base58() {
+		check_codec(&Base58(&crate::base58::ALPHABET_BITCOIN), Checksum::Crc32, true);
+		check_codec(&Base58(&crate::base58::ALPHABET_BITCOIN), Checksum::LuhnModN, false);
+	}
+
+	#[cfg(feature = "base64")]
+	#[test]
+	fn base64() {
+		use crate::base64::Config;
+
+		check_codec(&Base64(Config::STANDARD), Checksum::Crc32, true);
+		check_codec(&Base64(Config::URL_SAFE_NO_PAD), Checksum::LuhnModN, false);
+	}
+
+	#[cfg(feature = "z85")]
+	#[test]
+	fn z85() {
+		check_codec(&Z85, Checksum::Crc32, true);
+		check_codec(&Z85, Checksum::LuhnModN, true);
+	}
+}
@@ -0,0 +1,438 @@
+//! `data:` URLs ([RFC 2397]), parsed with the [data: URL processor] from the
+//! WHATWG fetch standard, the same way browsers do.
+//!
+//! Parsing is lenient like in browsers: the media type falls back to
+//! `text/plain;charset=US-ASCII` if it's missing or invalid, and base64 bodies
+//! are decoded with [`decode_base64_forgiving`]. The only failures are a
+//! missing `data:` scheme, a missing `,`, and a base64 body that can't be
+//! decoded even by the forgiving decoder.
+//!
+//! [RFC 2397]: https://datatracker.ietf.org/doc/html/rfc2397
+//! [data: URL processor]: https://fetch.spec.whatwg.org/#data-url-processor
+
+use crate::base64::{ decode_base64_forgiving, encode_base64 };
+use crate::percent_encoding::{ decode_percent_lossy, encode_percent, AsciiSet };
+use ::std::fmt;
+
+/// Bytes escaped when writing a percent-encoded body: the fragment
+/// percent-encode set, plus `#` (which would start a fragment) and `%`
+/// (so it isn't mistaken for an escape)
+const BODY_ENCODE_SET: AsciiSet = AsciiSet::FRAGMENT
+	.add(b'#')
+	.add(b'%');
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum ParseError {
+	#[error("not a data: URL")]
+	InvalidScheme,
+	#[error("missing `,` separating the media type and the body")]
+	MissingComma,
+	#[error("invalid base64 body: {0}")]
+	InvalidBase64(#[from] crate::base64::DecodeError)
+}
+
+/// A [MIME type] (media type), with its parameters
+///
+/// [MIME type]: https://mimesniff.spec.whatwg.org/#understanding-mime-types
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaType {
+	/// Type and subtype, lowercased (ex. `text/plain`)
+	essence: String,
+	/// Parameters in order, with lowercased names
+	parameters: Vec<(String, String)>
+}
+
+impl MediaType {
+	/// Creates a media type without parameters. Returns `None` if `essence`
+	/// isn't a valid `type/subtype` pair.
+	pub fn new(essence: &str) -> Option<Self> {
+		let media_type = Self::parse(essence)?;
+		if !media_type.parameters.is_empty() { return None }
+		Some(media_type)
+	}
+
+	/// The default media type of `data:` URLs, `text/plain;charset=US-ASCII`
+	pub fn text_plain_ascii() -> Self {
+		Self {
+			essence: "text/plain".into(),
+			parameters: vec![("charset".into(), "US-ASCII".into())]
+		}
+	}
+
+	/// Parses a media type with the [parse a MIME type] algorithm from the
+	/// WHATWG MIME sniffing standard. Invalid parameters are skipped, and if
+	/// a parameter name appears more than once, only the first is kept.
+	///
+	/// [parse a MIME type]: https://mimesniff.spec.whatwg.org/#parse-a-mime-type
+	pub fn parse(input: &str) -> Option<Self> {
+		let input = trim_http_whitespace(input);
+
+		let (type_, rest) = input.split_once('/')?;
+		if !is_token(type_) { return None }
+
+		let (subtype, mut rest) = rest.split_once(';').map_or((rest, None), |(s, r)| (s, Some(r)));
+		let subtype = subtype.trim_end_matches(is_http_whitespace);
+		if !is_token(subtype) { return None }
+
+		let essence = format!("{type_}/{subtype}").to_ascii_lowercase();
+		let mut parameters = Vec::<(String, String)>::new();
+
+		while let Some(params) = rest {
+			let params = params.trim_start_matches(is_http_whitespace);
+
+			let name_end = params.find([';', '=']).unwrap_or(params.len());
+			let name = params[..name_end].to_ascii_lowercase();
+			let params = &params[name_end..];
+
+			let Some(params) = params.strip_prefix('=') else {
+				// either no value, or reached the end
+				rest = params.strip_prefix(';');
+				continue
+			};
+
+			let value = if let Some(quoted) = params.strip_prefix('"') {
+				let (value, after) = parse_quoted_string(quoted);
+				// anything between the closing quote and the next `;` is ignored
+				rest = after.split_once(';').map(|(_, r)| r);
+				value
+			} else {
+				let (value, after) = params.split_once(';').map_or((params, None), |(v, r)| (v, Some(r)));
+				rest = after;
+				let value = value.trim_end_matches(is_http_whitespace);
+				if value.is_empty() { continue }
+				value.into()
+			};
+
+			if is_token(&name)
+				&& value.chars().all(is_quoted_string_char)
+				&& !parameters.iter().any(|(n, _)| *n == name)
+			{
+				parameters.push((name, value));
+			}
+		}
+
+		Some(Self { essence, parameters })
+	}
+
+	/// Type and subtype, lowercased (ex. `text/plain`)
+	#[inline]
+	pub fn essence(&self) -> &str {
+		&self.essence
+	}
+
+	#[inline]
+	pub fn type_(&self) -> &str {
+		self.essence.split_once('/').map_or(&*self.essence, |(t, _)| t)
+	}
+
+	#[inline]
+	pub fn subtype(&self) -> &str {
+		self.essence.split_once('/').map_or("", |(_, s)| s)
+	}
+
+	#[inline]
+	pub fn parameters(&self) -> &[(String, String)] {
+		&self.parameters
+	}
+
+	/// Value of a parameter, looked up case insensitively
+	pub fn parameter(&self, name: &str) -> Option<&str> {
+		self.parameters.iter()
+			.find(|(n, _)| n.eq_ignore_ascii_case(name))
+			.map(|(_, v)| &**v)
+	}
+
+	/// Sets a parameter, replacing its value if it's already set. Returns
+	/// `false` without changing anything if the name isn't a valid token, or
+	/// the value has chars that can't be in a parameter value.
+	pub fn set_parameter(&mut self, name: &str, value: &str) -> bool {
+		if !is_token(name) || !value.chars().all(is_quoted_string_char) { return false }
+
+		let name = name.to_ascii_lowercase();
+		match self.parameters.iter_mut().find(|(n, _)| *n == name) {
+			Some((_, v)) => { *v = value.into() }
+			None => { self.parameters.push((name, value.into())) }
+		}
+		true
+	}
+
+	/// Builder style version of [`set_parameter`](Self::set_parameter),
+	/// ignoring invalid parameters
+	#[inline]
+	pub fn with_parameter(mut self, name: &str, value: &str) -> Self {
+		self.set_parameter(name, value);
+		self
+	}
+}
+
+impl fmt::Display for MediaType {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.essence)?;
+
+		for (name, value) in &self.parameters {
+			write!(f, ";{name}=")?;
+
+			if !value.is_empty() && is_token(value) {
+				f.write_str(value)?;
+			} else {
+				f.write_str("\"")?;
+				for char in value.chars() {
+					if matches!(char, '"' | '\\') { f.write_str("\\")? }
+					write!(f, "{char}")?;
+				}
+				f.write_str("\"")?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// A parsed `data:` URL, or one to build
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataUrl {
+	pub media_type: MediaType,
+	pub body: Vec<u8>
+}
+
+impl DataUrl {
+	#[inline]
+	pub fn new(media_type: MediaType, body: Vec<u8>) -> Self {
+		Self { media_type, body }
+	}
+
+	/// Parses a `data:` URL. Any fragment (`#...`) is ignored.
+	pub fn parse(url: &str) -> Result<Self, ParseError> {
+		let url = url.trim_matches(|c: char| c <= ' ');
+
+		const SCHEME: &str = "data:";
+		let scheme = url.get(..SCHEME.len()).ok_or(ParseError::InvalidScheme)?;
+		if !scheme.eq_ignore_ascii_case(SCHEME) { return Err(ParseError::InvalidScheme) }
+
+		let input = &url[SCHEME.len()..];
+		let input = input.split_once('#').map_or(input, |(i, _)| i);
+		let input = input.trim_matches(|c: char| c.is_ascii_whitespace());
+
+		let (media_type, body) = input.split_once(',').ok_or(ParseError::MissingComma)?;
+		let mut media_type = media_type.trim_matches(|c: char| c.is_ascii_whitespace());
+
+		let body = decode_percent_lossy(body.as_bytes());
+
+		let body = match strip_base64_marker(media_type) {
+			Some(stripped) => {
+				media_type = stripped;
+				decode_base64_forgiving(&body)?
+			}
+			None => { body }
+		};
+
+		let media_type = if media_type.starts_with(';') {
+			MediaType::parse(&format!("text/plain{media_type}"))
+		} else {
+			MediaType::parse(media_type)
+		};
+		let media_type = media_type.unwrap_or_else(MediaType::text_plain_ascii);
+
+		Ok(Self { media_type, body })
+	}
+
+	/// Writes this as a `data:` URL with a base64 body, which is the most
+	/// compact for binary data
+	pub fn to_url_base64(&self) -> String {
+		format!("data:{};base64,{}", self.media_type, encode_base64(&self.body))
+	}
+
+	/// Writes this as a `data:` URL with a percent-encoded body, which is more
+	/// compact (and readable) for mostly ASCII text
+	pub fn to_url_percent(&self) -> String {
+		format!("data:{},{}", self.media_type, encode_percent(&self.body, &BODY_ENCODE_SET))
+	}
+}
+
+impl fmt::Display for DataUrl {
+	/// Same as [`to_url_base64`](DataUrl::to_url_base64)
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.to_url_base64())
+	}
+}
+
+/// If the media type ends with `;base64` (ignoring case, and allowing spaces
+/// before `base64`), returns the media type with it removed
+fn strip_base64_marker(media_type: &str) -> Option<&str> {
+	const MARKER: &str = "base64";
+
+	let marker_start = media_type.len().checked_sub(MARKER.len())?;
+	let marker = media_type.get(marker_start..)?;
+	if !marker.eq_ignore_ascii_case(MARKER) { return None }
+
+	let before = media_type[..marker_start].trim_end_matches(' ');
+	before.strip_suffix(';')
+}
+
+/// Collects an HTTP quoted string (the opening `"` already removed), returning
+/// the unescaped value and what's left after the closing `"`
+fn parse_quoted_string(input: &str) -> (String, &str) {
+	let mut value = String::new();
+	let mut chars = input.char_indices();
+
+	while let Some((i, char)) = chars.next() {
+		match char {
+			'"' => { return (value, &input[i + 1..]) }
+			'\\' => {
+				match chars.next() {
+					Some((_, escaped)) => { value.push(escaped) }
+					None => {
+						value.push('\\');
+						return (value, "")
+					}
+				}
+			}
+			char => { value.push(char) }
+		}
+	}
+
+	(value, "")
+}
+
+#[inline]
+fn is_http_whitespace(char: char) -> bool {
+	matches!(char, '\t' | '\n' | '\r' | ' ')
+}
+
+#[inline]
+fn trim_http_whitespace(s: &str) -> &str {
+	s.trim_matches(is_http_whitespace)
+}
+
+/// HTTP token code points
+#[inline]
+fn is_token_char(char: char) -> bool {
+	char.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(char)
+}
+
+#[inline]
+fn is_token(s: &str) -> bool {
+	!s.is_empty() && s.chars().all(is_token_char)
+}
+
+/// HTTP quoted-string token code points
+#[inline]
+fn is_quoted_string_char(char: char) -> bool {
+	matches!(char, '\t' | ' '..='~' | '\u{80}'..='\u{ff}')
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn parse() {
+		let url = DataUrl::parse("data:,Hello%2C%20World%21").unwrap();
+		assert_eq!(url.media_type, MediaType::text_plain_ascii());
+		assert_eq!(url.media_type.to_string(), "text/plain;charset=US-ASCII");
+		assert_eq!(url.body, b"Hello, World!");
+
+		let url = DataUrl::parse("data:text/plain;base64,SGVsbG8sIFdvcmxkIQ==").unwrap();
+		assert_eq!(url.media_type.essence(), "text/plain");
+		assert!(url.media_type.parameters().is_empty());
+		assert_eq!(url.body, b"Hello, World!");
+
+		let url = DataUrl::parse(" DATA:Text/HTML;Charset=\"utf-8\" ; BASE64 , PGI+aGk8L2I+ \n#frag").unwrap();
+		assert_eq!(url.media_type.essence(), "text/html");
+		assert_eq!(url.media_type.parameter("charset"), Some("utf-8"));
+		assert_eq!(url.body, b"<b>hi</b>");
+
+		// whitespace and missing padding in the body
+		let url = DataUrl::parse("data:;base64,SGVs bG8%0A").unwrap();
+		assert_eq!(url.media_type.essence(), "text/plain");
+		assert_eq!(url.body, b"Hello");
+
+		let url = DataUrl::parse("data:;charset=utf-8,%E2%9C%93").unwrap();
+		assert_eq!(url.media_type.to_string(), "text/plain;charset=utf-8");
+		assert_eq!(url.body, "✓".as_bytes());
+
+		// invalid media types fall back to the default
+		let url = DataUrl::parse("data:nonsense,abc").unwrap();
+		assert_eq!(url.media_type, MediaType::text_plain_ascii());
+
+		// `base64` not as its own parameter isn't the base64 marker
+		let url = DataUrl::parse("data:text/plain;x=base64,abc").unwrap();
+		assert_eq!(url.media_type.parameter("x"), Some("base64"));
+		assert_eq!(url.body, b"abc");
+
+		// malformed escapes are kept as is
+		let url = DataUrl::parse("data:,100%").unwrap();
+		assert_eq!(url.body, b"100%");
+	}
+
+	#[test]
+	fn parse_errors() {
+		assert!(matches!(DataUrl::parse("http://example.com"), Err(ParseError::InvalidScheme)));
+		assert!(matches!(DataUrl::parse("dat"), Err(ParseError::InvalidScheme)));
+		assert!(matches!(DataUrl::parse("data:text/plain"), Err(ParseError::MissingComma)));
+		assert!(matches!(DataUrl::parse("data:;base64,abcde"), Err(ParseError::InvalidBase64(_))));
+		assert!(matches!(DataUrl::parse("data:;base64,ab=c"), Err(ParseError::InvalidBase64(_))));
+	}
+
+	#[test]
+	fn media_types() {
+		let cases = [
+			("text/html;charset=gbk", Some("text/html;charset=gbk")),
+			("TEXT/HTML;CHARSET=GBK", Some("text/html;charset=GBK")),
+			(" text/html ; charset = gbk", Some("text/html")),
+			("text/html;charset=gbk;charset=windows-1252", Some("text/html;charset=gbk")),
+			("text/html;charset=\"gbk\"", Some("text/html;charset=gbk")),
+			("text/html;charset=\"g\\\"bk\"x;a=b", Some("text/html;charset=\"g\\\"bk\";a=b")),
+			("text/html;charset=\"\"", Some("text/html;charset=\"\"")),
+			("text/html;charset=;a=b", Some("text/html;a=b")),
+			("text/html;;;a", Some("text/html")),
+			("text/html;a=b c", Some("text/html;a=\"b c\"")),
+			("text", None),
+			("text/", None),
+			("/html", None),
+			("te xt/html", None),
+			("text/ht\"ml", None)
+		];
+
+		for (input, expected) in cases {
+			let parsed = MediaType::parse(input).map(|m| m.to_string());
+			assert_eq!(parsed.as_deref(), expected, "{input}");
+		}
+
+		let mut media_type = MediaType::new("Image/PNG").unwrap();
+		assert_eq!(media_type.type_(), "image");
+		assert_eq!(media_type.subtype(), "png");
+		assert!(media_type.set_parameter("Name", "a file.png"));
+		assert!(!media_type.set_parameter("bad name", "x"));
+		assert!(!media_type.set_parameter("x", "new\nline"));
+		assert_eq!(media_type.to_string(), "image/png;name=\"a file.png\"");
+		assert!(MediaType::new("text/plain;charset=utf-8").is_none());
+	}
+
+	#[test]
+	fn round_trip() {
+		let mut rng = thread_rng();
+
+		let media_types = [
+			MediaType::text_plain_ascii(),
+			MediaType::new("application/octet-stream").unwrap(),
+			MediaType::new("text/html").unwrap().with_parameter("charset", "utf-8").with_parameter("q", "a \"b\"")
+		];
+
+		for media_type in media_types {
+			for len in [0usize, 1, 2, 3, 50, 1000] {
+				let mut body = vec![0u8; len];
+				rng.fill(&mut *body);
+				let url = DataUrl::new(media_type.clone(), body);
+
+				assert_eq!(DataUrl::parse(&url.to_url_base64()).unwrap(), url);
+				assert_eq!(DataUrl::parse(&url.to_url_percent()).unwrap(), url);
+				assert_eq!(url.to_string(), url.to_url_base64());
+			}
+		}
+
+		let url = DataUrl::new(MediaType::new("text/plain").unwrap(), b"a #1, 100%".to_vec());
+		assert_eq!(url.to_url_percent(), "data:text/plain,a%20%231,%20100%25");
+	}
+}
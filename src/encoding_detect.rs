@@ -0,0 +1,264 @@
+//! Guessing which encoding some text is in, out of hex, base32, base64
+//! (standard and URL safe), and Z85.
+//!
+//! Every encoding that can decode the input is a candidate. Candidates are
+//! ranked by how likely it is that their encoder would have output the input:
+//! each data char is assumed to be picked uniformly from the encoding's
+//! alphabet, so encodings with smaller alphabets (ex. hex) are much more likely
+//! to have produced input that fits in them than ones with bigger alphabets
+//! that happen to contain all the same chars (ex. base64). Padding chars are
+//! fully determined by the length of the data, so correct padding counts in
+//! favour of an encoding. All encodings are assumed equally likely to begin
+//! with, and the confidences of all candidates add up to 1.
+//!
+//! ASCII whitespace at the start and end of the input is ignored (it's easy to
+//! pick some up when copying text), but whitespace anywhere else is not.
+
+use crate::{ base32, base64, hex, z85 };
+use crate::base64::{ Config, Padding };
+
+/// Base64 configs used for detection. Padding is optional, since it's often
+/// left out when pasting.
+const BASE64: Config = Config::STANDARD.padding(Padding::Optional);
+const BASE64_URL: Config = Config::URL_SAFE.padding(Padding::Optional);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+	/// Hex, in either upper or lower case
+	Hex,
+	/// Base32 with the [RFC 4648] alphabet, with padding (see
+	/// [`decode_base32`](base32::decode_base32))
+	///
+	/// [RFC 4648]: https://datatracker.ietf.org/doc/html/rfc4648#section-6
+	Base32,
+	/// Base64 with the standard alphabet, with or without padding
+	Base64,
+	/// Base64 with the URL and filename safe alphabet, with or without padding
+	Base64Url,
+	/// Z85, with this crate's nonstandard padding (see [`z85`](crate::z85))
+	Z85
+}
+
+impl Encoding {
+	/// All encodings, in the order they are ranked in when tied
+	pub const ALL: [Encoding; 5] = [
+		Encoding::Hex,
+		Encoding::Base32,
+		Encoding::Base64,
+		Encoding::Base64Url,
+		Encoding::Z85
+	];
+
+	#[inline]
+	pub fn name(&self) -> &'static str {
+		match self {
+			Encoding::Hex => { "hex" }
+			Encoding::Base32 => { "base32" }
+			Encoding::Base64 => { "base64" }
+			Encoding::Base64Url => { "base64url" }
+			Encoding::Z85 => { "z85" }
+		}
+	}
+
+	/// Decodes the given bytes with this encoding. Unlike detection, whitespace
+	/// at the start and end is not ignored.
+	pub fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+		Ok(match self {
+			Encoding::Hex => { hex::decode_hex(bytes)? }
+			Encoding::Base32 => { base32::decode_base32(bytes)? }
+			Encoding::Base64 => { base64::decode_base64_with(bytes, &BASE64)? }
+			Encoding::Base64Url => { base64::decode_base64_with(bytes, &BASE64_URL)? }
+			Encoding::Z85 => { z85::decode_z85(bytes)? }
+		})
+	}
+
+	/// Log of the probability of this encoding outputting `bytes` (given its
+	/// length), or `None` if it can't decode `bytes`
+	fn log_likelihood(&self, bytes: &[u8]) -> Option<f64> {
+		let valid = match self {
+			Encoding::Hex => { hex::validate_hex(bytes).is_ok() }
+			Encoding::Base32 => { base32::validate_base32(bytes).is_ok() }
+			Encoding::Base64 => { base64::validate_base64_with(bytes, &BASE64).is_ok() }
+			Encoding::Base64Url => { base64::validate_base64_with(bytes, &BASE64_URL).is_ok() }
+			Encoding::Z85 => { z85::validate_z85(bytes).is_ok() }
+		};
+		if !valid { return None }
+
+		let (data_chars, alphabet_len) = match self {
+			Encoding::Hex => {
+				// the encoder outputs one case or the other, not a mix
+				let lower = bytes.iter().any(u8::is_ascii_lowercase);
+				let upper = bytes.iter().any(u8::is_ascii_uppercase);
+				let alphabet_len = if lower && upper { 22 } else { hex::TABLE_ENCODER_LEN };
+				(bytes.len(), alphabet_len)
+			}
+			Encoding::Base32 => {
+				(bytes.len() - padding_len(bytes), base32::TABLE_ENCODER_LEN)
+			}
+			Encoding::Base64 | Encoding::Base64Url => {
+				(bytes.len() - padding_len(bytes), base64::TABLE_ENCODER_LEN)
+			}
+			Encoding::Z85 => {
+				// the padding marker can only be one of 4 chars
+				let (data_chars, marker) = match bytes.len() % z85::STRING_FRAME_LEN {
+					1 => { (bytes.len() - 1, (z85::BINARY_FRAME_LEN as f64).ln()) }
+					_ => { (bytes.len(), 0.0) }
+				};
+				return Some(-(data_chars as f64) * (z85::TABLE_ENCODER_LEN as f64).ln() - marker)
+			}
+		};
+
+		Some(-(data_chars as f64) * (alphabet_len as f64).ln())
+	}
+}
+
+impl ::std::fmt::Display for Encoding {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+/// An encoding the input could be in, and how likely it is (between 0 and 1)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+	pub encoding: Encoding,
+	pub confidence: f64
+}
+
+/// Returns every encoding that can decode the given bytes, most likely first.
+/// Returns no candidates for empty input (or input that is only whitespace),
+/// since every encoding decodes that the same.
+pub fn detect_encoding(bytes: &[u8]) -> Vec<Candidate> {
+	let bytes = trim_whitespace(bytes);
+	if bytes.is_empty() { return Vec::new() }
+
+	let log_likelihoods = Encoding::ALL.iter()
+		.filter_map(|encoding| Some((*encoding, encoding.log_likelihood(bytes)?)))
+		.collect::<Vec<_>>();
+
+	// normalise in log space first, so long inputs don't underflow to 0
+	let max = log_likelihoods.iter()
+		.map(|(_, l)| *l)
+		.fold(f64::NEG_INFINITY, f64::max);
+	let total = log_likelihoods.iter()
+		.map(|(_, l)| (l - max).exp())
+		.sum::<f64>();
+
+	let mut candidates = log_likelihoods.into_iter()
+		.map(|(encoding, l)| Candidate { encoding, confidence: (l - max).exp() / total })
+		.collect::<Vec<_>>();
+
+	// stable sort, so ties stay in the order of `Encoding::ALL`
+	candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+	candidates
+}
+
+/// Detects the encoding of the given bytes, and decodes them with the most
+/// likely candidate. Returns `None` if no encoding can decode them (or if the
+/// input is empty).
+pub fn decode_detected(bytes: &[u8]) -> Option<(Candidate, Vec<u8>)> {
+	let best = *detect_encoding(bytes).first()?;
+
+	// candidates are only returned if they can decode the input
+	let decoded = best.encoding.decode(trim_whitespace(bytes)).ok()?;
+	Some((best, decoded))
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError {
+	#[error(transparent)]
+	Hex(#[from] hex::DecodeError),
+	#[error(transparent)]
+	Base32(#[from] base32::DecodeError),
+	#[error(transparent)]
+	Base64(#[from] base64::DecodeError),
+	#[error(transparent)]
+	Z85(#[from] z85::DecodeError)
+}
+
+/// Trims ASCII whitespace from the start and end
+#[inline]
+fn trim_whitespace(bytes: &[u8]) -> &[u8] {
+	let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+	let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace()).map(|i| i + 1).unwrap_or(start);
+	&bytes[start..end]
+}
+
+/// Amount of `=` at the end
+#[inline]
+fn padding_len(bytes: &[u8]) -> usize {
+	bytes.iter().rev().take_while(|b| **b == b'=').count()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	fn best(bytes: &[u8]) -> Encoding {
+		detect_encoding(bytes)[0].encoding
+	}
+
+	#[test]
+	fn obvious() {
+		assert_eq!(best(b"deadbeef"), Encoding::Hex);
+		assert_eq!(best(b"DEADBEEF0123"), Encoding::Hex);
+		assert_eq!(best(b"MZXW6YTBOI======"), Encoding::Base32);
+		assert_eq!(best(b"Zm9vYmFy"), Encoding::Base64);
+		assert_eq!(best(b"Zm9vYmE="), Encoding::Base64);
+		assert_eq!(best(b"-_-_-_-_"), Encoding::Base64Url);
+		assert_eq!(best(b"Hello.Worl"), Encoding::Z85);
+		assert_eq!(best(b"  deadbeef\n"), Encoding::Hex);
+	}
+
+	#[test]
+	fn candidates() {
+		// valid in every encoding except base32
+		let candidates = detect_encoding(b"0123456789");
+		let encodings = candidates.iter().map(|c| c.encoding).collect::<Vec<_>>();
+		assert_eq!(encodings, [Encoding::Hex, Encoding::Base64, Encoding::Base64Url, Encoding::Z85]);
+
+		let total = candidates.iter().map(|c| c.confidence).sum::<f64>();
+		assert!((total - 1.0).abs() < 1e-9);
+		assert!(candidates[0].confidence > 0.99);
+		// same alphabet size and no padding, so it's a tie
+		assert_eq!(candidates[1].confidence, candidates[2].confidence);
+
+		assert!(detect_encoding(b"").is_empty());
+		assert!(detect_encoding(b" \n").is_empty());
+		assert!(detect_encoding(b"not valid in any!").is_empty());
+		assert!(decode_detected(b"not valid in any!").is_none());
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		for _ in 0..100 {
+			let mut bytes = vec![0u8; rng.gen_range(32..64)];
+			rng.fill(&mut *bytes);
+
+			for (encoding, encoded) in [
+				(Encoding::Hex, hex::encode_hex(&bytes)),
+				(Encoding::Hex, hex::encode_hex_upper(&bytes)),
+				(Encoding::Base32, base32::encode_base32(&bytes)),
+				(Encoding::Z85, z85::encode_z85(&bytes))
+			] {
+				let (candidate, decoded) = decode_detected(encoded.as_bytes()).unwrap();
+				assert_eq!(candidate.encoding, encoding);
+				assert_eq!(decoded, bytes);
+			}
+
+			// these can fit in each other's alphabets if the
+			// bytes happen to not use + / - or _ at all
+			let (candidate, decoded) = decode_detected(base64::encode_base64(&bytes).as_bytes()).unwrap();
+			assert!(matches!(candidate.encoding, Encoding::Base64 | Encoding::Base64Url));
+			assert_eq!(decoded, bytes);
+
+			let encoded = base64::encode_base64_with(&bytes, &Config::URL_SAFE_NO_PAD);
+			let (candidate, decoded) = decode_detected(encoded.as_bytes()).unwrap();
+			assert!(matches!(candidate.encoding, Encoding::Base64 | Encoding::Base64Url));
+			assert_eq!(decoded, bytes);
+		}
+	}
+}
@@ -1,80 +1,487 @@
 use ::std::{ slice, ptr };
+use ::std::mem::{ ManuallyDrop, MaybeUninit };
+use ::std::io::IoSlice;
 
-/// In debug mode, keeps track of the amount of bytes written, and asserts
-/// preconditions like not writing over capacity and having all preallocated
-/// capacity filled. However, in release mode, its just a wrapper around a vec,
-/// its ptr, raw ptr operations ([`ptr::copy_nonoverlapping`] etc), and a method
-/// that unsafetly sets the len of the vec before unwrapping it.
+pub mod dispatch;
+
+#[cfg(feature = "bytes")]
+pub mod bytes;
+
+#[cfg(feature = "tokio")]
+pub mod stream;
+
+/// Raw pointer based writing into preallocated buffers, implemented by
+/// [`UnsafeBufWriteGuard`] and [`UnsafeBufWriteSlice`], so codecs can write
+/// into either the whole output buffer or a part of it with the same code.
+pub trait UnsafeBufWrite {
+	unsafe fn write_bytes_const<const N: usize>(&mut self, src: *const u8);
+
+	unsafe fn write_bytes(&mut self, src: *const u8, n: usize);
+
+	/// Make sure to also call `add_byte_count` function afterwards, to keep
+	/// proper track of the ptr inside.
+	unsafe fn as_ptr(&mut self) -> *mut u8;
+
+	/// Like [`as_ptr`](Self::as_ptr), for writing up to `n` bytes through
+	/// the pointer (ex. from SIMD code). When [`CHECKED`], asserts there's
+	/// room for them first, since writes through the pointer aren't checked.
+	unsafe fn as_ptr_for(&mut self, n: usize) -> *mut u8;
+
+	unsafe fn add_byte_count(&mut self, n: usize);
+}
+
+/// Whether writes are bounds checked. They always are in debug mode, and in
+/// release mode only with the `checked-codecs` feature, since it costs a
+/// comparison on every write.
+const CHECKED: bool = cfg!(any(debug_assertions, feature = "checked-codecs"));
+
+/// Writes into a preallocated buffer of [`MaybeUninit`] bytes through a raw
+/// pointer ([`ptr::copy_nonoverlapping`] etc). Writes are bounds checked when
+/// [`CHECKED`]. Regardless, [`into_full_vec`](Self::into_full_vec) always
+/// checks the whole buffer has been written, so it never hands out
+/// uninitialised bytes.
 pub struct UnsafeBufWriteGuard {
-	vec: Vec<u8>,
-	ptr: *mut u8,
-	#[cfg(debug_assertions)]
-	bytes_written: usize
+	/// Length of this is the amount of bytes to be written, which may be less
+	/// than the capacity, if the allocator gave us more than asked for
+	buf: Vec<MaybeUninit<u8>>,
+	ptr: *mut u8
 }
 
 impl UnsafeBufWriteGuard {
 	#[inline(always)]
 	pub fn with_capacity(capacity: usize) -> Self {
-		let mut vec = Vec::with_capacity(capacity);
-		let ptr = vec.as_mut_ptr();
+		let mut buf = Vec::with_capacity(capacity);
+		// SAFETY: `MaybeUninit` doesn't need initialising, and we just
+		// allocated `capacity` elements
+		unsafe { buf.set_len(capacity) }
+		let ptr = buf.as_mut_ptr() as *mut u8;
 
-		#[cfg(debug_assertions)]
-		assert_eq!(vec.capacity(), capacity);
+		Self { buf, ptr }
+	}
+
+	/// Amount of bytes left to write
+	#[inline(always)]
+	fn remaining(&self) -> usize {
+		let end = self.buf.as_ptr_range().end as *const u8;
+		// SAFETY: `ptr` is always within `buf` (or one past its end)
+		unsafe { end.offset_from(self.ptr) as usize }
+	}
+
+	/// Splits the next part of the buffer into one [`UnsafeBufWriteSlice`] per
+	/// chunk, with lengths given by `output_len`, and calls `f` with each chunk
+	/// and its slice on its own (scoped) thread. The last chunk is processed on
+	/// the calling thread. Returns the first error returned by `f`, if any.
+	///
+	/// # Safety
+	///
+	/// The sum of all the output lengths must fit in the remaining capacity,
+	/// and `f` must fill its slice completely (unless it returns an error).
+	pub unsafe fn write_parallel<'h, L, F, E>(
+		&mut self,
+		chunks: &[&'h [u8]],
+		output_len: L,
+		f: F
+	) -> Result<(), E>
+	where
+		L: Fn(&'h [u8]) -> usize,
+		F: Fn(&'h [u8], &mut UnsafeBufWriteSlice) -> Result<(), E> + Sync,
+		E: Send
+	{
+		let Some((last, rest)) = chunks.split_last() else { return Ok(()) };
+
+		let lens = chunks.iter().map(|chunk| output_len(chunk)).collect::<Vec<_>>();
+		let total_len = lens.iter().sum::<usize>();
+		if CHECKED { assert!(total_len <= self.remaining(), "parallel write is within bounds") }
 
-		Self {
-			vec,
-			ptr,
-			#[cfg(debug_assertions)]
-			bytes_written: 0
+		let mut slices = Vec::with_capacity(chunks.len());
+		let mut offset = 0;
+		for len in lens {
+			slices.push(UnsafeBufWriteSlice::new(self.ptr.add(offset), len));
+			offset += len;
 		}
+
+		let mut slices = slices.into_iter();
+		let f = &f;
+
+		let result = ::std::thread::scope(|scope| {
+			let handles = rest.iter()
+				.zip(&mut slices)
+				.map(|(chunk, mut slice)| scope.spawn(move || {
+					f(chunk, &mut slice)?;
+					slice.assert_is_full();
+					Ok(())
+				}))
+				.collect::<Vec<_>>();
+
+			// SAFETY: one slice was made per chunk, and only `rest.len()` of them
+			// have been taken by the zip above
+			let mut last_slice = slices.next().unwrap_unchecked();
+			let last_result = f(last, &mut last_slice);
+			if last_result.is_ok() { last_slice.assert_is_full() }
+
+			handles.into_iter()
+				.try_for_each(|handle| match handle.join() {
+					Ok(result) => { result }
+					Err(panic) => { ::std::panic::resume_unwind(panic) }
+				})
+				.and(last_result)
+		});
+
+		self.add_byte_count(total_len);
+		result
 	}
 
+	/// Unwraps the written bytes. Panics if not all of the buffer was written.
 	#[inline(always)]
-	pub unsafe fn write_bytes_const<const N: usize>(&mut self, src: *const u8) {
-		#[cfg(debug_assertions)] {
-			self.bytes_written += N;
-			assert!(self.bytes_written <= self.vec.capacity())
-		}
+	pub fn into_full_vec(self) -> Vec<u8> {
+		assert!(self.remaining() == 0, "all bytes in buffer were written");
+
+		let mut buf = ManuallyDrop::new(self.buf);
+		let (ptr, len, capacity) = (buf.as_mut_ptr(), buf.len(), buf.capacity());
+
+		// SAFETY: every byte has been written (checked above), and
+		// `MaybeUninit<u8>` has the same layout as `u8`
+		unsafe { Vec::from_raw_parts(ptr as *mut u8, len, capacity) }
+	}
+}
+
+impl UnsafeBufWrite for UnsafeBufWriteGuard {
+	#[inline(always)]
+	unsafe fn write_bytes_const<const N: usize>(&mut self, src: *const u8) {
+		if CHECKED { assert!(N <= self.remaining(), "write is within bounds") }
 
 		ptr::copy_nonoverlapping(src, self.ptr, N);
 		self.ptr = self.ptr.add(N);
 	}
 
 	#[inline(always)]
-	pub unsafe fn write_bytes(&mut self, src: *const u8, n: usize) {
-		#[cfg(debug_assertions)] {
-			self.bytes_written += n;
-			assert!(self.bytes_written <= self.vec.capacity())
-		}
+	unsafe fn write_bytes(&mut self, src: *const u8, n: usize) {
+		if CHECKED { assert!(n <= self.remaining(), "write is within bounds") }
 
 		ptr::copy_nonoverlapping(src, self.ptr, n);
 		self.ptr = self.ptr.add(n);
 	}
 
-	/// Make sure to also call `add_byte_count` function afterwards, to keep
-	/// proper track of the ptr inside.
 	#[inline(always)]
-	pub unsafe fn as_ptr(&mut self) -> *mut u8 {
+	unsafe fn as_ptr(&mut self) -> *mut u8 {
 		self.ptr
 	}
 
 	#[inline(always)]
-	pub unsafe fn add_byte_count(&mut self, n: usize) {
-		#[cfg(debug_assertions)] {
-			self.bytes_written += n;
-			assert!(self.bytes_written <= self.vec.capacity())
-		}
+	unsafe fn as_ptr_for(&mut self, n: usize) -> *mut u8 {
+		if CHECKED { assert!(n <= self.remaining(), "write is within bounds") }
+
+		self.ptr
+	}
+
+	#[inline(always)]
+	unsafe fn add_byte_count(&mut self, n: usize) {
+		if CHECKED { assert!(n <= self.remaining(), "write is within bounds") }
 
 		self.ptr = self.ptr.add(n);
 	}
+}
 
+/// A part of the buffer of an [`UnsafeBufWriteGuard`], created by
+/// [`UnsafeBufWriteGuard::write_parallel`], or a writer over a caller provided
+/// buffer (ex. for decoding in place). Writes are bounds checked the same way
+/// the guard's are.
+pub struct UnsafeBufWriteSlice {
+	ptr: *mut u8,
+	end: *mut u8
+}
+
+// SAFETY: slices are only created over disjoint parts of a buffer, so
+// sending one to another thread can't cause a data race
+unsafe impl Send for UnsafeBufWriteSlice {}
+
+impl UnsafeBufWriteSlice {
+	/// # Safety
+	///
+	/// `ptr` must be valid for writes of `len` bytes for as long as this
+	/// slice is used
 	#[inline(always)]
-	pub unsafe fn into_full_vec(mut self) -> Vec<u8> {
-		#[cfg(debug_assertions)]
-		assert!(self.bytes_written == self.vec.capacity());
+	pub unsafe fn new(ptr: *mut u8, len: usize) -> Self {
+		Self { ptr, end: ptr.add(len) }
+	}
+
+	/// Amount of bytes left to write
+	#[inline(always)]
+	fn remaining(&self) -> usize {
+		// SAFETY: `ptr` is always within the slice (or one past its end)
+		unsafe { self.end.offset_from(self.ptr) as usize }
+	}
+
+	/// Panics if not all of the slice was written. Unlike the bounds checks on
+	/// writes, this is always checked.
+	#[inline(always)]
+	pub fn assert_is_full(&self) {
+		assert!(self.remaining() == 0, "all bytes in slice were written");
+	}
+}
+
+impl UnsafeBufWrite for UnsafeBufWriteSlice {
+	#[inline(always)]
+	unsafe fn write_bytes_const<const N: usize>(&mut self, src: *const u8) {
+		if CHECKED { assert!(N <= self.remaining(), "write is within bounds") }
+
+		ptr::copy_nonoverlapping(src, self.ptr, N);
+		self.ptr = self.ptr.add(N);
+	}
+
+	#[inline(always)]
+	unsafe fn write_bytes(&mut self, src: *const u8, n: usize) {
+		if CHECKED { assert!(n <= self.remaining(), "write is within bounds") }
+
+		ptr::copy_nonoverlapping(src, self.ptr, n);
+		self.ptr = self.ptr.add(n);
+	}
+
+	#[inline(always)]
+	unsafe fn as_ptr(&mut self) -> *mut u8 {
+		self.ptr
+	}
+
+	#[inline(always)]
+	unsafe fn as_ptr_for(&mut self, n: usize) -> *mut u8 {
+		if CHECKED { assert!(n <= self.remaining(), "write is within bounds") }
+
+		self.ptr
+	}
+
+	#[inline(always)]
+	unsafe fn add_byte_count(&mut self, n: usize) {
+		if CHECKED { assert!(n <= self.remaining(), "write is within bounds") }
 
-		self.vec.set_len(self.vec.capacity());
-		self.vec
+		self.ptr = self.ptr.add(n);
+	}
+}
+
+/// Throws away everything written to it. Used to run decoders for validation
+/// only, without allocating anywhere to put the output. It has no buffer, so
+/// [`as_ptr`](UnsafeBufWrite::as_ptr) must not be used with it (which is fine
+/// for the decoders, as they only write through the `write_bytes` functions).
+pub struct UnsafeBufWriteDiscard;
+
+impl UnsafeBufWrite for UnsafeBufWriteDiscard {
+	#[inline(always)]
+	unsafe fn write_bytes_const<const N: usize>(&mut self, _src: *const u8) {}
+
+	#[inline(always)]
+	unsafe fn write_bytes(&mut self, _src: *const u8, _n: usize) {}
+
+	#[inline(always)]
+	unsafe fn as_ptr(&mut self) -> *mut u8 {
+		unreachable!("UnsafeBufWriteDiscard has no buffer to point into")
+	}
+
+	#[inline(always)]
+	unsafe fn as_ptr_for(&mut self, _n: usize) -> *mut u8 {
+		unreachable!("UnsafeBufWriteDiscard has no buffer to point into")
+	}
+
+	#[inline(always)]
+	unsafe fn add_byte_count(&mut self, _n: usize) {}
+}
+
+/// Minimum amount of input bytes for each thread when encoding or decoding
+/// in parallel. Inputs too small to give at least two threads this much work
+/// are processed on the calling thread instead, since spawning threads
+/// would cost more than it saves.
+pub const PARALLEL_MIN_CHUNK_LEN: usize = 1024 * 1024;
+
+/// Amount of threads the `*_parallel` functions split their work across,
+/// which is the [available parallelism](::std::thread::available_parallelism)
+/// (or 1, if that can't be determined)
+#[inline]
+pub fn available_threads() -> usize {
+	::std::thread::available_parallelism()
+		.map(|n| n.get())
+		.unwrap_or(1)
+}
+
+/// Splits `bytes` into chunks for parallel processing, one per thread (out of
+/// `threads`, but with no less than [`PARALLEL_MIN_CHUNK_LEN`] bytes each).
+/// All chunks except the last are a multiple of `frame_len`. Returns `None` if
+/// the input isn't big enough to be worth splitting.
+pub fn parallel_chunks(bytes: &[u8], frame_len: usize, threads: usize) -> Option<Vec<&[u8]>> {
+	let threads = threads.min(bytes.len() / PARALLEL_MIN_CHUNK_LEN);
+	if threads < 2 { return None }
+
+	let frames = bytes.len() / frame_len;
+	// round up, so the last chunk is the smallest
+	let chunk_len = ((frames + threads - 1) / threads) * frame_len;
+
+	let mut chunks = bytes.chunks(chunk_len).collect::<Vec<_>>();
+
+	// a leftover partial frame would get its own chunk if the last chunk
+	// was exactly full, so merge it back in to keep the partial frame at the
+	// end of the last chunk
+	if chunks.len() > 1 && chunks.last().map(|c| c.len() < frame_len).unwrap_or(false) {
+		let partial = chunks.pop().unwrap();
+		let last = chunks.pop().unwrap();
+		// SAFETY: `last` and `partial` are next to each other in `bytes`
+		chunks.push(unsafe { slice::from_raw_parts(last.as_ptr(), last.len() + partial.len()) });
+	}
+
+	Some(chunks)
+}
+
+/// Input made of several byte slices, which encoders treat as if they were
+/// all joined together (without actually joining them). Implemented for slices
+/// of [`IoSlice`] and `&[u8]`, and for the string pool's
+/// [`SlicesWrap`](crate::string_pool::pool::SlicesWrap) when that feature
+/// is enabled.
+pub trait VectoredInput {
+	/// Calls `f` with every slice, in order
+	fn for_each_slice<F: FnMut(&[u8])>(&self, f: F);
+
+	/// Total amount of bytes in all the slices
+	#[inline]
+	fn total_len(&self) -> usize {
+		let mut len = 0;
+		self.for_each_slice(|slice| len += slice.len());
+		len
+	}
+}
+
+impl VectoredInput for [IoSlice<'_>] {
+	#[inline]
+	fn for_each_slice<F: FnMut(&[u8])>(&self, f: F) {
+		self.iter().map(|slice| &**slice).for_each(f)
+	}
+}
+
+impl VectoredInput for [&[u8]] {
+	#[inline]
+	fn for_each_slice<F: FnMut(&[u8])>(&self, f: F) {
+		self.iter().copied().for_each(f)
+	}
+}
+
+#[cfg(feature = "string-pool")]
+impl VectoredInput for crate::string_pool::pool::SlicesWrap<'_> {
+	#[inline]
+	fn for_each_slice<F: FnMut(&[u8])>(&self, f: F) {
+		self.0.for_each_slice(f)
+	}
+}
+
+/// Splits vectored input into runs of whole frames of `N` bytes, calling `f`
+/// with each run in order. Frames that straddle the boundary between two
+/// slices are copied into a temporary frame and passed on their own. Every
+/// run passed to `f` is a multiple of `N` bytes long, except for the last,
+/// which is the leftover partial frame (if there is one).
+pub fn for_each_frame_run<const N: usize, V, F>(input: &V, mut f: F)
+where
+	V: VectoredInput + ?Sized,
+	F: FnMut(&[u8])
+{
+	let mut pending = [0u8; N];
+	let mut pending_len = 0;
+
+	input.for_each_slice(|mut slice| {
+		if pending_len > 0 {
+			let take = (N - pending_len).min(slice.len());
+			pending[pending_len..pending_len + take].copy_from_slice(&slice[..take]);
+			pending_len += take;
+			slice = &slice[take..];
+
+			if pending_len < N { return }
+			f(&pending);
+		}
+
+		let whole_len = slice.len() - (slice.len() % N);
+		let (whole, rest) = slice.split_at(whole_len);
+		if !whole.is_empty() { f(whole) }
+
+		pending[..rest.len()].copy_from_slice(rest);
+		pending_len = rest.len();
+	});
+
+	if pending_len > 0 { f(&pending[..pending_len]) }
+}
+
+/// A fixed length string stored inline (ie. on the stack), returned by the
+/// `encode_*_array` functions. All `N` bytes are always part of the string.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArrayString<const N: usize> {
+	bytes: [u8; N]
+}
+
+impl<const N: usize> ArrayString<N> {
+	/// # Safety
+	///
+	/// `bytes` must be valid UTF-8
+	#[inline]
+	pub unsafe fn from_utf8_unchecked(bytes: [u8; N]) -> Self {
+		debug_assert!(::std::str::from_utf8(&bytes).is_ok(), "bytes are valid utf-8");
+		Self { bytes }
+	}
+
+	#[inline]
+	pub fn as_str(&self) -> &str {
+		// SAFETY: invariant of this struct
+		unsafe { ::std::str::from_utf8_unchecked(&self.bytes) }
+	}
+
+	#[inline]
+	pub fn as_bytes(&self) -> &[u8; N] {
+		&self.bytes
+	}
+
+	#[inline]
+	pub fn into_bytes(self) -> [u8; N] {
+		self.bytes
+	}
+}
+
+impl<const N: usize> ::std::ops::Deref for ArrayString<N> {
+	type Target = str;
+
+	#[inline]
+	fn deref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl<const N: usize> AsRef<str> for ArrayString<N> {
+	#[inline]
+	fn as_ref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl<const N: usize> AsRef<[u8]> for ArrayString<N> {
+	#[inline]
+	fn as_ref(&self) -> &[u8] {
+		&self.bytes
+	}
+}
+
+impl<const N: usize> PartialEq<str> for ArrayString<N> {
+	#[inline]
+	fn eq(&self, other: &str) -> bool {
+		self.as_str() == other
+	}
+}
+
+impl<const N: usize> PartialEq<&str> for ArrayString<N> {
+	#[inline]
+	fn eq(&self, other: &&str) -> bool {
+		self.as_str() == *other
+	}
+}
+
+impl<const N: usize> ::std::fmt::Debug for ArrayString<N> {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+		::std::fmt::Debug::fmt(self.as_str(), f)
+	}
+}
+
+impl<const N: usize> ::std::fmt::Display for ArrayString<N> {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+		::std::fmt::Display::fmt(self.as_str(), f)
 	}
 }
 
@@ -158,3 +565,104 @@ impl<'h, const N: usize> ChunkedSlice<'h, N> {
 		assert!(self.bytes.is_empty(), "all bytes were consumed");
 	}
 }
+
+/// Test helpers for the codecs' `vectored` tests
+#[cfg(test)]
+pub mod test_utils {
+	use ::rand::Rng;
+
+	/// Splits `bytes` into slices at up to 5 random points (so some slices
+	/// may be empty), to make sure frames get split across slices
+	pub fn random_slices<'h, R: Rng>(bytes: &'h [u8], rng: &mut R) -> Vec<&'h [u8]> {
+		let mut cuts = (0..rng.gen_range(0..6)).map(|_| rng.gen_range(0..=bytes.len())).collect::<Vec<_>>();
+		cuts.sort();
+
+		let mut slices = Vec::new();
+		let mut start = 0;
+		for cut in cuts.into_iter().chain([bytes.len()]) {
+			slices.push(&bytes[start..cut]);
+			start = cut;
+		}
+		slices
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn guard_full() {
+		let mut guard = UnsafeBufWriteGuard::with_capacity(6);
+		unsafe {
+			guard.write_bytes_const::<2>(b"ab".as_ptr());
+			guard.write_bytes(b"cd".as_ptr(), 2);
+			*guard.as_ptr() = b'e';
+			*guard.as_ptr().add(1) = b'f';
+			guard.add_byte_count(2);
+		}
+		assert_eq!(guard.into_full_vec(), b"abcdef");
+	}
+
+	#[test]
+	#[should_panic = "all bytes in buffer were written"]
+	fn guard_not_full() {
+		let mut guard = UnsafeBufWriteGuard::with_capacity(4);
+		unsafe { guard.write_bytes(b"abc".as_ptr(), 3) }
+		guard.into_full_vec();
+	}
+
+	// bounds checks are always on in debug mode, and the `checked-codecs`
+	// feature turns them on in release mode
+	#[test]
+	#[cfg_attr(any(debug_assertions, feature = "checked-codecs"), should_panic = "write is within bounds")]
+	#[cfg_attr(not(any(debug_assertions, feature = "checked-codecs")), ignore = "bounds checks are off")]
+	fn guard_out_of_bounds() {
+		let mut guard = UnsafeBufWriteGuard::with_capacity(2);
+		unsafe { guard.write_bytes(b"abc".as_ptr(), 3) }
+	}
+
+	#[test]
+	#[should_panic = "all bytes in slice were written"]
+	fn slice_not_full() {
+		let mut buf = [0u8; 4];
+		let mut slice = unsafe { UnsafeBufWriteSlice::new(buf.as_mut_ptr(), buf.len()) };
+		unsafe { slice.write_bytes(b"abc".as_ptr(), 3) }
+		slice.assert_is_full();
+	}
+
+	#[test]
+	#[cfg_attr(any(debug_assertions, feature = "checked-codecs"), should_panic = "write is within bounds")]
+	#[cfg_attr(not(any(debug_assertions, feature = "checked-codecs")), ignore = "bounds checks are off")]
+	fn slice_out_of_bounds() {
+		let mut buf = [0u8; 4];
+		let mut slice = unsafe { UnsafeBufWriteSlice::new(buf.as_mut_ptr(), 2) };
+		unsafe { slice.write_bytes(b"abc".as_ptr(), 3) }
+	}
+
+	#[test]
+	#[cfg_attr(any(debug_assertions, feature = "checked-codecs"), should_panic = "write is within bounds")]
+	#[cfg_attr(not(any(debug_assertions, feature = "checked-codecs")), ignore = "bounds checks are off")]
+	fn ptr_for_out_of_bounds() {
+		let mut guard = UnsafeBufWriteGuard::with_capacity(2);
+		unsafe { guard.as_ptr_for(2) };
+		unsafe { guard.as_ptr_for(3) };
+	}
+
+	#[test]
+	fn parallel_chunks_split() {
+		let bytes = vec![0u8; (PARALLEL_MIN_CHUNK_LEN * 4) + 2];
+
+		assert!(parallel_chunks(&bytes, 4, 1).is_none());
+		assert!(parallel_chunks(&bytes[..PARALLEL_MIN_CHUNK_LEN * 2 - 1], 4, 4).is_none());
+
+		let chunks = parallel_chunks(&bytes, 4, 4).unwrap();
+		assert_eq!(chunks.len(), 4);
+		assert!(chunks[..3].iter().all(|chunk| chunk.len() % 4 == 0));
+		assert!(chunks.last().unwrap().len() % 4 == 2, "partial frame is at the end of the last chunk");
+		assert_eq!(chunks.iter().map(|chunk| chunk.len()).sum::<usize>(), bytes.len());
+
+		// never more threads than chunks with the minimum length
+		assert_eq!(parallel_chunks(&bytes, 4, 64).unwrap().len(), 4);
+	}
+}
@@ -0,0 +1,225 @@
+//! Encoding from and decoding from any [`Buf`] into a [`BytesMut`], generic
+//! over the codec used (see [`BufCodec`]).
+//!
+//! The output is appended to the end of the [`BytesMut`], which has space
+//! reserved for it once up front. The input [`Buf`] doesn't have to be
+//! contiguous; frames that are split across two chunks are copied out into a
+//! temporary frame, and everything else is processed in place in the chunks.
+
+use super::{ UnsafeBufWrite, UnsafeBufWriteSlice };
+use ::bytes::{ Buf, BytesMut };
+use ::std::convert::Infallible;
+
+/// Largest frame length of any codec, used for the temporary frames
+const MAX_FRAME_LEN: usize = 8;
+
+/// A codec that can be used with the functions in this module
+pub trait BufCodec {
+	type Error;
+
+	/// Amount of bytes encoded together as one frame
+	const BINARY_FRAME_LEN: usize;
+
+	/// Amount of encoded bytes decoded together as one frame
+	const STRING_FRAME_LEN: usize;
+
+	/// Exact amount of bytes that encoding `bytes_len` bytes outputs
+	fn encoded_len(&self, bytes_len: usize) -> usize;
+
+	/// # Safety
+	///
+	/// `dest` must have `encoded_len(bytes.len())` bytes of capacity left.
+	/// If `bytes` isn't a whole number of frames, it must be the end of
+	/// the input (it'll get padded).
+	unsafe fn encode_into(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice);
+
+	/// Decodes whole frames, that are not the last frame of the input
+	///
+	/// # Safety
+	///
+	/// `bytes` must only contain whole frames, and `dest` must have enough
+	/// capacity left to hold all of them decoded
+	unsafe fn decode_frames(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) -> Result<(), Self::Error>;
+
+	/// Decodes the end of the input, including the last frame and any
+	/// padding, checking the length is valid
+	fn decode_end(&self, bytes: &[u8]) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Encodes everything remaining in `src`, appending it to `dest`
+pub fn encode_buf<C: BufCodec, B: Buf>(codec: &C, mut src: B, dest: &mut BytesMut) {
+	let len = src.remaining();
+	let encoded_len = codec.encoded_len(len);
+
+	let result = unsafe {
+		// SAFETY: we reserve exactly the encoded length of all of `src`, and only
+		// the last run passed to us (if any) can be a partial frame
+		write_reserved(dest, encoded_len, |out| {
+			for_each_frame_run(&mut src, len, C::BINARY_FRAME_LEN, |run| {
+				codec.encode_into(run, out);
+				Ok::<_, Infallible>(())
+			})
+		})
+	};
+
+	match result {
+		Ok(()) => {}
+		Err(never) => { match never {} }
+	}
+}
+
+/// Decodes everything remaining in `src`, appending it to `dest`. If an error
+/// is returned, `dest` is left as it was, but an unspecified amount of `src`
+/// will have been consumed.
+pub fn decode_buf<C: BufCodec, B: Buf>(codec: &C, mut src: B, dest: &mut BytesMut) -> Result<(), C::Error> {
+	let len = src.remaining();
+	let frame_len = C::STRING_FRAME_LEN;
+
+	// the last frame plus one more byte (enough for z85's padding marker) is
+	// left for `decode_end`, so it can check the length and padding
+	let head_len = len.saturating_sub(frame_len + 1) / frame_len * frame_len;
+	let head_decoded_len = head_len / frame_len * C::BINARY_FRAME_LEN;
+	let end_len = len - head_len;
+	debug_assert!(end_len <= MAX_FRAME_LEN * 2, "end fits in two temporary frames");
+
+	// decoding never makes anything longer, so this is enough for the end too
+	dest.reserve(head_decoded_len + end_len);
+	let start_len = dest.len();
+
+	let result = unsafe {
+		// SAFETY: the head is a whole number of frames, and we reserved exactly
+		// the amount of bytes it decodes to
+		write_reserved(dest, head_decoded_len, |out| {
+			for_each_frame_run(&mut src, head_len, frame_len, |run| codec.decode_frames(run, out))
+		})
+	};
+
+	let result = result.and_then(|()| {
+		let mut end = [0u8; MAX_FRAME_LEN * 2];
+		src.copy_to_slice(&mut end[..end_len]);
+		codec.decode_end(&end[..end_len])
+	});
+
+	match result {
+		Ok(decoded) => {
+			dest.extend_from_slice(&decoded);
+			Ok(())
+		}
+		Err(e) => {
+			dest.truncate(start_len);
+			Err(e)
+		}
+	}
+}
+
+/// Reserves `len` bytes at the end of `dest`, and calls `f` to write into
+/// them. The length of `dest` is only updated if `f` returns `Ok`.
+///
+/// # Safety
+///
+/// `f` must fill the slice completely (unless it returns an error)
+unsafe fn write_reserved<F, E>(dest: &mut BytesMut, len: usize, f: F) -> Result<(), E>
+where
+	F: FnOnce(&mut UnsafeBufWriteSlice) -> Result<(), E>
+{
+	dest.reserve(len);
+
+	let ptr = dest.spare_capacity_mut().as_mut_ptr() as *mut u8;
+	let mut slice = UnsafeBufWriteSlice::new(ptr, len);
+	f(&mut slice)?;
+	slice.assert_is_full();
+
+	dest.set_len(dest.len() + len);
+	Ok(())
+}
+
+/// Takes the next `len` bytes off of `src`, calling `f` with runs of whole
+/// frames of `frame_len` bytes, in order. Frames that straddle the boundary
+/// between two chunks are copied into a temporary frame and passed on their
+/// own. The last run can be a partial frame, if `len` isn't a multiple
+/// of `frame_len`.
+fn for_each_frame_run<B, F, E>(src: &mut B, mut len: usize, frame_len: usize, mut f: F) -> Result<(), E>
+where
+	B: Buf,
+	F: FnMut(&[u8]) -> Result<(), E>
+{
+	debug_assert!(frame_len <= MAX_FRAME_LEN, "frame fits in temporary frame");
+
+	let mut pending = [0u8; MAX_FRAME_LEN];
+	let mut pending_len = 0;
+
+	while len > 0 {
+		let chunk = src.chunk();
+		let chunk = &chunk[..chunk.len().min(len)];
+		let chunk_len = chunk.len();
+		let mut rest = chunk;
+
+		if pending_len > 0 {
+			let take = (frame_len - pending_len).min(rest.len());
+			pending[pending_len..pending_len + take].copy_from_slice(&rest[..take]);
+			pending_len += take;
+			rest = &rest[take..];
+
+			if pending_len == frame_len {
+				f(&pending[..frame_len])?;
+				pending_len = 0;
+			}
+		}
+
+		let whole_len = rest.len() - (rest.len() % frame_len);
+		let (whole, rest) = rest.split_at(whole_len);
+		if !whole.is_empty() { f(whole)? }
+
+		pending[pending_len..pending_len + rest.len()].copy_from_slice(rest);
+		pending_len += rest.len();
+
+		len -= chunk_len;
+		src.advance(chunk_len);
+	}
+
+	if pending_len > 0 { f(&pending[..pending_len])? }
+	Ok(())
+}
+
+/// Test helpers for the codecs' `bytes` tests
+#[cfg(test)]
+pub mod test_utils {
+	use ::bytes::Buf;
+
+	/// A [`Buf`] made of chunks of at most `n` bytes, to make sure frames get
+	/// split across chunks
+	pub struct Chunked<'h> {
+		chunks: Vec<&'h [u8]>,
+		pos: usize
+	}
+
+	impl<'h> Chunked<'h> {
+		pub fn new(bytes: &'h [u8], n: usize) -> Self {
+			Self { chunks: bytes.chunks(n).rev().collect(), pos: 0 }
+		}
+	}
+
+	impl Buf for Chunked<'_> {
+		fn remaining(&self) -> usize {
+			self.chunks.iter().map(|c| c.len()).sum::<usize>() - self.pos
+		}
+
+		fn chunk(&self) -> &[u8] {
+			self.chunks.last().map(|c| &c[self.pos..]).unwrap_or(&[])
+		}
+
+		fn advance(&mut self, mut cnt: usize) {
+			while cnt > 0 {
+				let left = self.chunks.last().expect("advanced past the end").len() - self.pos;
+				if cnt < left {
+					self.pos += cnt;
+					return
+				}
+
+				cnt -= left;
+				self.chunks.pop();
+				self.pos = 0;
+			}
+		}
+	}
+}
@@ -0,0 +1,218 @@
+//! Runtime CPU feature dispatch for the codecs.
+//!
+//! CPU features are detected once (the first time any codec needs them), and
+//! each codec then picks its table of function pointers for that backend once,
+//! and caches it in a [`Dispatch`]. Codecs that don't have an implementation
+//! for a backend just hand out their generic table for it.
+//!
+//! Only codecs with at least one SIMD implementation go through this. For now
+//! that's just hex encoding; hex decoding, base32, base64, and Z85 call their
+//! generic code directly, and would get a table here along with their first
+//! backend.
+//!
+//! The backend can be forced by setting the environment variable
+//! [`BACKEND_ENV_VAR`] to one of `generic`, `neon`, `sse`, or `avx2` before the
+//! first use of any codec. Backends not supported by the CPU (or unknown
+//! names) are ignored, since running them would be undefined behaviour. Tests
+//! can also force a backend for the current thread with [`with_backend`].
+
+use ::std::env;
+use ::std::sync::OnceLock;
+
+/// Name of the environment variable used to force a specific backend
+pub const BACKEND_ENV_VAR: &str = "WIWI_CODEC_BACKEND";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+	/// Plain Rust, no SIMD. Always available
+	Generic,
+	/// aarch64 NEON
+	Neon,
+	/// x86/x86_64 SSE, up to and including SSSE3
+	Sse,
+	/// x86/x86_64 AVX2
+	Avx2
+}
+
+impl Backend {
+	/// Every backend, from least to most preferred
+	pub const ALL: [Backend; 4] = [Backend::Generic, Backend::Neon, Backend::Sse, Backend::Avx2];
+
+	/// Whether or not the current CPU supports this backend
+	pub fn is_supported(self) -> bool {
+		match self {
+			Backend::Generic => { true }
+			Backend::Neon => {
+				#[cfg(target_arch = "aarch64")]
+				return ::std::arch::is_aarch64_feature_detected!("neon");
+				#[cfg(not(target_arch = "aarch64"))]
+				return false;
+			}
+			Backend::Sse => {
+				#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+				return ::std::arch::is_x86_feature_detected!("ssse3");
+				#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+				return false;
+			}
+			Backend::Avx2 => {
+				#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+				return ::std::arch::is_x86_feature_detected!("avx2");
+				#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+				return false;
+			}
+		}
+	}
+
+	/// The most preferred backend supported by the current CPU
+	pub fn detect() -> Backend {
+		Backend::ALL.into_iter()
+			.rev()
+			.find(|b| b.is_supported())
+			.unwrap_or(Backend::Generic)
+	}
+
+	/// Parses a backend name (as used in [`BACKEND_ENV_VAR`]), ignoring case
+	pub fn from_name(name: &str) -> Option<Backend> {
+		let backend = match &*name.trim().to_ascii_lowercase() {
+			"generic" => { Backend::Generic }
+			"neon" => { Backend::Neon }
+			"sse" => { Backend::Sse }
+			"avx2" => { Backend::Avx2 }
+			_ => { return None }
+		};
+		Some(backend)
+	}
+}
+
+/// The backend in use by codecs, taking [`BACKEND_ENV_VAR`] into account.
+/// Only detected once, and cached after that.
+pub fn backend() -> Backend {
+	static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+	*BACKEND.get_or_init(|| backend_from_var(env::var(BACKEND_ENV_VAR).ok().as_deref()))
+}
+
+/// The backend to use given the value of [`BACKEND_ENV_VAR`], if it's set.
+/// Falls back to [detecting](Backend::detect) it if the value isn't a known
+/// backend, or isn't supported by the CPU.
+fn backend_from_var(value: Option<&str>) -> Backend {
+	value
+		.and_then(Backend::from_name)
+		.filter(|b| b.is_supported())
+		.unwrap_or_else(Backend::detect)
+}
+
+/// Caches a codec's table of function pointers for the backend in use.
+/// Meant to be stored in a `static`.
+pub struct Dispatch<T: 'static> {
+	select: fn(Backend) -> &'static T,
+	table: OnceLock<&'static T>
+}
+
+impl<T: 'static> Dispatch<T> {
+	/// `select` returns the table for a backend. It will only be called with
+	/// backends supported by the current CPU.
+	pub const fn new(select: fn(Backend) -> &'static T) -> Self {
+		Self { select, table: OnceLock::new() }
+	}
+
+	#[inline]
+	pub fn get(&self) -> &'static T {
+		#[cfg(test)]
+		if let Some(backend) = OVERRIDE.with(|o| o.get()) {
+			return (self.select)(backend)
+		}
+
+		self.table.get_or_init(|| (self.select)(backend()))
+	}
+}
+
+#[cfg(test)]
+thread_local! {
+	static OVERRIDE: ::std::cell::Cell<Option<Backend>> = const { ::std::cell::Cell::new(None) };
+}
+
+/// Runs `f` with codecs on the current thread forced to use `backend`. Returns
+/// `None` without running `f` if the CPU doesn't support `backend`.
+#[cfg(test)]
+pub fn with_backend<F, R>(backend: Backend, f: F) -> Option<R>
+where
+	F: FnOnce() -> R
+{
+	if !backend.is_supported() { return None }
+
+	/// Puts back the previous override when dropped, so it's restored
+	/// even if `f` panics
+	struct Restore(Option<Backend>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			OVERRIDE.with(|o| o.set(self.0));
+		}
+	}
+
+	let _restore = Restore(OVERRIDE.with(|o| o.replace(Some(backend))));
+	Some(f())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn names() {
+		for backend in Backend::ALL {
+			let name = format!("{backend:?}");
+			assert_eq!(Backend::from_name(&name), Some(backend));
+			assert_eq!(Backend::from_name(&name.to_ascii_uppercase()), Some(backend));
+		}
+		assert_eq!(Backend::from_name("avx512"), None);
+	}
+
+	#[test]
+	fn env_var() {
+		assert_eq!(backend_from_var(Some("generic")), Backend::Generic);
+		assert_eq!(backend_from_var(Some(" Generic\n")), Backend::Generic);
+		assert_eq!(backend_from_var(None), Backend::detect());
+		assert_eq!(backend_from_var(Some("")), Backend::detect());
+		assert_eq!(backend_from_var(Some("avx512")), Backend::detect());
+
+		for b in Backend::ALL {
+			let expected = if b.is_supported() { b } else { Backend::detect() };
+			assert_eq!(backend_from_var(Some(&format!("{b:?}"))), expected);
+		}
+	}
+
+	#[test]
+	fn detection() {
+		assert!(Backend::Generic.is_supported());
+		assert!(Backend::detect().is_supported());
+		assert!(backend().is_supported());
+	}
+
+	#[test]
+	fn overrides() {
+		static DISPATCH: Dispatch<Backend> = Dispatch::new(|backend| match backend {
+			Backend::Generic => { &Backend::Generic }
+			Backend::Neon => { &Backend::Neon }
+			Backend::Sse => { &Backend::Sse }
+			Backend::Avx2 => { &Backend::Avx2 }
+		});
+
+		assert_eq!(*DISPATCH.get(), backend());
+		for b in Backend::ALL {
+			if let Some(selected) = with_backend(b, || *DISPATCH.get()) {
+				assert_eq!(selected, b);
+			}
+		}
+		assert_eq!(*DISPATCH.get(), backend());
+		assert_eq!(with_backend(Backend::Generic, || ()), Some(()));
+
+		// a panic in `f` doesn't leave the backend forced
+		let result = ::std::panic::catch_unwind(|| {
+			with_backend(Backend::Generic, || panic!("oh no"));
+		});
+		assert!(result.is_err());
+		assert_eq!(OVERRIDE.with(|o| o.get()), None);
+	}
+}
@@ -0,0 +1,479 @@
+//! Adapters for tokio's [`AsyncRead`] and [`AsyncWrite`] that encode or decode
+//! data on the fly, generic over the codec used (see [`StreamCodec`]).
+//! Codecs without any settings can be used with `new`, and ones with settings
+//! (ex. base64 and its [`Config`](crate::base64::Config)) with `with_codec`.
+//!
+//! Data is only ever encoded or decoded in whole frames, so a frame split
+//! across multiple reads or writes is held onto until the rest of it arrives.
+//! Decode errors are returned as [`io::Error`]s of kind
+//! [`InvalidData`](io::ErrorKind::InvalidData), with the codec's error as the
+//! inner error (get it back with [`io::Error::get_ref`] or
+//! [`io::Error::into_inner`]).
+
+use ::std::io;
+use ::std::pin::Pin;
+use ::std::task::{ Context, Poll, ready };
+use ::tokio::io::{ AsyncRead, AsyncWrite, ReadBuf };
+
+/// Size of the temporary buffer used to read from the inner reader
+const READ_BUF_LEN: usize = 8 * 1024;
+
+/// A codec that can be used with the stream adapters in this module
+pub trait StreamCodec {
+	type Error: ::std::error::Error + Send + Sync + 'static;
+
+	/// Amount of bytes encoded together as one frame. Any amount of whole
+	/// frames can be encoded on their own, without affecting the output.
+	const BINARY_FRAME_LEN: usize;
+
+	/// Amount of encoded bytes decoded together as one frame. Any amount of
+	/// whole frames can be decoded on their own, without affecting the output.
+	const STRING_FRAME_LEN: usize;
+
+	/// Whether or not the last frame needs to be held onto until the end of the
+	/// input, because it can be affected by something that comes after it (ie.
+	/// padding markers)
+	const HOLD_LAST_FRAME: bool;
+
+	fn encode(&self, bytes: &[u8]) -> String;
+
+	/// Decodes whole frames, that are not the end of the input
+	fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, Self::Error>;
+
+	/// Decodes the end of the input, including the last frame and any
+	/// padding. Defaults to [`decode`](StreamCodec::decode), for codecs
+	/// where the end of the input is no different.
+	#[inline]
+	fn decode_end(&self, bytes: &[u8]) -> Result<Vec<u8>, Self::Error> {
+		self.decode(bytes)
+	}
+}
+
+/// Wraps an [`AsyncRead`], encoding everything read from it
+pub struct EncodeReader<R, C> {
+	inner: R,
+	pending: Pending,
+	/// Bytes read from inner, but not enough to make a whole frame yet
+	input: Vec<u8>,
+	eof: bool,
+	codec: C
+}
+
+/// Wraps an [`AsyncRead`] of encoded data, decoding everything read from it
+pub struct DecodeReader<R, C> {
+	inner: R,
+	pending: Pending,
+	/// Encoded bytes read from inner, but not decoded yet
+	input: Vec<u8>,
+	eof: bool,
+	codec: C
+}
+
+/// Wraps an [`AsyncWrite`], encoding everything written to it before passing
+/// it on to the inner writer
+///
+/// Any partial frame left at the end is only encoded and written when the
+/// writer is shut down, so make sure to call
+/// [`shutdown`](::tokio::io::AsyncWriteExt::shutdown) when done.
+pub struct EncodeWriter<W, C> {
+	inner: W,
+	pending: Pending,
+	/// Bytes written to us, but not enough to make a whole frame yet
+	input: Vec<u8>,
+	finished: bool,
+	codec: C
+}
+
+/// Wraps an [`AsyncWrite`], decoding everything written to it before passing
+/// it on to the inner writer
+///
+/// The last bit of the input is only decoded and written when the writer is
+/// shut down, so make sure to call
+/// [`shutdown`](::tokio::io::AsyncWriteExt::shutdown) when done.
+pub struct DecodeWriter<W, C> {
+	inner: W,
+	pending: Pending,
+	/// Encoded bytes written to us, but not decoded yet
+	input: Vec<u8>,
+	finished: bool,
+	codec: C
+}
+
+/// Output that's been encoded/decoded, but not been read out or written to
+/// the inner writer yet
+struct Pending {
+	bytes: Vec<u8>,
+	pos: usize
+}
+
+impl Pending {
+	#[inline]
+	fn new() -> Self {
+		Self { bytes: Vec::new(), pos: 0 }
+	}
+
+	#[inline]
+	fn is_empty(&self) -> bool {
+		self.pos >= self.bytes.len()
+	}
+
+	#[inline]
+	fn set(&mut self, bytes: Vec<u8>) {
+		debug_assert!(self.is_empty(), "pending output was used up before being replaced");
+		self.bytes = bytes;
+		self.pos = 0;
+	}
+
+	/// Copies as much as possible into `buf`
+	fn read_into(&mut self, buf: &mut ReadBuf<'_>) {
+		let len = buf.remaining().min(self.bytes.len() - self.pos);
+		buf.put_slice(&self.bytes[self.pos..self.pos + len]);
+		self.pos += len;
+	}
+
+	/// Writes everything left into `writer`
+	fn poll_write_into<W: AsyncWrite + Unpin>(
+		&mut self,
+		writer: &mut W,
+		cx: &mut Context<'_>
+	) -> Poll<io::Result<()>> {
+		while !self.is_empty() {
+			let written = ready!(Pin::new(&mut *writer).poll_write(cx, &self.bytes[self.pos..]))?;
+			if written == 0 { return Poll::Ready(Err(io::ErrorKind::WriteZero.into())) }
+			self.pos += written;
+		}
+		Poll::Ready(Ok(()))
+	}
+}
+
+/// Length of the part of `input` that can be decoded right now, without
+/// waiting for more input
+#[inline]
+fn decodable_len<C: StreamCodec>(input: &[u8]) -> usize {
+	let len = input.len() - (input.len() % C::STRING_FRAME_LEN);
+	if C::HOLD_LAST_FRAME {
+		len.saturating_sub(C::STRING_FRAME_LEN)
+	} else {
+		len
+	}
+}
+
+#[inline]
+fn decode<C: StreamCodec>(codec: &C, bytes: &[u8]) -> io::Result<Vec<u8>> {
+	codec.decode(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[inline]
+fn decode_end<C: StreamCodec>(codec: &C, bytes: &[u8]) -> io::Result<Vec<u8>> {
+	codec.decode_end(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Reads from `reader` into a temporary buffer, then calls `f` with what was
+/// read (empty slice on EOF)
+fn poll_read_chunk<R, F>(reader: &mut R, cx: &mut Context<'_>, f: F) -> Poll<io::Result<()>>
+where
+	R: AsyncRead + Unpin,
+	F: FnOnce(&[u8]) -> io::Result<()>
+{
+	let mut buf = [0u8; READ_BUF_LEN];
+	let mut buf = ReadBuf::new(&mut buf);
+	ready!(Pin::new(reader).poll_read(cx, &mut buf))?;
+	Poll::Ready(f(buf.filled()))
+}
+
+impl<T, C: StreamCodec> EncodeReader<T, C> {
+	#[inline]
+	pub fn new(inner: T) -> Self
+	where
+		C: Default
+	{
+		Self::with_codec(inner, C::default())
+	}
+
+	#[inline]
+	pub fn with_codec(inner: T, codec: C) -> Self {
+		Self { inner, pending: Pending::new(), input: Vec::new(), eof: false, codec }
+	}
+}
+
+impl<T, C: StreamCodec> DecodeReader<T, C> {
+	#[inline]
+	pub fn new(inner: T) -> Self
+	where
+		C: Default
+	{
+		Self::with_codec(inner, C::default())
+	}
+
+	#[inline]
+	pub fn with_codec(inner: T, codec: C) -> Self {
+		Self { inner, pending: Pending::new(), input: Vec::new(), eof: false, codec }
+	}
+}
+
+impl<T, C: StreamCodec> EncodeWriter<T, C> {
+	#[inline]
+	pub fn new(inner: T) -> Self
+	where
+		C: Default
+	{
+		Self::with_codec(inner, C::default())
+	}
+
+	#[inline]
+	pub fn with_codec(inner: T, codec: C) -> Self {
+		Self { inner, pending: Pending::new(), input: Vec::new(), finished: false, codec }
+	}
+}
+
+impl<T, C: StreamCodec> DecodeWriter<T, C> {
+	#[inline]
+	pub fn new(inner: T) -> Self
+	where
+		C: Default
+	{
+		Self::with_codec(inner, C::default())
+	}
+
+	#[inline]
+	pub fn with_codec(inner: T, codec: C) -> Self {
+		Self { inner, pending: Pending::new(), input: Vec::new(), finished: false, codec }
+	}
+}
+
+macro_rules! impl_accessors {
+	($($struct:ident)*) => {
+		$(
+			impl<T, C> $struct<T, C> {
+				#[inline]
+				pub fn get_ref(&self) -> &T {
+					&self.inner
+				}
+
+				#[inline]
+				pub fn get_mut(&mut self) -> &mut T {
+					&mut self.inner
+				}
+
+				#[inline]
+				pub fn codec(&self) -> &C {
+					&self.codec
+				}
+
+				/// Unwraps this adapter, returning the inner value. Any data that
+				/// hasn't been passed through yet is lost.
+				#[inline]
+				pub fn into_inner(self) -> T {
+					self.inner
+				}
+			}
+		)*
+	}
+}
+
+impl_accessors! { EncodeReader DecodeReader EncodeWriter DecodeWriter }
+
+impl<R: AsyncRead + Unpin, C: StreamCodec + Unpin> AsyncRead for EncodeReader<R, C> {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut ReadBuf<'_>
+	) -> Poll<io::Result<()>> {
+		let this = self.get_mut();
+		if buf.remaining() == 0 { return Poll::Ready(Ok(())) }
+
+		loop {
+			if !this.pending.is_empty() {
+				this.pending.read_into(buf);
+				return Poll::Ready(Ok(()))
+			}
+			if this.eof { return Poll::Ready(Ok(())) }
+
+			let Self { inner, pending, input, eof, codec } = this;
+			ready!(poll_read_chunk(inner, cx, |read| {
+				if read.is_empty() {
+					// end of input, encode whatever is left (with padding)
+					*eof = true;
+					pending.set(codec.encode(input).into_bytes());
+					input.clear();
+				} else {
+					input.extend_from_slice(read);
+					let whole = input.len() - (input.len() % C::BINARY_FRAME_LEN);
+					pending.set(codec.encode(&input[..whole]).into_bytes());
+					input.drain(..whole);
+				}
+				Ok(())
+			}))?;
+		}
+	}
+}
+
+impl<R: AsyncRead + Unpin, C: StreamCodec + Unpin> AsyncRead for DecodeReader<R, C> {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut ReadBuf<'_>
+	) -> Poll<io::Result<()>> {
+		let this = self.get_mut();
+		if buf.remaining() == 0 { return Poll::Ready(Ok(())) }
+
+		loop {
+			if !this.pending.is_empty() {
+				this.pending.read_into(buf);
+				return Poll::Ready(Ok(()))
+			}
+			if this.eof && this.input.is_empty() { return Poll::Ready(Ok(())) }
+
+			if this.eof {
+				// end of input, decode whatever is left (returning the error
+				// again if it doesn't decode)
+				this.pending.set(decode_end(&this.codec, &this.input)?);
+				this.input.clear();
+				continue
+			}
+
+			let Self { inner, pending, input, eof, codec } = this;
+			ready!(poll_read_chunk(inner, cx, |read| {
+				if read.is_empty() {
+					*eof = true;
+				} else {
+					input.extend_from_slice(read);
+					let decodable = decodable_len::<C>(input);
+					pending.set(decode(codec, &input[..decodable])?);
+					input.drain(..decodable);
+				}
+				Ok(())
+			}))?;
+		}
+	}
+}
+
+impl<W: AsyncWrite + Unpin, C: StreamCodec + Unpin> AsyncWrite for EncodeWriter<W, C> {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &[u8]
+	) -> Poll<io::Result<usize>> {
+		let this = self.get_mut();
+		if this.finished { return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())) }
+
+		ready!(this.pending.poll_write_into(&mut this.inner, cx))?;
+
+		this.input.extend_from_slice(buf);
+		let whole = this.input.len() - (this.input.len() % C::BINARY_FRAME_LEN);
+		this.pending.set(this.codec.encode(&this.input[..whole]).into_bytes());
+		this.input.drain(..whole);
+
+		Poll::Ready(Ok(buf.len()))
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		let this = self.get_mut();
+		ready!(this.pending.poll_write_into(&mut this.inner, cx))?;
+		Pin::new(&mut this.inner).poll_flush(cx)
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		let this = self.get_mut();
+		ready!(this.pending.poll_write_into(&mut this.inner, cx))?;
+
+		if !this.finished {
+			this.finished = true;
+			this.pending.set(this.codec.encode(&this.input).into_bytes());
+			this.input = Vec::new();
+			ready!(this.pending.poll_write_into(&mut this.inner, cx))?;
+		}
+
+		Pin::new(&mut this.inner).poll_shutdown(cx)
+	}
+}
+
+impl<W: AsyncWrite + Unpin, C: StreamCodec + Unpin> AsyncWrite for DecodeWriter<W, C> {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &[u8]
+	) -> Poll<io::Result<usize>> {
+		let this = self.get_mut();
+		if this.finished { return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())) }
+
+		ready!(this.pending.poll_write_into(&mut this.inner, cx))?;
+
+		let prev_len = this.input.len();
+		this.input.extend_from_slice(buf);
+		let decodable = decodable_len::<C>(&this.input);
+
+		match decode(&this.codec, &this.input[..decodable]) {
+			Ok(decoded) => {
+				this.pending.set(decoded);
+				this.input.drain(..decodable);
+				Poll::Ready(Ok(buf.len()))
+			}
+			Err(e) => {
+				// don't take the bad input
+				this.input.truncate(prev_len);
+				Poll::Ready(Err(e))
+			}
+		}
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		let this = self.get_mut();
+		ready!(this.pending.poll_write_into(&mut this.inner, cx))?;
+		Pin::new(&mut this.inner).poll_flush(cx)
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		let this = self.get_mut();
+		ready!(this.pending.poll_write_into(&mut this.inner, cx))?;
+
+		if !this.finished {
+			this.pending.set(decode_end(&this.codec, &this.input)?);
+			this.finished = true;
+			this.input = Vec::new();
+			ready!(this.pending.poll_write_into(&mut this.inner, cx))?;
+		}
+
+		Pin::new(&mut this.inner).poll_shutdown(cx)
+	}
+}
+
+/// Test helpers for the codecs' stream adapter tests
+#[cfg(test)]
+pub mod test_utils {
+	use super::*;
+
+	/// Reader that returns at most `n` bytes per read, and is pending every
+	/// other poll, to make sure frames get split across reads
+	pub struct Trickle<'h> {
+		bytes: &'h [u8],
+		n: usize,
+		pending: bool
+	}
+
+	impl<'h> Trickle<'h> {
+		pub fn new(bytes: &'h [u8], n: usize) -> Self {
+			Self { bytes, n, pending: false }
+		}
+	}
+
+	impl AsyncRead for Trickle<'_> {
+		fn poll_read(
+			mut self: Pin<&mut Self>,
+			cx: &mut Context<'_>,
+			buf: &mut ReadBuf<'_>
+		) -> Poll<io::Result<()>> {
+			self.pending = !self.pending;
+			if self.pending {
+				cx.waker().wake_by_ref();
+				return Poll::Pending
+			}
+
+			let len = self.n.min(self.bytes.len()).min(buf.remaining());
+			let (read, rest) = self.bytes.split_at(len);
+			buf.put_slice(read);
+			self.bytes = rest;
+			Poll::Ready(Ok(()))
+		}
+	}
+}
@@ -0,0 +1,529 @@
+//! [Geohash] encoding and decoding.
+//!
+//! A geohash interleaves the bits of a longitude and a latitude (longitude
+//! first), each found by repeatedly halving its range, and encodes the result
+//! with a base32 alphabet (different from the one in [`base32`](crate::base32)).
+//! Every char added narrows down the cell, and geohashes sharing a prefix are
+//! inside the cell of that prefix, so areas can be looked up with prefix
+//! queries (see [`cover_bounding_box`] and [`covering_prefixes`]).
+//!
+//! [Geohash]: https://en.wikipedia.org/wiki/Geohash
+
+pub const TABLE_ENCODER_LEN: usize = 32;
+pub const TABLE_ENCODER: [u8; TABLE_ENCODER_LEN] = *b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+pub const TABLE_DECODER_LEN: usize = 256;
+/// Decoding is case insensitive
+pub static TABLE_DECODER: [Option<u8>; TABLE_DECODER_LEN] = decoder_table();
+
+/// Max supported precision (length in chars). 12 chars is 60 bits, which is
+/// a cell less than 4cm across.
+pub const MAX_PRECISION: usize = 12;
+
+/// Max amount of cells [`cover_bounding_box`] returns. Covering a large box
+/// at a high precision can take billions of cells, so it's refused with
+/// [`EncodeError::TooManyCells`] instead of trying to allocate them all.
+pub const MAX_COVER_CELLS: usize = 1 << 20;
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum EncodeError {
+	#[error("latitude must be between -90 and 90")]
+	InvalidLatitude,
+	#[error("longitude must be between -180 and 180")]
+	InvalidLongitude,
+	#[error("precision must be between 1 and 12")]
+	InvalidPrecision,
+	#[error("bounding box needs more than 2^20 cells to cover at this precision")]
+	TooManyCells
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError {
+	#[error("invalid length")]
+	InvalidLength,
+	#[error("invalid character")]
+	InvalidChar
+}
+
+/// A cell (or any other latitude/longitude rectangle). Bounds are in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+	pub min_lat: f64,
+	pub max_lat: f64,
+	pub min_lon: f64,
+	pub max_lon: f64
+}
+
+impl BoundingBox {
+	/// The centre of this box, as `(latitude, longitude)`
+	#[inline]
+	pub fn centre(&self) -> (f64, f64) {
+		((self.min_lat + self.max_lat) / 2.0, (self.min_lon + self.max_lon) / 2.0)
+	}
+
+	/// Whether the point is in this box, including its edges
+	#[inline]
+	pub fn contains(&self, lat: f64, lon: f64) -> bool {
+		(self.min_lat..=self.max_lat).contains(&lat)
+			&& (self.min_lon..=self.max_lon).contains(&lon)
+	}
+
+	/// Half the height of the box, ie. the max latitude error of the centre
+	#[inline]
+	pub fn lat_error(&self) -> f64 {
+		(self.max_lat - self.min_lat) / 2.0
+	}
+
+	/// Half the width of the box, ie. the max longitude error of the centre
+	#[inline]
+	pub fn lon_error(&self) -> f64 {
+		(self.max_lon - self.min_lon) / 2.0
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+	North,
+	NorthEast,
+	East,
+	SouthEast,
+	South,
+	SouthWest,
+	West,
+	NorthWest
+}
+
+impl Direction {
+	/// Every direction, clockwise starting from north
+	pub const ALL: [Direction; 8] = [
+		Direction::North,
+		Direction::NorthEast,
+		Direction::East,
+		Direction::SouthEast,
+		Direction::South,
+		Direction::SouthWest,
+		Direction::West,
+		Direction::NorthWest
+	];
+
+	/// Steps in cells, as `(latitude, longitude)`
+	#[inline]
+	const fn offset(self) -> (i8, i8) {
+		match self {
+			Direction::North => { (1, 0) }
+			Direction::NorthEast => { (1, 1) }
+			Direction::East => { (0, 1) }
+			Direction::SouthEast => { (-1, 1) }
+			Direction::South => { (-1, 0) }
+			Direction::SouthWest => { (-1, -1) }
+			Direction::West => { (0, -1) }
+			Direction::NorthWest => { (1, -1) }
+		}
+	}
+}
+
+/// Encodes a point into a geohash of `precision` chars
+pub fn encode_geohash(lat: f64, lon: f64, precision: usize) -> Result<String, EncodeError> {
+	check_precision(precision)?;
+	let cell = Cell::at(lat, lon, precision)?;
+	Ok(cell.encode())
+}
+
+/// Decodes a geohash into the bounding box of its cell
+pub fn decode_geohash(hash: &[u8]) -> Result<BoundingBox, DecodeError> {
+	Cell::decode(hash).map(|cell| cell.bounding_box())
+}
+
+/// Decodes a geohash into the centre of its cell, as `(latitude, longitude)`
+#[inline]
+pub fn decode_geohash_centre(hash: &[u8]) -> Result<(f64, f64), DecodeError> {
+	decode_geohash(hash).map(|bbox| bbox.centre())
+}
+
+/// Returns the geohash of the cell next to the given one, with the same
+/// precision. Longitude wraps around the antimeridian, but there is nothing
+/// past the poles, so `None` is returned when going north from a cell at the
+/// north pole (or south from one at the south pole).
+pub fn neighbour(hash: &[u8], direction: Direction) -> Result<Option<String>, DecodeError> {
+	Cell::decode(hash).map(|cell| cell.neighbour(direction).map(|cell| cell.encode()))
+}
+
+/// Returns the geohashes of all 8 cells around the given one, in the order of
+/// [`Direction::ALL`] (see [`neighbour`])
+pub fn neighbours(hash: &[u8]) -> Result<[Option<String>; 8], DecodeError> {
+	let cell = Cell::decode(hash)?;
+	Ok(Direction::ALL.map(|direction| cell.neighbour(direction).map(|cell| cell.encode())))
+}
+
+/// Returns the geohashes of every cell with `precision` chars that overlaps
+/// the given bounding box. Any point inside the box has a geohash starting
+/// with one of them, so they can be used as prefixes to look up everything
+/// in the box (filtering out the extra results from the edges of the cells).
+///
+/// If `min_lon` is greater than `max_lon`, the box is taken to cross the
+/// antimeridian. If it takes more than [`MAX_COVER_CELLS`] cells to cover the
+/// box, [`EncodeError::TooManyCells`] is returned.
+pub fn cover_bounding_box(bbox: &BoundingBox, precision: usize) -> Result<Vec<String>, EncodeError> {
+	check_precision(precision)?;
+	let (lat_range, lon_range) = cell_ranges(bbox, precision)?;
+
+	let cells = lat_range.len().saturating_mul(lon_range.len());
+	if cells > MAX_COVER_CELLS { return Err(EncodeError::TooManyCells) }
+
+	let mut hashes = Vec::with_capacity(cells);
+	for lat in lat_range.iter() {
+		for lon in lon_range.iter() {
+			hashes.push(Cell { lat, lon, precision }.encode());
+		}
+	}
+
+	Ok(hashes)
+}
+
+/// Like [`cover_bounding_box`], but picks the highest precision that covers
+/// the box with at most `max_prefixes` geohashes. This is the most specific
+/// set of prefixes to query with, while keeping the amount of queries down.
+/// A precision of at least 1 is always used, even if that needs more than
+/// `max_prefixes` geohashes. `max_prefixes` is capped to [`MAX_COVER_CELLS`].
+pub fn covering_prefixes(bbox: &BoundingBox, max_prefixes: usize) -> Result<Vec<String>, EncodeError> {
+	let max_prefixes = max_prefixes.min(MAX_COVER_CELLS);
+
+	let mut precision = 1;
+	while precision < MAX_PRECISION {
+		let (lat_range, lon_range) = cell_ranges(bbox, precision + 1)?;
+		if lat_range.len().saturating_mul(lon_range.len()) > max_prefixes { break }
+		precision += 1;
+	}
+
+	cover_bounding_box(bbox, precision)
+}
+
+#[inline]
+fn check_precision(precision: usize) -> Result<(), EncodeError> {
+	if !(1..=MAX_PRECISION).contains(&precision) { return Err(EncodeError::InvalidPrecision) }
+	Ok(())
+}
+
+/// Ranges of cell indices along each axis that overlap the box
+fn cell_ranges(bbox: &BoundingBox, precision: usize) -> Result<(CellRange, CellRange), EncodeError> {
+	let min = Cell::at(bbox.min_lat, bbox.min_lon, precision)?;
+	let max = Cell::at(bbox.max_lat, bbox.max_lon, precision)?;
+
+	let (lat_bits, lon_bits) = bit_counts(precision);
+	let lat = CellRange { start: min.lat.min(max.lat), end: min.lat.max(max.lat), bits: lat_bits };
+	let lon = if bbox.min_lon > bbox.max_lon && min.lon == max.lon {
+		// crossing the antimeridian with both edges in the same column goes
+		// all the way around, not just through that one column
+		CellRange { start: 0, end: (1 << lon_bits) - 1, bits: lon_bits }
+	} else {
+		CellRange { start: min.lon, end: max.lon, bits: lon_bits }
+	};
+	Ok((lat, lon))
+}
+
+/// Inclusive range of cell indices along one axis, wrapping around if `start`
+/// is greater than `end`
+struct CellRange {
+	start: u64,
+	end: u64,
+	bits: u32
+}
+
+impl CellRange {
+	#[inline]
+	fn len(&self) -> usize {
+		let count = 1 << self.bits;
+		((self.end + count - self.start) % count + 1) as usize
+	}
+
+	#[inline]
+	fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+		let mask = (1 << self.bits) - 1;
+		(0..self.len() as u64).map(move |i| (self.start + i) & mask)
+	}
+}
+
+/// Amount of bits used for latitude and longitude in a geohash of `precision`
+/// chars. Longitude gets the extra bit if there's an odd amount.
+#[inline]
+fn bit_counts(precision: usize) -> (u32, u32) {
+	let bits = (precision * 5) as u32;
+	(bits / 2, bits - (bits / 2))
+}
+
+/// A cell as its index along each axis, counting from south and west
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Cell {
+	lat: u64,
+	lon: u64,
+	precision: usize
+}
+
+impl Cell {
+	/// The cell that contains the given point
+	fn at(lat: f64, lon: f64, precision: usize) -> Result<Self, EncodeError> {
+		if !(-90.0..=90.0).contains(&lat) { return Err(EncodeError::InvalidLatitude) }
+		if !(-180.0..=180.0).contains(&lon) { return Err(EncodeError::InvalidLongitude) }
+
+		let (lat_bits, lon_bits) = bit_counts(precision);
+		Ok(Self {
+			lat: bisect(lat, -90.0, 90.0, lat_bits),
+			lon: bisect(lon, -180.0, 180.0, lon_bits),
+			precision
+		})
+	}
+
+	fn decode(hash: &[u8]) -> Result<Self, DecodeError> {
+		if !(1..=MAX_PRECISION).contains(&hash.len()) { return Err(DecodeError::InvalidLength) }
+
+		let mut bits = 0u64;
+		for byte in hash {
+			let Some(value) = TABLE_DECODER[*byte as usize] else {
+				return Err(DecodeError::InvalidChar)
+			};
+			bits = (bits << 5) | value as u64;
+		}
+
+		let (lat_bits, lon_bits) = bit_counts(hash.len());
+		let total = lat_bits + lon_bits;
+		let mut lat = 0;
+		let mut lon = 0;
+
+		// first (most significant) bit is longitude, then alternating
+		for i in 0..total {
+			let bit = (bits >> (total - 1 - i)) & 1;
+			if i & 1 == 0 {
+				lon = (lon << 1) | bit;
+			} else {
+				lat = (lat << 1) | bit;
+			}
+		}
+
+		Ok(Self { lat, lon, precision: hash.len() })
+	}
+
+	fn encode(&self) -> String {
+		let (lat_bits, lon_bits) = bit_counts(self.precision);
+		let total = lat_bits + lon_bits;
+
+		let mut bits = 0u64;
+		let (mut lat_left, mut lon_left) = (lat_bits, lon_bits);
+		for i in 0..total {
+			let bit = if i & 1 == 0 {
+				lon_left -= 1;
+				(self.lon >> lon_left) & 1
+			} else {
+				lat_left -= 1;
+				(self.lat >> lat_left) & 1
+			};
+			bits = (bits << 1) | bit;
+		}
+
+		(0..self.precision)
+			.rev()
+			.map(|i| TABLE_ENCODER[((bits >> (i * 5)) & 0x1f) as usize] as char)
+			.collect()
+	}
+
+	fn bounding_box(&self) -> BoundingBox {
+		let (lat_bits, lon_bits) = bit_counts(self.precision);
+		let lat_size = 180.0 / (1u64 << lat_bits) as f64;
+		let lon_size = 360.0 / (1u64 << lon_bits) as f64;
+
+		let min_lat = -90.0 + (self.lat as f64 * lat_size);
+		let min_lon = -180.0 + (self.lon as f64 * lon_size);
+		BoundingBox {
+			min_lat,
+			max_lat: min_lat + lat_size,
+			min_lon,
+			max_lon: min_lon + lon_size
+		}
+	}
+
+	fn neighbour(&self, direction: Direction) -> Option<Self> {
+		let (lat_bits, lon_bits) = bit_counts(self.precision);
+		let (lat_step, lon_step) = direction.offset();
+
+		let lat = self.lat.checked_add_signed(lat_step as i64)
+			.filter(|lat| *lat < 1 << lat_bits)?;
+		let lon = self.lon.wrapping_add_signed(lon_step as i64) & ((1 << lon_bits) - 1);
+
+		Some(Self { lat, lon, precision: self.precision })
+	}
+}
+
+/// Index of the cell containing `value`, after halving the range `bits` times.
+/// Values exactly on a boundary go to the upper half (so the max value ends
+/// up in the last cell).
+fn bisect(value: f64, mut min: f64, mut max: f64, bits: u32) -> u64 {
+	let mut index = 0;
+	for _ in 0..bits {
+		let mid = (min + max) / 2.0;
+		index <<= 1;
+		if value >= mid {
+			index |= 1;
+			min = mid;
+		} else {
+			max = mid;
+		}
+	}
+	index
+}
+
+const fn decoder_table() -> [Option<u8>; TABLE_DECODER_LEN] {
+	let mut table = [None; TABLE_DECODER_LEN];
+
+	let mut i = 0;
+	while i < TABLE_ENCODER_LEN {
+		let byte = TABLE_ENCODER[i];
+		table[byte as usize] = Some(i as u8);
+		table[byte.to_ascii_uppercase() as usize] = Some(i as u8);
+		i += 1;
+	}
+
+	table
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn known_values() {
+		assert_eq!(encode_geohash(42.6, -5.6, 5).unwrap(), "ezs42");
+		assert_eq!(encode_geohash(57.64911, 10.40744, 11).unwrap(), "u4pruydqqvj");
+		assert_eq!(encode_geohash(-90.0, -180.0, 12).unwrap(), "000000000000");
+		assert_eq!(encode_geohash(90.0, 180.0, 12).unwrap(), "zzzzzzzzzzzz");
+
+		let bbox = decode_geohash(b"ezs42").unwrap();
+		assert_eq!(bbox, BoundingBox {
+			min_lat: 42.5830078125,
+			max_lat: 42.626953125,
+			min_lon: -5.625,
+			max_lon: -5.5810546875
+		});
+		assert!(bbox.contains(42.6, -5.6));
+		let (lat, lon) = decode_geohash_centre(b"EZS42").unwrap();
+		assert!((lat - 42.605).abs() < bbox.lat_error());
+		assert!((lon + 5.603).abs() < bbox.lon_error());
+	}
+
+	#[test]
+	fn errors() {
+		assert!(matches!(encode_geohash(90.1, 0.0, 5), Err(EncodeError::InvalidLatitude)));
+		assert!(matches!(encode_geohash(f64::NAN, 0.0, 5), Err(EncodeError::InvalidLatitude)));
+		assert!(matches!(encode_geohash(0.0, -180.1, 5), Err(EncodeError::InvalidLongitude)));
+		assert!(matches!(encode_geohash(0.0, 0.0, 0), Err(EncodeError::InvalidPrecision)));
+		assert!(matches!(encode_geohash(0.0, 0.0, 13), Err(EncodeError::InvalidPrecision)));
+
+		assert!(matches!(decode_geohash(b""), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_geohash(b"0000000000000"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_geohash(b"ezs4a"), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn neighbours_of_cell() {
+		let expected = ["dqcjw", "dqcjx", "dqcjr", "dqcjp", "dqcjn", "dqcjj", "dqcjm", "dqcjt"];
+		let actual = neighbours(b"dqcjq").unwrap();
+		for (actual, expected) in actual.into_iter().zip(expected) {
+			assert_eq!(actual.as_deref(), Some(expected));
+		}
+
+		// wraps around the antimeridian
+		assert_eq!(neighbour(b"0", Direction::West).unwrap().as_deref(), Some("p"));
+		assert_eq!(neighbour(b"p", Direction::East).unwrap().as_deref(), Some("0"));
+		// but not over the poles
+		assert_eq!(neighbour(b"zzz", Direction::North).unwrap(), None);
+		assert_eq!(neighbour(b"000", Direction::SouthEast).unwrap(), None);
+		assert_eq!(neighbours(b"zzz").unwrap().iter().filter(|n| n.is_some()).count(), 5);
+	}
+
+	#[test]
+	fn covering() {
+		let bbox = decode_geohash(b"ezs42").unwrap();
+		assert_eq!(cover_bounding_box(&bbox, 4).unwrap(), ["ezs4"]);
+
+		// a box spanning two cells, with the max edge exactly on the next cell
+		let bbox = BoundingBox { min_lat: 42.59, max_lat: 42.62, min_lon: -5.62, max_lon: -5.5810546875 };
+		let mut cells = cover_bounding_box(&bbox, 5).unwrap();
+		cells.sort();
+		assert_eq!(cells, ["ezs42", "ezs43"]);
+
+		// crossing the antimeridian
+		let bbox = BoundingBox { min_lat: -1.0, max_lat: 1.0, min_lon: 179.0, max_lon: -179.0 };
+		let mut cells = cover_bounding_box(&bbox, 1).unwrap();
+		cells.sort();
+		assert_eq!(cells, ["2", "8", "r", "x"]);
+
+		// crossing the antimeridian with both edges in the same column covers
+		// all 8 columns (in both rows)
+		let bbox = BoundingBox { min_lat: -1.0, max_lat: 1.0, min_lon: 170.0, max_lon: 160.0 };
+		let mut cells = cover_bounding_box(&bbox, 1).unwrap();
+		cells.sort();
+		assert_eq!(cells, ["2", "3", "6", "7", "8", "9", "d", "e", "k", "m", "q", "r", "s", "t", "w", "x"]);
+		let bbox = BoundingBox { min_lat: -90.0, max_lat: 90.0, min_lon: 1.0, max_lon: 0.5 };
+		assert!(matches!(cover_bounding_box(&bbox, 5), Err(EncodeError::TooManyCells)));
+
+		let bbox = BoundingBox { min_lat: 42.59, max_lat: 42.62, min_lon: -5.62, max_lon: -5.59 };
+		let prefixes = covering_prefixes(&bbox, 4).unwrap();
+		assert!(!prefixes.is_empty() && prefixes.len() <= 4);
+		assert!(prefixes.iter().all(|p| p.len() == prefixes[0].len()));
+		assert!(covering_prefixes(&bbox, 0).unwrap().iter().all(|p| p.len() == 1));
+
+		// the whole world at full precision is 2^60 cells
+		let world = BoundingBox { min_lat: -90.0, max_lat: 90.0, min_lon: -180.0, max_lon: 180.0 };
+		assert!(matches!(cover_bounding_box(&world, MAX_PRECISION), Err(EncodeError::TooManyCells)));
+		assert_eq!(cover_bounding_box(&world, 4).unwrap().len(), MAX_COVER_CELLS);
+		assert!(matches!(cover_bounding_box(&world, 5), Err(EncodeError::TooManyCells)));
+		assert_eq!(covering_prefixes(&world, usize::MAX).unwrap().len(), MAX_COVER_CELLS);
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		for _ in 0..1000 {
+			let lat = rng.gen_range(-90.0..=90.0);
+			let lon = rng.gen_range(-180.0..=180.0);
+			let precision = rng.gen_range(1..=MAX_PRECISION);
+
+			let hash = encode_geohash(lat, lon, precision).unwrap();
+			assert_eq!(hash.len(), precision);
+
+			let bbox = decode_geohash(hash.as_bytes()).unwrap();
+			assert!(bbox.contains(lat, lon));
+			let (centre_lat, centre_lon) = bbox.centre();
+			assert_eq!(encode_geohash(centre_lat, centre_lon, precision).unwrap(), hash);
+
+			// shorter geohashes are prefixes of longer ones
+			for shorter in 1..precision {
+				assert_eq!(encode_geohash(lat, lon, shorter).unwrap(), hash[..shorter]);
+			}
+
+			for (direction, neighbour) in Direction::ALL.into_iter().zip(neighbours(hash.as_bytes()).unwrap()) {
+				let Some(neighbour) = neighbour else { continue };
+				let (lat_step, lon_step) = direction.offset();
+				let (lat, lon) = decode_geohash_centre(neighbour.as_bytes()).unwrap();
+				let lat_diff = (lat - centre_lat) / (bbox.lat_error() * 2.0);
+				let lon_diff = (lon - centre_lon) / (bbox.lon_error() * 2.0);
+				assert!((lat_diff - lat_step as f64).abs() < 1e-6);
+				let lon_cells = 360.0 / (bbox.lon_error() * 2.0);
+				let lon_diff = (lon_diff - lon_step as f64).rem_euclid(lon_cells);
+				assert!(lon_diff < 1e-6 || lon_cells - lon_diff < 1e-6);
+			}
+
+			let bbox = BoundingBox {
+				min_lat: (lat - 0.5).max(-90.0),
+				max_lat: (lat + 0.5).min(90.0),
+				min_lon: (lon - 0.5).max(-180.0),
+				max_lon: (lon + 0.5).min(180.0)
+			};
+			let prefixes = covering_prefixes(&bbox, 9).unwrap();
+			let inner_lat = rng.gen_range(bbox.min_lat..=bbox.max_lat);
+			let inner_lon = rng.gen_range(bbox.min_lon..=bbox.max_lon);
+			let inner = encode_geohash(inner_lat, inner_lon, MAX_PRECISION).unwrap();
+			assert!(prefixes.iter().any(|p| inner.starts_with(&**p)));
+		}
+	}
+}
@@ -1,11 +1,21 @@
-use crate::encoding_utils::UnsafeBufWriteGuard;
+use crate::encoding_utils::{ available_threads, parallel_chunks, UnsafeBufWrite, UnsafeBufWriteDiscard, UnsafeBufWriteGuard, UnsafeBufWriteSlice };
+use crate::encoding_utils::dispatch::{ Backend, Dispatch };
 
 pub const TABLE_ENCODER_LEN: usize = 16;
 pub const TABLE_ENCODER_LOWER: [u8; TABLE_ENCODER_LEN] = *b"0123456789abcdef";
 pub const TABLE_ENCODER_UPPER: [u8; TABLE_ENCODER_LEN] = *b"0123456789ABCDEF";
 
+pub const TABLE_DECODER_LEN: usize = 256;
+pub use self::decode::TABLE_DECODER;
+pub use crate::encoding_utils::{ ArrayString, VectoredInput };
+
 mod encode;
 mod decode;
+mod grouped;
+mod int;
+
+pub use self::grouped::{ decode_hex_grouped, encode_hex_grouped, HexFormat };
+pub use self::int::{ decode_int_hex, encode_int_hex, encode_int_hex_upper, parse_int_hex, ByteOrder, HexInt, ParseIntError };
 
 #[inline]
 pub fn encode_hex(bytes: &[u8]) -> String {
@@ -17,42 +27,220 @@ pub fn encode_hex_upper(bytes: &[u8]) -> String {
 	_encode::<true>(bytes)
 }
 
-// mut is used by cfg(target_arch) which might be inactive
-#[allow(unused_mut)]
+/// Encodes the given bytes into a hex [`String`], splitting the work across
+/// multiple threads for large inputs. Smaller inputs are encoded on the
+/// calling thread, the same as [`encode_hex`].
+#[inline]
+pub fn encode_hex_parallel(bytes: &[u8]) -> String {
+	_encode_parallel::<false>(bytes, available_threads())
+}
+
+/// Encodes the given bytes into an upper hex [`String`], splitting the work
+/// across multiple threads for large inputs. Smaller inputs are encoded on
+/// the calling thread, the same as [`encode_hex_upper`].
+#[inline]
+pub fn encode_hex_upper_parallel(bytes: &[u8]) -> String {
+	_encode_parallel::<true>(bytes, available_threads())
+}
+
+/// Encodes the concatenation of several slices (ex. `&[IoSlice]`) into one hex
+/// [`String`], without joining them first
+#[inline]
+pub fn encode_hex_vectored<V: VectoredInput + ?Sized>(input: &V) -> String {
+	_encode_vectored::<false, V>(input)
+}
+
+/// Encodes the concatenation of several slices (ex. `&[IoSlice]`) into one
+/// upper hex [`String`], without joining them first
+#[inline]
+pub fn encode_hex_upper_vectored<V: VectoredInput + ?Sized>(input: &V) -> String {
+	_encode_vectored::<true, V>(input)
+}
+
+/// Encodes a fixed size array of bytes into hex, returning it as an
+/// [`ArrayString`] on the stack instead of allocating.
+///
+/// `M` must be exactly `N * 2`, which is checked at compile time; usually it
+/// can be inferred from a type annotation.
+#[inline]
+pub fn encode_hex_array<const N: usize, const M: usize>(bytes: &[u8; N]) -> ArrayString<M> {
+	_encode_array::<false, N, M>(bytes)
+}
+
+/// Encodes a fixed size array of bytes into upper hex, returning it as an
+/// [`ArrayString`] on the stack instead of allocating.
+///
+/// `M` must be exactly `N * 2`, which is checked at compile time.
+#[inline]
+pub fn encode_hex_upper_array<const N: usize, const M: usize>(bytes: &[u8; N]) -> ArrayString<M> {
+	_encode_array::<true, N, M>(bytes)
+}
+
+fn _encode_array<const UPPER: bool, const N: usize, const M: usize>(bytes: &[u8; N]) -> ArrayString<M> {
+	let () = ArrayLens::<N, M>::CHECK;
+
+	let mut array = [0u8; M];
+	unsafe {
+		// SAFETY: array has exactly the space needed
+		let mut dest = UnsafeBufWriteSlice::new(array.as_mut_ptr(), M);
+		encode_into::<UPPER, _>(bytes, &mut dest);
+		dest.assert_is_full();
+
+		// SAFETY: we only are pushing in chars in the table, which are all ASCII chars
+		ArrayString::from_utf8_unchecked(array)
+	}
+}
+
+/// Encodes the given bytes into hex, writing it to the start of `dest` instead
+/// of allocating, and returning the amount of bytes written (`bytes.len() * 2`).
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than `bytes.len() * 2`.
+#[inline]
+pub fn encode_hex_to_slice(bytes: &[u8], dest: &mut [u8]) -> usize {
+	_encode_to_slice::<false>(bytes, dest)
+}
+
+/// Encodes the given bytes into upper hex, writing it to the start of `dest`
+/// instead of allocating, and returning the amount of bytes written
+/// (`bytes.len() * 2`).
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than `bytes.len() * 2`.
+#[inline]
+pub fn encode_hex_upper_to_slice(bytes: &[u8], dest: &mut [u8]) -> usize {
+	_encode_to_slice::<true>(bytes, dest)
+}
+
+fn _encode_to_slice<const UPPER: bool>(bytes: &[u8], dest: &mut [u8]) -> usize {
+	let len = bytes.len() * 2;
+	assert!(dest.len() >= len, "dest is too short for the encoded bytes");
+
+	unsafe {
+		// SAFETY: we checked dest has enough space
+		let mut dest = UnsafeBufWriteSlice::new(dest.as_mut_ptr(), len);
+		encode_into::<UPPER, _>(bytes, &mut dest);
+		dest.assert_is_full();
+	}
+
+	len
+}
+
+/// Compile time check that an encoded array length `M` matches decoded
+/// array length `N`
+struct ArrayLens<const N: usize, const M: usize>;
+
+impl<const N: usize, const M: usize> ArrayLens<N, M> {
+	const CHECK: () = assert!(N * 2 == M, "hex string length must be twice the length of the bytes");
+}
+
 fn _encode<const UPPER: bool>(bytes: &[u8]) -> String {
-	let bytes_len = bytes.len();
-	let capacity = bytes_len * 2;
+	let capacity = bytes.len() * 2;
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe { encode_into::<UPPER, _>(bytes, &mut dest) }
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+	unsafe { String::from_utf8_unchecked(vec) }
+}
 
-	let mut bytes_ptr = bytes as *const [u8] as *const u8;
+fn _encode_vectored<const UPPER: bool, V: VectoredInput + ?Sized>(input: &V) -> String {
+	let capacity = input.total_len() * 2;
 	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
-	let mut rounds = bytes_len;
 
-	#[cfg(target_arch = "aarch64")] {
-		if ::std::arch::is_aarch64_feature_detected!("neon") {
-			// we handle the big chunks, but leave enough info for the below generic
-			// to continue the uneven chunks
-			// divide by 16
-			let neon_rounds = rounds >> 4;
-			// mod 16
-			let remainder = bytes_len & 0b1111;
+	// every byte is its own frame, so no frames straddle slices
+	// SAFETY: we preallocated two chars for every byte in all the slices
+	input.for_each_slice(|slice| unsafe { encode_into::<UPPER, _>(slice, &mut dest) });
 
-			bytes_ptr = unsafe { encode::neon_uint8x16::<UPPER>(bytes_ptr, dest.as_ptr(), neon_rounds) };
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+	unsafe { String::from_utf8_unchecked(vec) }
+}
 
-			// multiply by 32
-			// multiply by num rounds (^) times two, which is shift one more
-			let amount_written = neon_rounds << 5;
-			rounds = remainder;
-			unsafe { dest.add_byte_count(amount_written) }
-		}
-	}
+fn _encode_parallel<const UPPER: bool>(bytes: &[u8], threads: usize) -> String {
+	let Some(chunks) = parallel_chunks(bytes, 1, threads) else {
+		return _encode::<UPPER>(bytes)
+	};
+
+	let capacity = bytes.len() * 2;
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
 
-	unsafe { encode::generic::<UPPER>(bytes_ptr, &mut dest, rounds) };
+	let result = unsafe {
+		dest.write_parallel(&chunks, |chunk| chunk.len() * 2, |chunk, dest| {
+			encode_into::<UPPER, _>(chunk, dest);
+			Ok::<_, ()>(())
+		})
+	};
+	debug_assert!(result.is_ok(), "encoding doesn't fail");
 
-	let vec = unsafe { dest.into_full_vec() };
+	let vec = dest.into_full_vec();
 	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
 	unsafe { String::from_utf8_unchecked(vec) }
 }
 
+/// # Safety
+///
+/// `dest` must have `bytes.len() * 2` bytes of capacity left
+unsafe fn encode_into<const UPPER: bool, W: UnsafeBufWrite>(bytes: &[u8], dest: &mut W) {
+	let bytes_len = bytes.len();
+	let bytes_ptr = bytes as *const [u8] as *const u8;
+
+	// the SIMD backends handle the big chunks, but leave enough info
+	// for the below generic to continue the uneven chunks
+	let fns = FNS.get();
+	let encode = if UPPER { fns.encode_upper } else { fns.encode_lower };
+	let processed = encode(bytes_ptr, dest.as_ptr_for(bytes_len * 2), bytes_len);
+	dest.add_byte_count(processed * 2);
+
+	encode::generic::<UPPER, _>(bytes_ptr.add(processed), dest, bytes_len - processed);
+}
+
+/// Encodes as many bytes as the backend can handle in bulk, writing to the
+/// pointer, and returns the amount of bytes processed
+type EncodeFn = unsafe fn(*const u8, *mut u8, usize) -> usize;
+
+struct Fns {
+	encode_lower: EncodeFn,
+	encode_upper: EncodeFn
+}
+
+static FNS: Dispatch<Fns> = Dispatch::new(|backend| match backend {
+	#[cfg(target_arch = "aarch64")]
+	Backend::Neon => { &FNS_NEON }
+	#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+	Backend::Sse => { &FNS_SSE }
+	#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+	Backend::Avx2 => { &FNS_AVX2 }
+	_ => { &FNS_GENERIC }
+});
+
+static FNS_GENERIC: Fns = Fns {
+	// generic handles everything
+	encode_lower: |_, _, _| 0,
+	encode_upper: |_, _, _| 0
+};
+
+#[cfg(target_arch = "aarch64")]
+static FNS_NEON: Fns = Fns {
+	encode_lower: encode::neon_uint8x16::<false>,
+	encode_upper: encode::neon_uint8x16::<true>
+};
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+static FNS_SSE: Fns = Fns {
+	encode_lower: encode::sse_m128i::<false>,
+	encode_upper: encode::sse_m128i::<true>
+};
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+static FNS_AVX2: Fns = Fns {
+	encode_lower: encode::avx2_m256i::<false>,
+	encode_upper: encode::avx2_m256i::<true>
+};
+
 pub fn decode_hex(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
 	// AND 0b1 is chopping off all the other bits; last bit will
 	// always be 0 or 1, depending on odd or even
@@ -67,7 +255,119 @@ pub fn decode_hex(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
 
 	unsafe { decode::generic(bytes_ptr, &mut dest, capacity)? }
 
-	Ok(unsafe { dest.into_full_vec() })
+	Ok(dest.into_full_vec())
+}
+
+/// Decodes a hex string into a fixed size array, returning
+/// [`DecodeError::InvalidLength`] if it doesn't decode to exactly `N` bytes.
+pub fn decode_hex_array<const N: usize>(bytes: &[u8]) -> Result<[u8; N], DecodeError> {
+	if bytes.len() != N * 2 { return Err(DecodeError::InvalidLength) }
+
+	let mut array = [0u8; N];
+	let bytes_ptr = bytes as *const [u8] as *const u8;
+
+	unsafe {
+		// SAFETY: array has exactly the space needed
+		let mut dest = UnsafeBufWriteSlice::new(array.as_mut_ptr(), N);
+		decode::generic(bytes_ptr, &mut dest, N)?;
+		dest.assert_is_full();
+	}
+
+	Ok(array)
+}
+
+/// Decodes a hex string in place, writing the decoded bytes over the start of
+/// the input buffer, and returning the part of the buffer they were written to.
+///
+/// If an error is returned, the contents of the buffer are unspecified (part
+/// of it may have already been overwritten).
+pub fn decode_hex_in_place(bytes: &mut [u8]) -> Result<&mut [u8], DecodeError> {
+	if bytes.len() & 0b1 != 0 { return Err(DecodeError::InvalidLength) }
+
+	let len = bytes.len() >> 1;
+	let ptr = bytes.as_mut_ptr();
+
+	unsafe {
+		// SAFETY: output byte `n` is written to index `n`, after input bytes
+		// `2n` and `2n + 1` have been read, so we never overwrite anything
+		// we haven't read yet. All reads and writes go through `ptr`.
+		let mut dest = UnsafeBufWriteSlice::new(ptr, len);
+		decode::generic(ptr, &mut dest, len)?;
+		dest.assert_is_full();
+	}
+
+	Ok(&mut bytes[..len])
+}
+
+/// Amount of bytes the given hex string decodes to. Only the length of the
+/// input is checked, not its chars, so this doesn't go through the input.
+#[inline]
+pub fn decoded_len_hex(bytes: &[u8]) -> Result<usize, DecodeError> {
+	if bytes.len() & 0b1 != 0 { return Err(DecodeError::InvalidLength) }
+	Ok(bytes.len() >> 1)
+}
+
+/// Decodes a hex string, writing the decoded bytes to the start of `dest`
+/// instead of allocating, and returning the amount of bytes written.
+///
+/// If an error is returned, the contents of `dest` are unspecified (part of it
+/// may have already been written to).
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than [`decoded_len_hex`].
+pub fn decode_hex_to_slice(bytes: &[u8], dest: &mut [u8]) -> Result<usize, DecodeError> {
+	let len = decoded_len_hex(bytes)?;
+	assert!(dest.len() >= len, "dest is too short for the decoded bytes");
+
+	let bytes_ptr = bytes as *const [u8] as *const u8;
+
+	unsafe {
+		// SAFETY: we checked dest has enough space
+		let mut dest = UnsafeBufWriteSlice::new(dest.as_mut_ptr(), len);
+		decode::generic(bytes_ptr, &mut dest, len)?;
+		dest.assert_is_full();
+	}
+
+	Ok(len)
+}
+
+/// Checks that the given bytes are valid hex, returning the same error
+/// [`decode_hex`] would, but without allocating anywhere to put the
+/// decoded bytes.
+pub fn validate_hex(bytes: &[u8]) -> Result<(), DecodeError> {
+	if bytes.len() & 0b1 != 0 { return Err(DecodeError::InvalidLength) }
+
+	let bytes_ptr = bytes as *const [u8] as *const u8;
+	unsafe { decode::generic(bytes_ptr, &mut UnsafeBufWriteDiscard, bytes.len() >> 1) }
+}
+
+/// Decodes a hex string back into the source bytes, splitting the work across
+/// multiple threads for large inputs. Smaller inputs are decoded on the
+/// calling thread, the same as [`decode_hex`].
+#[inline]
+pub fn decode_hex_parallel(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode_parallel(bytes, available_threads())
+}
+
+fn _decode_parallel(bytes: &[u8], threads: usize) -> Result<Vec<u8>, DecodeError> {
+	if bytes.len() & 0b1 != 0 { return Err(DecodeError::InvalidLength) }
+
+	let Some(chunks) = parallel_chunks(bytes, 2, threads) else {
+		return decode_hex(bytes)
+	};
+
+	let capacity = bytes.len() >> 1;
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe {
+		dest.write_parallel(&chunks, |chunk| chunk.len() >> 1, |chunk, dest| {
+			let chunk_ptr = chunk as *const [u8] as *const u8;
+			decode::generic(chunk_ptr, dest, chunk.len() >> 1)
+		})?;
+	}
+
+	Ok(dest.into_full_vec())
 }
 
 #[derive(Debug, ::thiserror::Error)]
@@ -78,6 +378,129 @@ pub enum DecodeError {
 	InvalidChar
 }
 
+#[cfg(feature = "bytes")]
+pub use self::bytes::*;
+
+/// Encoding and decoding from any [`Buf`](::bytes::Buf) (which doesn't have
+/// to be contiguous), appending the output to a [`BytesMut`](::bytes::BytesMut)
+#[cfg(feature = "bytes")]
+mod bytes {
+	use crate::encoding_utils::bytes::{ decode_buf, encode_buf, BufCodec };
+	use crate::encoding_utils::UnsafeBufWriteSlice;
+	use ::bytes::{ Buf, BytesMut };
+	use super::*;
+
+	/// Hex encodes everything remaining in `src`, appending it to `dest`
+	#[inline]
+	pub fn encode_hex_buf<B: Buf>(src: B, dest: &mut BytesMut) {
+		encode_buf(&HexBufCodec::<false>, src, dest)
+	}
+
+	/// Upper hex encodes everything remaining in `src`, appending it to `dest`
+	#[inline]
+	pub fn encode_hex_upper_buf<B: Buf>(src: B, dest: &mut BytesMut) {
+		encode_buf(&HexBufCodec::<true>, src, dest)
+	}
+
+	/// Decodes the hex remaining in `src`, appending the decoded bytes to
+	/// `dest`. If an error is returned, `dest` is left as it was, but an
+	/// unspecified amount of `src` will have been consumed.
+	#[inline]
+	pub fn decode_hex_buf<B: Buf>(src: B, dest: &mut BytesMut) -> Result<(), DecodeError> {
+		decode_buf(&HexBufCodec::<false>, src, dest)
+	}
+
+	struct HexBufCodec<const UPPER: bool>;
+
+	impl<const UPPER: bool> BufCodec for HexBufCodec<UPPER> {
+		type Error = DecodeError;
+		const BINARY_FRAME_LEN: usize = 1;
+		const STRING_FRAME_LEN: usize = 2;
+
+		#[inline]
+		fn encoded_len(&self, bytes_len: usize) -> usize {
+			bytes_len * 2
+		}
+
+		#[inline]
+		unsafe fn encode_into(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) {
+			encode_into::<UPPER, _>(bytes, dest)
+		}
+
+		#[inline]
+		unsafe fn decode_frames(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) -> Result<(), DecodeError> {
+			decode::generic(bytes.as_ptr(), dest, bytes.len() >> 1)
+		}
+
+		#[inline]
+		fn decode_end(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			decode_hex(bytes)
+		}
+	}
+}
+
+#[cfg(feature = "tokio")]
+pub use self::tokio::*;
+
+/// Adapters for tokio's [`AsyncRead`](::tokio::io::AsyncRead) and
+/// [`AsyncWrite`](::tokio::io::AsyncWrite) that hex encode or decode data as
+/// it passes through. Decode errors are returned as [`io::Error`](::std::io::Error)s
+/// of kind [`InvalidData`](::std::io::ErrorKind::InvalidData), wrapping a [`DecodeError`].
+#[cfg(feature = "tokio")]
+mod tokio {
+	use crate::encoding_utils::stream::{ DecodeReader, DecodeWriter, EncodeReader, EncodeWriter, StreamCodec };
+	use super::*;
+
+	pub type HexEncodeReader<R> = EncodeReader<R, HexCodec>;
+	pub type HexUpperEncodeReader<R> = EncodeReader<R, HexUpperCodec>;
+	pub type HexDecodeReader<R> = DecodeReader<R, HexCodec>;
+	pub type HexEncodeWriter<W> = EncodeWriter<W, HexCodec>;
+	pub type HexUpperEncodeWriter<W> = EncodeWriter<W, HexUpperCodec>;
+	pub type HexDecodeWriter<W> = DecodeWriter<W, HexCodec>;
+
+	/// Lower hex codec for the stream adapters. Decoding accepts both cases.
+	#[derive(Clone, Copy, Debug, Default)]
+	pub struct HexCodec;
+
+	/// Upper hex codec for the stream adapters. Decoding accepts both cases.
+	#[derive(Clone, Copy, Debug, Default)]
+	pub struct HexUpperCodec;
+
+	impl StreamCodec for HexCodec {
+		type Error = DecodeError;
+		const BINARY_FRAME_LEN: usize = 1;
+		const STRING_FRAME_LEN: usize = 2;
+		const HOLD_LAST_FRAME: bool = false;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			encode_hex(bytes)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			decode_hex(bytes)
+		}
+	}
+
+	impl StreamCodec for HexUpperCodec {
+		type Error = DecodeError;
+		const BINARY_FRAME_LEN: usize = 1;
+		const STRING_FRAME_LEN: usize = 2;
+		const HOLD_LAST_FRAME: bool = false;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			encode_hex_upper(bytes)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			decode_hex(bytes)
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -167,6 +590,121 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn arrays() {
+		let mut rng = thread_rng();
+
+		let bytes = rng.gen::<[u8; 16]>();
+		let encoded: ArrayString<32> = encode_hex_array(&bytes);
+		assert_eq!(encoded, &*encode_hex(&bytes));
+		assert_eq!(decode_hex_array::<16>(encoded.as_bytes()).unwrap(), bytes);
+
+		let bytes = rng.gen::<[u8; 32]>();
+		let encoded: ArrayString<64> = encode_hex_upper_array(&bytes);
+		assert_eq!(encoded, &*encode_hex_upper(&bytes));
+		assert_eq!(decode_hex_array::<32>(encoded.as_bytes()).unwrap(), bytes);
+
+		let mut bytes = [0u8; 64];
+		rng.fill(&mut bytes);
+		let encoded: ArrayString<128> = encode_hex_array(&bytes);
+		assert_eq!(decode_hex_array::<64>(encoded.as_bytes()).unwrap(), bytes);
+
+		let encoded: ArrayString<0> = encode_hex_array(&[]);
+		assert_eq!(encoded, "");
+		assert_eq!(decode_hex_array::<0>(b"").unwrap(), []);
+
+		assert!(matches!(decode_hex_array::<4>(b"deadbe"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_array::<4>(b"deadbeef00"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_array::<4>(b"deadbeeg"), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn in_place() {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 2, 15, 16, 17, 1000] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let mut encoded = encode_hex(&bytes).into_bytes();
+			let decoded = decode_hex_in_place(&mut encoded)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+		}
+
+		assert!(matches!(decode_hex_in_place(&mut b"abc".to_vec()), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_in_place(&mut b"abcg".to_vec()), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn to_slice() {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 2, 15, 16, 17, 1000] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			// extra space at the end is left alone
+			let mut encoded = vec![0u8; len * 2 + 3];
+			assert_eq!(encode_hex_to_slice(&bytes, &mut encoded), len * 2);
+			assert_eq!(&encoded[..len * 2], encode_hex(&bytes).as_bytes());
+			assert_eq!(encoded[len * 2..], [0, 0, 0]);
+
+			assert_eq!(encode_hex_upper_to_slice(&bytes, &mut encoded), len * 2);
+			assert_eq!(&encoded[..len * 2], encode_hex_upper(&bytes).as_bytes());
+
+			let encoded = &encoded[..len * 2];
+			assert_eq!(decoded_len_hex(encoded).unwrap(), len);
+			let mut decoded = vec![0u8; len];
+			assert_eq!(decode_hex_to_slice(encoded, &mut decoded).unwrap(), len);
+			assert_eq!(decoded, bytes);
+		}
+
+		assert!(matches!(decoded_len_hex(b"abc"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_to_slice(b"abcg", &mut [0u8; 2]), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	#[should_panic = "too short"]
+	fn to_slice_too_short() {
+		decode_hex_to_slice(b"abcd", &mut [0u8; 1]).ok();
+	}
+
+	#[test]
+	fn validate() {
+		let mut rng = thread_rng();
+
+		let mut bytes = vec![0u8; 1000];
+		rng.fill(&mut *bytes);
+		let encoded = encode_hex(&bytes);
+		assert!(validate_hex(encoded.as_bytes()).is_ok());
+		assert!(validate_hex(b"").is_ok());
+		assert!(validate_hex(b"aBcD").is_ok());
+
+		assert!(matches!(validate_hex(b"abc"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(validate_hex(b"abcg"), Err(DecodeError::InvalidChar)));
+		assert!(matches!(validate_hex(b"ab c"), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn backends() {
+		use crate::encoding_utils::dispatch::with_backend;
+
+		let mut rng = thread_rng();
+
+		for backend in Backend::ALL {
+			with_backend(backend, || {
+				for len in (0usize..100).chain([1000, 1001, 100_000]) {
+					let mut bytes = vec![0u8; len];
+					rng.fill(&mut *bytes);
+
+					assert_eq!(encode_hex(&bytes), ::hex::encode(&bytes));
+					assert_eq!(encode_hex_upper(&bytes), ::hex::encode_upper(&bytes));
+				}
+			});
+		}
+	}
+
 	#[test]
 	fn hex_crate_compat() {
 		let mut rng = thread_rng();
@@ -186,4 +724,148 @@ mod tests {
 
 		assert_eq!(wiwi_decoded_hex, hex_decoded_wiwi);
 	}
+
+	#[test]
+	fn parallel() {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 10, 5 * 1024 * 1024, 5 * 1024 * 1024 + 3] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			// always split as if there are 4 cores, so the parallel
+			// path gets exercised on single core machines too
+			let encoded_lower = _encode_parallel::<false>(&bytes, 4);
+			let encoded_upper = _encode_parallel::<true>(&bytes, 4);
+			assert_eq!(encoded_lower, encode_hex(&bytes));
+			assert_eq!(encoded_upper, encode_hex_upper(&bytes));
+			assert_eq!(encoded_lower, encode_hex_parallel(&bytes));
+			assert_eq!(encoded_upper, encode_hex_upper_parallel(&bytes));
+
+			let decoded_lower = _decode_parallel(encoded_lower.as_bytes(), 4)
+				.expect("can round trip decode just encoded data");
+			let decoded_upper = _decode_parallel(encoded_upper.as_bytes(), 4)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded_lower);
+			assert_eq!(bytes, decoded_upper);
+			assert_eq!(decoded_lower.len(), decoded_lower.capacity());
+			assert_eq!(bytes, decode_hex_parallel(encoded_lower.as_bytes()).unwrap());
+		}
+
+		let mut invalid = encode_hex(&vec![0u8; 5 * 1024 * 1024]).into_bytes();
+		invalid[3 * 1024 * 1024] = b'g';
+		assert!(matches!(_decode_parallel(&invalid, 4), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_hex_parallel(&invalid), Err(DecodeError::InvalidChar)));
+	}
+
+	#[cfg(feature = "tokio")]
+	#[::tokio::test(crate = "::tokio")]
+	async fn stream() {
+		use crate::encoding_utils::stream::test_utils::Trickle;
+		use ::tokio::io::{ AsyncReadExt, AsyncWriteExt };
+
+		let mut rng = thread_rng();
+		let mut bytes = vec![0u8; 100_000];
+		rng.fill(&mut *bytes);
+		let encoded = encode_hex(&bytes);
+
+		for n in [1, 3, 7, 10_000] {
+			let mut reader = HexEncodeReader::new(Trickle::new(&bytes, n));
+			let mut out = String::new();
+			reader.read_to_string(&mut out).await.unwrap();
+			assert_eq!(encoded, out);
+
+			let mut reader = HexDecodeReader::new(Trickle::new(encoded.as_bytes(), n));
+			let mut out = Vec::new();
+			reader.read_to_end(&mut out).await.unwrap();
+			assert_eq!(bytes, out);
+
+			let mut writer = HexUpperEncodeWriter::new(Vec::new());
+			for chunk in bytes.chunks(n) {
+				writer.write_all(chunk).await.unwrap();
+			}
+			writer.shutdown().await.unwrap();
+			assert_eq!(encode_hex_upper(&bytes).as_bytes(), writer.into_inner());
+
+			let mut writer = HexDecodeWriter::new(Vec::new());
+			for chunk in encoded.as_bytes().chunks(n) {
+				writer.write_all(chunk).await.unwrap();
+			}
+			writer.shutdown().await.unwrap();
+			assert_eq!(bytes, writer.into_inner());
+		}
+
+		let mut reader = HexDecodeReader::new(Trickle::new(b"abcdefgh", 3));
+		let err = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+		assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidData);
+		assert!(matches!(err.get_ref().and_then(|e| e.downcast_ref()), Some(DecodeError::InvalidChar)));
+
+		let mut writer = HexDecodeWriter::new(Vec::new());
+		writer.write_all(b"abc").await.unwrap();
+		let err = writer.shutdown().await.unwrap_err();
+		assert!(matches!(err.get_ref().and_then(|e| e.downcast_ref()), Some(DecodeError::InvalidLength)));
+	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn bytes() {
+		use crate::encoding_utils::bytes::test_utils::Chunked;
+		use ::bytes::BytesMut;
+
+		let mut rng = thread_rng();
+		let mut bytes = vec![0u8; 1000];
+		rng.fill(&mut *bytes);
+		let encoded = encode_hex(&bytes);
+
+		for n in [1, 3, 7, 1000] {
+			let mut dest = BytesMut::from(&b"prefix"[..]);
+			encode_hex_buf(Chunked::new(&bytes, n), &mut dest);
+			assert_eq!(&dest[..6], b"prefix");
+			assert_eq!(&dest[6..], encoded.as_bytes());
+
+			let mut dest = BytesMut::new();
+			encode_hex_upper_buf(Chunked::new(&bytes, n), &mut dest);
+			assert_eq!(&dest[..], encode_hex_upper(&bytes).as_bytes());
+
+			let mut dest = BytesMut::from(&b"prefix"[..]);
+			decode_hex_buf(Chunked::new(encoded.as_bytes(), n), &mut dest).unwrap();
+			assert_eq!(&dest[..6], b"prefix");
+			assert_eq!(&dest[6..], &*bytes);
+		}
+
+		let mut dest = BytesMut::new();
+		decode_hex_buf(&b""[..], &mut dest).unwrap();
+		assert!(dest.is_empty());
+
+		let mut dest = BytesMut::from(&b"prefix"[..]);
+		assert!(matches!(decode_hex_buf(Chunked::new(b"abcdefgh0", 2), &mut dest), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_buf(Chunked::new(b"abcdefgh00", 3), &mut dest), Err(DecodeError::InvalidChar)));
+		assert_eq!(&dest[..], b"prefix");
+	}
+
+	#[test]
+	fn vectored() {
+		use crate::encoding_utils::test_utils::random_slices;
+		use ::std::io::IoSlice;
+
+		let mut rng = thread_rng();
+
+		for len in (0usize..40).chain([1000]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let slices = random_slices(&bytes, &mut rng);
+			let io_slices = slices.iter().map(|s| IoSlice::new(s)).collect::<Vec<_>>();
+
+			assert_eq!(encode_hex_vectored(&*slices), encode_hex(&bytes));
+			assert_eq!(encode_hex_vectored(&*io_slices), encode_hex(&bytes));
+			assert_eq!(encode_hex_upper_vectored(&*io_slices), encode_hex_upper(&bytes));
+
+			#[cfg(feature = "string-pool")]
+			assert_eq!(
+				encode_hex_vectored(&crate::string_pool::pool::SlicesWrap(&slices)),
+				encode_hex(&bytes)
+			);
+		}
+	}
 }
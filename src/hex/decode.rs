@@ -1,8 +1,8 @@
-use crate::encoding_utils::UnsafeBufWriteGuard;
-use super::DecodeError;
+use crate::encoding_utils::UnsafeBufWrite;
+use super::{ DecodeError, TABLE_DECODER_LEN };
 
-// table is 256 long
-static TABLE_DECODER: &[Option<u8>] = &[
+// generated by `generate-hex-table-decoder`, a binary in this crate
+pub static TABLE_DECODER: [Option<u8>; TABLE_DECODER_LEN] = [
 	None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,
 	None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,
 	None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,       None,
@@ -23,12 +23,12 @@ static TABLE_DECODER: &[Option<u8>] = &[
 
 /// number of rounds is the same as input / 2,
 /// or the count of output bytes
-pub(super) unsafe fn generic(
+pub(super) unsafe fn generic<W: UnsafeBufWrite>(
 	mut bytes_ptr: *const u8,
-	dest: &mut UnsafeBufWriteGuard,
+	dest: &mut W,
 	rounds: usize
 ) -> Result<(), DecodeError> {
-	let table_ptr = TABLE_DECODER as *const [Option<u8>] as *const Option<u8>;
+	let table_ptr = &TABLE_DECODER as *const [Option<u8>] as *const Option<u8>;
 
 	for _ in 0..rounds {
 		unsafe {
@@ -1,8 +1,8 @@
-use crate::encoding_utils::UnsafeBufWriteGuard;
+use crate::encoding_utils::UnsafeBufWrite;
 
-pub(super) unsafe fn generic<const UPPER: bool>(
+pub(super) unsafe fn generic<const UPPER: bool, W: UnsafeBufWrite>(
 	mut bytes_ptr: *const u8,
-	dest: &mut UnsafeBufWriteGuard,
+	dest: &mut W,
 	num_rounds: usize
 ) {
 	let char_a = if UPPER { b'A' } else { b'a' } - 10;
@@ -25,17 +25,20 @@ pub(super) unsafe fn generic<const UPPER: bool>(
 	}
 }
 
-/// num_rounds should be equivalent to number of bytes divided by 16.
-/// This function processes 16 bytes at a time
+/// Encodes as many whole blocks of 16 bytes as there are in `bytes_len`,
+/// returning the amount of bytes processed (the rest is left for [`generic`])
 #[cfg(target_arch = "aarch64")]
 #[target_feature(enable = "neon")]
 pub(super) unsafe fn neon_uint8x16<const UPPER: bool>(
 	mut bytes_ptr: *const u8,
 	mut dest_ptr: *mut u8,
-	rounds: usize
-) -> *const u8 {
+	bytes_len: usize
+) -> usize {
 	use ::std::arch::aarch64::*;
 
+	// divide by 16
+	let rounds = bytes_len >> 4;
+
 	let four_lower_bits = vdupq_n_u8(0xf);
 	let nine = vdupq_n_u8(9);
 	let char_a = vdupq_n_u8(if UPPER { b'A' } else { b'a' } - 10);
@@ -72,5 +75,99 @@ pub(super) unsafe fn neon_uint8x16<const UPPER: bool>(
 		dest_ptr = dest_ptr.add(32);
 	}
 
-	bytes_ptr
+	rounds << 4
+}
+
+/// Encodes as many whole blocks of 16 bytes as there are in `bytes_len`,
+/// returning the amount of bytes processed (the rest is left for [`generic`])
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
+pub(super) unsafe fn sse_m128i<const UPPER: bool>(
+	mut bytes_ptr: *const u8,
+	mut dest_ptr: *mut u8,
+	bytes_len: usize
+) -> usize {
+	#[cfg(target_arch = "x86")]
+	use ::std::arch::x86::*;
+	#[cfg(target_arch = "x86_64")]
+	use ::std::arch::x86_64::*;
+
+	// divide by 16
+	let rounds = bytes_len >> 4;
+
+	let table = if UPPER { super::TABLE_ENCODER_UPPER } else { super::TABLE_ENCODER_LOWER };
+	let table = _mm_loadu_si128(&table as *const [u8; 16] as *const __m128i);
+	let four_lower_bits = _mm_set1_epi8(0xf);
+
+	for _ in 0..rounds {
+		// load 16 u8 vals
+		let vec = _mm_loadu_si128(bytes_ptr as *const __m128i);
+
+		// get upper 4 bits and lower 4 bits into 2 seperate vecs. there's no
+		// 8 bit shift, so shift as 16 bit and mask off what came from the
+		// neighbouring byte
+		let upper_vals = _mm_and_si128(_mm_srli_epi16::<4>(vec), four_lower_bits);
+		let lower_vals = _mm_and_si128(vec, four_lower_bits);
+
+		// look up the chars in the table
+		let upper = _mm_shuffle_epi8(table, upper_vals);
+		let lower = _mm_shuffle_epi8(table, lower_vals);
+
+		// zip bytes together and write to out ptr
+		_mm_storeu_si128(dest_ptr as *mut __m128i, _mm_unpacklo_epi8(upper, lower));
+		_mm_storeu_si128(dest_ptr.add(16) as *mut __m128i, _mm_unpackhi_epi8(upper, lower));
+
+		bytes_ptr = bytes_ptr.add(16);
+		dest_ptr = dest_ptr.add(32);
+	}
+
+	rounds << 4
+}
+
+/// Encodes as many whole blocks of 32 bytes as there are in `bytes_len`,
+/// returning the amount of bytes processed (the rest is left for [`generic`])
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn avx2_m256i<const UPPER: bool>(
+	mut bytes_ptr: *const u8,
+	mut dest_ptr: *mut u8,
+	bytes_len: usize
+) -> usize {
+	#[cfg(target_arch = "x86")]
+	use ::std::arch::x86::*;
+	#[cfg(target_arch = "x86_64")]
+	use ::std::arch::x86_64::*;
+
+	// divide by 32
+	let rounds = bytes_len >> 5;
+
+	let table = if UPPER { super::TABLE_ENCODER_UPPER } else { super::TABLE_ENCODER_LOWER };
+	// shuffles only work within each 128 bit lane, so both lanes need the table
+	let table = _mm256_broadcastsi128_si256(_mm_loadu_si128(&table as *const [u8; 16] as *const __m128i));
+	let four_lower_bits = _mm256_set1_epi8(0xf);
+
+	for _ in 0..rounds {
+		// load 32 u8 vals
+		let vec = _mm256_loadu_si256(bytes_ptr as *const __m256i);
+
+		let upper_vals = _mm256_and_si256(_mm256_srli_epi16::<4>(vec), four_lower_bits);
+		let lower_vals = _mm256_and_si256(vec, four_lower_bits);
+
+		let upper = _mm256_shuffle_epi8(table, upper_vals);
+		let lower = _mm256_shuffle_epi8(table, lower_vals);
+
+		// unpacking also happens per lane, so this ends up with input bytes
+		// [0..8, 16..24] in `lo` and [8..16, 24..32] in `hi`
+		let lo = _mm256_unpacklo_epi8(upper, lower);
+		let hi = _mm256_unpackhi_epi8(upper, lower);
+
+		// put the lanes back in order and write to out ptr
+		_mm256_storeu_si256(dest_ptr as *mut __m256i, _mm256_permute2x128_si256::<0x20>(lo, hi));
+		_mm256_storeu_si256(dest_ptr.add(32) as *mut __m256i, _mm256_permute2x128_si256::<0x31>(lo, hi));
+
+		bytes_ptr = bytes_ptr.add(32);
+		dest_ptr = dest_ptr.add(64);
+	}
+
+	rounds << 5
 }
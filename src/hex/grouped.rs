@@ -0,0 +1,220 @@
+use crate::encoding_utils::{ UnsafeBufWrite, UnsafeBufWriteGuard };
+use super::{ decode, encode_into, DecodeError };
+
+/// Layout for hex strings split into groups, like `AA:BB:CC` (MAC addresses),
+/// `aabb ccdd`, or `AABB-CCDD`, used by [`encode_hex_grouped`] and
+/// [`decode_hex_grouped`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexFormat {
+	separator: char,
+	group_len: usize,
+	upper: bool
+}
+
+impl HexFormat {
+	/// `AA:BB:CC:DD:EE:FF`
+	pub const MAC: HexFormat = HexFormat::new(':', 1).upper();
+
+	/// Creates a new format with groups of `group_len` bytes (ie. `group_len * 2`
+	/// hex chars) separated by `separator`, encoding to lower hex.
+	///
+	/// # Panics
+	///
+	/// Panics if `group_len` is 0, or if `separator` is a hex digit (it would
+	/// be impossible to tell apart from the hex when decoding).
+	pub const fn new(separator: char, group_len: usize) -> Self {
+		assert!(group_len > 0, "group length must be at least 1");
+		assert!(!separator.is_ascii_hexdigit(), "separator cannot be a hex digit");
+		Self { separator, group_len, upper: false }
+	}
+
+	/// Encode to upper hex
+	#[inline]
+	pub const fn upper(mut self) -> Self {
+		self.upper = true;
+		self
+	}
+
+	/// Encode to lower hex (the default)
+	#[inline]
+	pub const fn lower(mut self) -> Self {
+		self.upper = false;
+		self
+	}
+
+	#[inline]
+	pub const fn separator(&self) -> char {
+		self.separator
+	}
+
+	/// Amount of bytes per group
+	#[inline]
+	pub const fn group_len(&self) -> usize {
+		self.group_len
+	}
+
+	#[inline]
+	pub const fn is_upper(&self) -> bool {
+		self.upper
+	}
+}
+
+/// Encodes the given bytes into hex, split into groups as set in `format`
+pub fn encode_hex_grouped(bytes: &[u8], format: &HexFormat) -> String {
+	if format.upper {
+		_encode_grouped::<true>(bytes, format)
+	} else {
+		_encode_grouped::<false>(bytes, format)
+	}
+}
+
+fn _encode_grouped<const UPPER: bool>(bytes: &[u8], format: &HexFormat) -> String {
+	let mut separator = [0u8; 4];
+	let separator = format.separator.encode_utf8(&mut separator).as_bytes();
+
+	let groups = bytes.len().div_ceil(format.group_len);
+	let capacity = (bytes.len() * 2) + (groups.saturating_sub(1) * separator.len());
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	let mut chunks = bytes.chunks(format.group_len);
+	unsafe {
+		// SAFETY: we preallocated exact amount of memory needed, two chars for
+		// every byte, and one separator between every two groups
+		if let Some(first) = chunks.next() {
+			encode_into::<UPPER, _>(first, &mut dest);
+		}
+		for chunk in chunks {
+			dest.write_bytes(separator.as_ptr(), separator.len());
+			encode_into::<UPPER, _>(chunk, &mut dest);
+		}
+	}
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+
+	// SAFETY: the hex chars are all ASCII, and the separator is a whole
+	// (utf-8 encoded) char
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Decodes hex split into groups as set in `format` back into the source bytes.
+/// Both upper and lower hex are accepted, regardless of the case set in `format`.
+///
+/// Every group except for the last must be the full group length, and groups
+/// must be separated by exactly one separator (no leading or trailing
+/// separators). If the separators are in the wrong place, either
+/// [`DecodeError::InvalidLength`] or [`DecodeError::InvalidChar`] is returned.
+pub fn decode_hex_grouped(bytes: &[u8], format: &HexFormat) -> Result<Vec<u8>, DecodeError> {
+	if bytes.is_empty() { return Ok(Vec::new()) }
+
+	let mut separator = [0u8; 4];
+	let separator = format.separator.encode_utf8(&mut separator).as_bytes();
+
+	let group_chars = format.group_len * 2;
+	// pretend there's a separator after the last group too, so the input is
+	// made of whole (group + separator) units, except maybe a short last group
+	let unit_len = group_chars + separator.len();
+	let padded_len = bytes.len() + separator.len();
+
+	let whole_units = padded_len / unit_len;
+	let capacity = match padded_len % unit_len {
+		0 => { whole_units * format.group_len }
+		rem => {
+			// the short last group, needs to be a non zero, even amount of chars
+			let last_chars = rem.checked_sub(separator.len())
+				.filter(|chars| *chars > 0 && chars & 0b1 == 0)
+				.ok_or(DecodeError::InvalidLength)?;
+			(whole_units * format.group_len) + (last_chars >> 1)
+		}
+	};
+
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+	let mut remaining = bytes;
+
+	loop {
+		let group_len = remaining.len().min(group_chars);
+		let (group, rest) = remaining.split_at(group_len);
+
+		// SAFETY: the amount of output bytes was calculated from the layout
+		// above, and group has an even amount of chars
+		unsafe { decode::generic(group.as_ptr(), &mut dest, group_len >> 1)? }
+
+		if rest.is_empty() { break }
+
+		let Some(rest) = rest.strip_prefix(separator) else {
+			return Err(DecodeError::InvalidChar)
+		};
+		remaining = rest;
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use super::super::encode_hex;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn formats() {
+		let bytes = [0xaa, 0xbb, 0xcc, 0xdd, 0xee];
+
+		let cases = [
+			(HexFormat::MAC, "AA:BB:CC:DD:EE"),
+			(HexFormat::new(' ', 2), "aabb ccdd ee"),
+			(HexFormat::new('-', 2).upper(), "AABB-CCDD-EE"),
+			(HexFormat::new('-', 5), "aabbccddee"),
+			(HexFormat::new('-', 10), "aabbccddee"),
+			(HexFormat::new('·', 3), "aabbcc·ddee")
+		];
+
+		for (format, encoded) in cases {
+			assert_eq!(encode_hex_grouped(&bytes, &format), encoded);
+			assert_eq!(decode_hex_grouped(encoded.as_bytes(), &format).unwrap(), bytes);
+		}
+
+		assert_eq!(encode_hex_grouped(&[], &HexFormat::MAC), "");
+		assert_eq!(decode_hex_grouped(b"", &HexFormat::MAC).unwrap(), []);
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		for group_len in 1..6 {
+			for separator in [':', ' ', '—'] {
+				let format = HexFormat::new(separator, group_len);
+
+				for len in 0..40 {
+					let mut bytes = vec![0u8; len];
+					rng.fill(&mut *bytes);
+
+					let encoded = encode_hex_grouped(&bytes, &format);
+					assert_eq!(encoded.len(), encoded.capacity());
+					assert_eq!(encoded.replace(separator, ""), encode_hex(&bytes));
+
+					let decoded = decode_hex_grouped(encoded.as_bytes(), &format)
+						.expect("can round trip decode just encoded data");
+					assert_eq!(decoded, bytes);
+					assert_eq!(decoded.len(), decoded.capacity());
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn invalid() {
+		let mac = HexFormat::MAC;
+		assert!(matches!(decode_hex_grouped(b"AA:BB:C", &mac), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_grouped(b"AA:BB:", &mac), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_grouped(b":AA:BB", &mac), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_grouped(b"AAB:B:CC", &mac), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_hex_grouped(b"AA-BB-CC", &mac), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_hex_grouped(b"AA:BG:CC", &mac), Err(DecodeError::InvalidChar)));
+
+		let spaced = HexFormat::new(' ', 2);
+		assert!(matches!(decode_hex_grouped(b"aabb ccd", &spaced), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_hex_grouped(b"aabbccdd", &spaced), Err(DecodeError::InvalidLength)));
+	}
+}
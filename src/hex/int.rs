@@ -0,0 +1,213 @@
+use super::{ _encode_array, decode_hex_array, ArrayString, DecodeError, TABLE_DECODER };
+
+/// Byte order to encode integers in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+	/// Most significant byte first, how numbers are usually written
+	/// (ex. `0x1234u16` encodes to `1234`)
+	BigEndian,
+	/// Least significant byte first (ex. `0x1234u16` encodes to `3412`)
+	LittleEndian
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum ParseIntError {
+	#[error("invalid length")]
+	InvalidLength,
+	#[error("invalid character")]
+	InvalidChar,
+	#[error("value does not fit in the integer type")]
+	Overflow
+}
+
+/// Unsigned integers that can be encoded to and decoded from fixed width hex
+/// ([`u8`], [`u16`], [`u32`], [`u64`], and [`u128`])
+pub trait HexInt: Copy + private::Sealed {
+	/// Stack string fitting exactly the hex of this integer (two chars per byte)
+	type Encoded;
+
+	#[doc(hidden)]
+	fn _encode<const UPPER: bool>(self, order: ByteOrder) -> Self::Encoded;
+
+	#[doc(hidden)]
+	fn _decode(bytes: &[u8], order: ByteOrder) -> Result<Self, DecodeError>;
+
+	#[doc(hidden)]
+	fn _parse(bytes: &[u8]) -> Result<Self, ParseIntError>;
+}
+
+mod private {
+	pub trait Sealed {}
+}
+
+/// Encodes an integer into fixed width (zero padded) lower hex, two chars for
+/// every byte of the integer, in the given byte order
+#[inline]
+pub fn encode_int_hex<T: HexInt>(int: T, order: ByteOrder) -> T::Encoded {
+	int._encode::<false>(order)
+}
+
+/// Encodes an integer into fixed width (zero padded) upper hex, two chars for
+/// every byte of the integer, in the given byte order
+#[inline]
+pub fn encode_int_hex_upper<T: HexInt>(int: T, order: ByteOrder) -> T::Encoded {
+	int._encode::<true>(order)
+}
+
+/// Decodes fixed width hex, as encoded by [`encode_int_hex`], back into an
+/// integer. The input must be exactly two chars for every byte of the integer,
+/// otherwise [`DecodeError::InvalidLength`] is returned.
+#[inline]
+pub fn decode_int_hex<T: HexInt>(bytes: &[u8], order: ByteOrder) -> Result<T, DecodeError> {
+	T::_decode(bytes, order)
+}
+
+/// Parses big endian hex of any (non zero) length into an integer, the same
+/// way a number is usually written. Leading zeros are allowed, and don't count
+/// towards the width of the integer.
+///
+/// Returns [`ParseIntError::Overflow`] if the value is too big to fit.
+#[inline]
+pub fn parse_int_hex<T: HexInt>(bytes: &[u8]) -> Result<T, ParseIntError> {
+	T::_parse(bytes)
+}
+
+/// Parses into a u128, checking that it fits in `max_chars` significant chars
+#[inline]
+fn parse(bytes: &[u8], max_chars: usize) -> Result<u128, ParseIntError> {
+	if bytes.is_empty() { return Err(ParseIntError::InvalidLength) }
+
+	let leading_zeros = bytes.iter().take_while(|b| **b == b'0').count();
+	let significant = &bytes[leading_zeros..];
+
+	let mut int = 0u128;
+	for byte in significant {
+		let Some(value) = TABLE_DECODER[*byte as usize] else {
+			return Err(ParseIntError::InvalidChar)
+		};
+		// overflowing values get checked below, after all the chars are checked
+		int = int.wrapping_shl(4) | value as u128;
+	}
+
+	if significant.len() > max_chars { return Err(ParseIntError::Overflow) }
+	Ok(int)
+}
+
+macro_rules! impl_hex_int {
+	($($int:ident $bytes:literal $chars:literal)*) => {
+		$(
+			impl private::Sealed for $int {}
+
+			impl HexInt for $int {
+				type Encoded = ArrayString<$chars>;
+
+				#[inline]
+				fn _encode<const UPPER: bool>(self, order: ByteOrder) -> ArrayString<$chars> {
+					let bytes = match order {
+						ByteOrder::BigEndian => { self.to_be_bytes() }
+						ByteOrder::LittleEndian => { self.to_le_bytes() }
+					};
+					_encode_array::<UPPER, $bytes, $chars>(&bytes)
+				}
+
+				#[inline]
+				fn _decode(bytes: &[u8], order: ByteOrder) -> Result<$int, DecodeError> {
+					let bytes = decode_hex_array::<$bytes>(bytes)?;
+					let int = match order {
+						ByteOrder::BigEndian => { $int::from_be_bytes(bytes) }
+						ByteOrder::LittleEndian => { $int::from_le_bytes(bytes) }
+					};
+					Ok(int)
+				}
+
+				#[inline]
+				fn _parse(bytes: &[u8]) -> Result<$int, ParseIntError> {
+					// parse checked that it fits in $chars chars
+					parse(bytes, $chars).map(|int| int as $int)
+				}
+			}
+		)*
+	}
+}
+
+impl_hex_int! {
+	u8 1 2
+	u16 2 4
+	u32 4 8
+	u64 8 16
+	u128 16 32
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn known_values() {
+		use ByteOrder::*;
+
+		assert_eq!(encode_int_hex(0xabu8, BigEndian), "ab");
+		assert_eq!(encode_int_hex(0x1234u16, BigEndian), "1234");
+		assert_eq!(encode_int_hex(0x1234u16, LittleEndian), "3412");
+		assert_eq!(encode_int_hex(0xdeadbeefu32, BigEndian), "deadbeef");
+		assert_eq!(encode_int_hex_upper(0xdeadbeefu32, LittleEndian), "EFBEADDE");
+		assert_eq!(encode_int_hex(1u64, BigEndian), "0000000000000001");
+		assert_eq!(
+			encode_int_hex(0x0123456789abcdef_fedcba9876543210u128, BigEndian),
+			"0123456789abcdeffedcba9876543210"
+		);
+
+		assert_eq!(decode_int_hex::<u16>(b"3412", LittleEndian).unwrap(), 0x1234);
+		assert_eq!(decode_int_hex::<u32>(b"DEADbeef", BigEndian).unwrap(), 0xdeadbeef);
+
+		assert_eq!(parse_int_hex::<u8>(b"f").unwrap(), 0xf);
+		assert_eq!(parse_int_hex::<u8>(b"000000ff").unwrap(), 0xff);
+		assert_eq!(parse_int_hex::<u16>(b"0").unwrap(), 0);
+		assert_eq!(parse_int_hex::<u128>(&[b'f'; 32]).unwrap(), u128::MAX);
+	}
+
+	#[test]
+	fn errors() {
+		use ByteOrder::*;
+
+		assert!(matches!(decode_int_hex::<u32>(b"deadbee", BigEndian), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_int_hex::<u32>(b"deadbeef00", BigEndian), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_int_hex::<u32>(b"deadbeeg", BigEndian), Err(DecodeError::InvalidChar)));
+
+		assert!(matches!(parse_int_hex::<u8>(b""), Err(ParseIntError::InvalidLength)));
+		assert!(matches!(parse_int_hex::<u8>(b"100"), Err(ParseIntError::Overflow)));
+		assert!(matches!(parse_int_hex::<u128>(&[b'f'; 33]), Err(ParseIntError::Overflow)));
+		assert!(matches!(parse_int_hex::<u16>(b"-1"), Err(ParseIntError::InvalidChar)));
+		assert!(matches!(parse_int_hex::<u8>(b"1000g"), Err(ParseIntError::InvalidChar)));
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		macro_rules! check {
+			($($int:ident)*) => {
+				$(
+					for _ in 0..1000 {
+						let int = rng.gen::<$int>();
+
+						for order in [ByteOrder::BigEndian, ByteOrder::LittleEndian] {
+							let encoded = encode_int_hex(int, order);
+							assert_eq!(decode_int_hex::<$int>(encoded.as_bytes(), order).unwrap(), int);
+							let encoded = encode_int_hex_upper(int, order);
+							assert_eq!(decode_int_hex::<$int>(encoded.as_bytes(), order).unwrap(), int);
+						}
+
+						let encoded = encode_int_hex(int, ByteOrder::BigEndian);
+						assert_eq!(encoded, &*format!("{int:0width$x}", width = encoded.len()));
+						assert_eq!(parse_int_hex::<$int>(encoded.as_bytes()).unwrap(), int);
+						assert_eq!(parse_int_hex::<$int>(format!("{int:x}").as_bytes()).unwrap(), int);
+					}
+				)*
+			}
+		}
+
+		check! { u8 u16 u32 u64 u128 }
+	}
+}
@@ -0,0 +1,554 @@
+//! UUIDs ([RFC 9562]) and [ULID]s: parsing, formatting, generation, and
+//! conversion between the two.
+//!
+//! Both are 128 bit values, so converting between them keeps all the bits as
+//! is. A ULID converted to a UUID is not a valid v7 UUID though (the version
+//! and variant bits will be whatever the random bits happened to be), and a v7
+//! UUID converted to a ULID keeps its timestamp, but has the version and
+//! variant bits in its "random" part.
+//!
+//! [RFC 9562]: https://datatracker.ietf.org/doc/html/rfc9562
+//! [ULID]: https://github.com/ulid/spec
+
+use crate::base32::{ TABLE_DECODER_CROCKFORD, TABLE_ENCODER_CROCKFORD };
+use crate::encoding_utils::ArrayString;
+use crate::hex::{ decode_hex_array, encode_hex_array, encode_hex_upper_array };
+use ::chrono::{ DateTime, Utc };
+use ::std::fmt;
+use ::std::str::FromStr;
+
+/// Length of a UUID in its hyphenated form (8-4-4-4-12 hex digits)
+pub const UUID_LEN: usize = 36;
+/// Length of a UUID in its simple form (32 hex digits)
+pub const UUID_SIMPLE_LEN: usize = 32;
+/// Length of a ULID (26 Crockford base32 chars)
+pub const ULID_LEN: usize = 26;
+
+/// Indices of the hyphens in a hyphenated UUID
+const HYPHENS: [usize; 4] = [8, 13, 18, 23];
+
+/// Largest timestamp that fits in the 48 bits available in v7 UUIDs and ULIDs
+pub const MAX_TIMESTAMP_MS: u64 = (1 << 48) - 1;
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum ParseError {
+	#[error("invalid length")]
+	InvalidLength,
+	#[error("invalid character")]
+	InvalidChar,
+	#[error("value does not fit in 128 bits")]
+	Overflow
+}
+
+/// Returned from [`UlidGenerator::generate`] and
+/// [`UlidGenerator::generate_at`]
+#[derive(Debug, ::thiserror::Error)]
+pub enum GenerateError {
+	/// More ULIDs have been generated within one millisecond than the random
+	/// part can count up to
+	#[error("ran out of ULIDs for this millisecond")]
+	MonotonicOverflow,
+	/// The timestamp is after [`MAX_TIMESTAMP_MS`], so it doesn't fit in a ULID
+	#[error("timestamp does not fit in 48 bits")]
+	TimestampOutOfRange
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Uuid {
+	bytes: [u8; 16]
+}
+
+impl Uuid {
+	/// The nil UUID, all bits set to 0
+	pub const NIL: Uuid = Uuid { bytes: [0; 16] };
+	/// The max UUID, all bits set to 1
+	pub const MAX: Uuid = Uuid { bytes: [0xff; 16] };
+
+	#[inline]
+	pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+		Self { bytes }
+	}
+
+	#[inline]
+	pub const fn from_u128(int: u128) -> Self {
+		Self { bytes: int.to_be_bytes() }
+	}
+
+	#[inline]
+	pub const fn as_bytes(&self) -> &[u8; 16] {
+		&self.bytes
+	}
+
+	#[inline]
+	pub const fn to_u128(&self) -> u128 {
+		u128::from_be_bytes(self.bytes)
+	}
+
+	/// Generates a random (version 4) UUID
+	pub fn new_v4() -> Self {
+		Self::from_random_v4(random_bytes())
+	}
+
+	/// Creates a version 4 UUID from the given random bytes, setting the
+	/// version and variant bits
+	pub const fn from_random_v4(mut bytes: [u8; 16]) -> Self {
+		bytes[6] = (bytes[6] & 0x0f) | 0x40;
+		bytes[8] = (bytes[8] & 0x3f) | 0x80;
+		Self { bytes }
+	}
+
+	/// Generates a time ordered (version 7) UUID, using the current time
+	pub fn new_v7() -> Self {
+		Self::from_parts_v7(now_ms(), random_bytes())
+	}
+
+	/// Creates a version 7 UUID with the given unix timestamp in milliseconds
+	/// (only the lower 48 bits are used), filling the rest with the given
+	/// random bytes (the first 6 are ignored), and setting the version and
+	/// variant bits.
+	pub const fn from_parts_v7(timestamp_ms: u64, random: [u8; 16]) -> Self {
+		let timestamp = timestamp_ms.to_be_bytes();
+		let mut bytes = random;
+
+		let mut i = 0;
+		while i < 6 {
+			bytes[i] = timestamp[i + 2];
+			i += 1;
+		}
+
+		bytes[6] = (bytes[6] & 0x0f) | 0x70;
+		bytes[8] = (bytes[8] & 0x3f) | 0x80;
+		Self { bytes }
+	}
+
+	/// The version number of this UUID (ex. 4 for random UUIDs). This isn't
+	/// meaningful for the nil and max UUIDs, or for UUIDs that aren't of the
+	/// RFC 9562 variant.
+	#[inline]
+	pub const fn version(&self) -> u8 {
+		self.bytes[6] >> 4
+	}
+
+	/// Whether the variant bits are set to the variant of RFC 9562 (which is
+	/// the one used by all UUIDs generated by this module)
+	#[inline]
+	pub const fn is_rfc_variant(&self) -> bool {
+		self.bytes[8] >> 6 == 0b10
+	}
+
+	/// The timestamp of a version 7 UUID (`None` for any other version)
+	pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+		if self.version() != 7 { return None }
+		DateTime::from_timestamp_millis(self.timestamp_ms() as i64)
+	}
+
+	/// The first 48 bits, which is the timestamp in version 7 UUIDs
+	#[inline]
+	const fn timestamp_ms(&self) -> u64 {
+		(self.to_u128() >> 80) as u64
+	}
+
+	/// Parses a UUID in its hyphenated form (`67e55044-10b1-426f-9247-bb680e5fe0c8`)
+	/// or simple form (`67e5504410b1426f9247bb680e5fe0c8`), in either case
+	pub fn parse(bytes: &[u8]) -> Result<Self, ParseError> {
+		let simple = match bytes.len() {
+			UUID_SIMPLE_LEN => {
+				// SAFETY: just checked the length
+				unsafe { *(bytes as *const [u8] as *const [u8; UUID_SIMPLE_LEN]) }
+			}
+			UUID_LEN => {
+				if HYPHENS.iter().any(|i| bytes[*i] != b'-') {
+					return Err(ParseError::InvalidChar)
+				}
+
+				let mut simple = [0u8; UUID_SIMPLE_LEN];
+				let mut written = 0;
+				let mut start = 0;
+				for end in HYPHENS.into_iter().chain([UUID_LEN]) {
+					let group = &bytes[start..end];
+					simple[written..written + group.len()].copy_from_slice(group);
+					written += group.len();
+					start = end + 1;
+				}
+
+				simple
+			}
+			_ => { return Err(ParseError::InvalidLength) }
+		};
+
+		decode_hex_array::<16>(&simple)
+			.map(Self::from_bytes)
+			.map_err(|_| ParseError::InvalidChar)
+	}
+
+	/// Formats this UUID in its hyphenated form, in lowercase
+	#[inline]
+	pub fn encode(&self) -> ArrayString<UUID_LEN> {
+		hyphenate(encode_hex_array(&self.bytes))
+	}
+
+	/// Formats this UUID in its hyphenated form, in uppercase
+	#[inline]
+	pub fn encode_upper(&self) -> ArrayString<UUID_LEN> {
+		hyphenate(encode_hex_upper_array(&self.bytes))
+	}
+
+	/// Formats this UUID in its simple form (without hyphens), in lowercase
+	#[inline]
+	pub fn encode_simple(&self) -> ArrayString<UUID_SIMPLE_LEN> {
+		encode_hex_array(&self.bytes)
+	}
+
+	/// Converts to a ULID with the same bits
+	#[inline]
+	pub const fn to_ulid(&self) -> Ulid {
+		Ulid::from_u128(self.to_u128())
+	}
+}
+
+fn hyphenate(simple: ArrayString<UUID_SIMPLE_LEN>) -> ArrayString<UUID_LEN> {
+	let simple = simple.as_bytes();
+	let mut hyphenated = [b'-'; UUID_LEN];
+
+	let mut read = 0;
+	let mut start = 0;
+	for end in HYPHENS.into_iter().chain([UUID_LEN]) {
+		let len = end - start;
+		hyphenated[start..end].copy_from_slice(&simple[read..read + len]);
+		read += len;
+		start = end + 1;
+	}
+
+	// SAFETY: hex chars and hyphens are all ASCII
+	unsafe { ArrayString::from_utf8_unchecked(hyphenated) }
+}
+
+impl FromStr for Uuid {
+	type Err = ParseError;
+
+	#[inline]
+	fn from_str(s: &str) -> Result<Self, ParseError> {
+		Self::parse(s.as_bytes())
+	}
+}
+
+impl fmt::Display for Uuid {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.encode())
+	}
+}
+
+impl fmt::Debug for Uuid {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Uuid({})", self.encode())
+	}
+}
+
+impl From<Ulid> for Uuid {
+	#[inline]
+	fn from(ulid: Ulid) -> Self {
+		ulid.to_uuid()
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Ulid {
+	int: u128
+}
+
+impl Ulid {
+	/// Amount of bits in the random part
+	const RANDOM_BITS: u32 = 80;
+	const RANDOM_MASK: u128 = (1 << Self::RANDOM_BITS) - 1;
+
+	#[inline]
+	pub const fn from_u128(int: u128) -> Self {
+		Self { int }
+	}
+
+	#[inline]
+	pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+		Self { int: u128::from_be_bytes(bytes) }
+	}
+
+	#[inline]
+	pub const fn to_u128(&self) -> u128 {
+		self.int
+	}
+
+	#[inline]
+	pub const fn to_bytes(&self) -> [u8; 16] {
+		self.int.to_be_bytes()
+	}
+
+	/// Generates a ULID using the current time and random bits. ULIDs generated
+	/// within the same millisecond are not ordered; use [`UlidGenerator`] if
+	/// that's needed.
+	pub fn new() -> Self {
+		Self::from_parts(now_ms(), u128::from_be_bytes(random_bytes()))
+	}
+
+	/// Creates a ULID from a unix timestamp in milliseconds (only the lower 48
+	/// bits are used) and random bits (only the lower 80 bits are used)
+	#[inline]
+	pub const fn from_parts(timestamp_ms: u64, random: u128) -> Self {
+		let timestamp = (timestamp_ms & MAX_TIMESTAMP_MS) as u128;
+		Self { int: (timestamp << Self::RANDOM_BITS) | (random & Self::RANDOM_MASK) }
+	}
+
+	/// The unix timestamp in milliseconds
+	#[inline]
+	pub const fn timestamp_ms(&self) -> u64 {
+		(self.int >> Self::RANDOM_BITS) as u64
+	}
+
+	pub fn timestamp(&self) -> DateTime<Utc> {
+		DateTime::from_timestamp_millis(self.timestamp_ms() as i64)
+			.expect("48 bit timestamps are in range")
+	}
+
+	/// The random part (lower 80 bits)
+	#[inline]
+	pub const fn random(&self) -> u128 {
+		self.int & Self::RANDOM_MASK
+	}
+
+	/// Parses a ULID. Decoding is case insensitive, and accepts `I`/`L` as `1`
+	/// and `O` as `0`, as specified by Crockford's base32.
+	pub fn parse(bytes: &[u8]) -> Result<Self, ParseError> {
+		if bytes.len() != ULID_LEN { return Err(ParseError::InvalidLength) }
+
+		let mut int = 0u128;
+		for (i, byte) in bytes.iter().enumerate() {
+			let Some(value) = TABLE_DECODER_CROCKFORD[*byte as usize] else {
+				return Err(ParseError::InvalidChar)
+			};
+			// 26 chars is 130 bits, so the first char can only hold 3 bits
+			if i == 0 && value > 7 { return Err(ParseError::Overflow) }
+			int = (int << 5) | value as u128;
+		}
+
+		Ok(Self { int })
+	}
+
+	pub fn encode(&self) -> ArrayString<ULID_LEN> {
+		let mut chars = [0u8; ULID_LEN];
+		for (i, char) in chars.iter_mut().enumerate() {
+			let shift = (ULID_LEN - 1 - i) * 5;
+			*char = TABLE_ENCODER_CROCKFORD[((self.int >> shift) & 0x1f) as usize];
+		}
+
+		// SAFETY: chars in the table are all ASCII
+		unsafe { ArrayString::from_utf8_unchecked(chars) }
+	}
+
+	/// Converts to a UUID with the same bits
+	#[inline]
+	pub const fn to_uuid(&self) -> Uuid {
+		Uuid::from_u128(self.int)
+	}
+}
+
+impl FromStr for Ulid {
+	type Err = ParseError;
+
+	#[inline]
+	fn from_str(s: &str) -> Result<Self, ParseError> {
+		Self::parse(s.as_bytes())
+	}
+}
+
+impl fmt::Display for Ulid {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.encode())
+	}
+}
+
+impl fmt::Debug for Ulid {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Ulid({})", self.encode())
+	}
+}
+
+impl From<Uuid> for Ulid {
+	#[inline]
+	fn from(uuid: Uuid) -> Self {
+		uuid.to_ulid()
+	}
+}
+
+/// Generates monotonic ULIDs: ULIDs generated within the same millisecond
+/// (or if the clock goes backwards) reuse the last timestamp and increment
+/// the random part, so every generated ULID sorts after the last one.
+#[derive(Debug)]
+pub struct UlidGenerator {
+	last: Option<Ulid>
+}
+
+impl UlidGenerator {
+	#[inline]
+	pub fn new() -> Self {
+		Self { last: None }
+	}
+
+	/// Generates a ULID with the current time
+	#[inline]
+	pub fn generate(&mut self) -> Result<Ulid, GenerateError> {
+		self.generate_at(now_ms())
+	}
+
+	/// Generates a ULID with the given timestamp, returning
+	/// [`GenerateError::TimestampOutOfRange`] if it's after [`MAX_TIMESTAMP_MS`]
+	pub fn generate_at(&mut self, timestamp_ms: u64) -> Result<Ulid, GenerateError> {
+		if timestamp_ms > MAX_TIMESTAMP_MS { return Err(GenerateError::TimestampOutOfRange) }
+
+		let ulid = match self.last {
+			Some(last) if timestamp_ms <= last.timestamp_ms() => {
+				if last.random() == Ulid::RANDOM_MASK { return Err(GenerateError::MonotonicOverflow) }
+				Ulid::from_u128(last.int + 1)
+			}
+			_ => {
+				// leave the top bit of the random part clear, so there's always
+				// lots of room to increment within a millisecond
+				let random = u128::from_be_bytes(random_bytes()) & (Ulid::RANDOM_MASK >> 1);
+				Ulid::from_parts(timestamp_ms, random)
+			}
+		};
+
+		self.last = Some(ulid);
+		Ok(ulid)
+	}
+}
+
+/// The current unix timestamp in milliseconds, or 0 if the system clock is
+/// set to before 1970
+#[inline]
+fn now_ms() -> u64 {
+	u64::try_from(Utc::now().timestamp_millis()).unwrap_or(0)
+}
+
+#[inline]
+fn random_bytes() -> [u8; 16] {
+	let mut bytes = [0u8; 16];
+	::getrandom::getrandom(&mut bytes).expect("failed to get random bytes from the OS");
+	bytes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn uuid_format() {
+		let uuid = Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8);
+		assert_eq!(uuid.encode(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+		assert_eq!(uuid.encode_upper(), "67E55044-10B1-426F-9247-BB680E5FE0C8");
+		assert_eq!(uuid.encode_simple(), "67e5504410b1426f9247bb680e5fe0c8");
+		assert_eq!(uuid.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+
+		for s in ["67e55044-10b1-426f-9247-bb680e5fe0c8", "67E55044-10B1-426F-9247-BB680E5FE0C8", "67e5504410b1426f9247bb680e5fe0c8"] {
+			assert_eq!(s.parse::<Uuid>().unwrap(), uuid);
+		}
+		assert_eq!(uuid.version(), 4);
+		assert!(uuid.is_rfc_variant());
+
+		assert_eq!(Uuid::NIL.encode(), "00000000-0000-0000-0000-000000000000");
+		assert_eq!(Uuid::MAX.encode(), "ffffffff-ffff-ffff-ffff-ffffffffffff");
+	}
+
+	#[test]
+	fn uuid_invalid() {
+		assert!(matches!(Uuid::parse(b""), Err(ParseError::InvalidLength)));
+		assert!(matches!(Uuid::parse(b"67e55044-10b1-426f-9247-bb680e5fe0c"), Err(ParseError::InvalidLength)));
+		assert!(matches!(Uuid::parse(b"67e55044-10b1-426f-9247+bb680e5fe0c8"), Err(ParseError::InvalidChar)));
+		assert!(matches!(Uuid::parse(b"67e5504-410b1-426f-9247-bb680e5fe0c8"), Err(ParseError::InvalidChar)));
+		assert!(matches!(Uuid::parse(b"g7e55044-10b1-426f-9247-bb680e5fe0c8"), Err(ParseError::InvalidChar)));
+	}
+
+	#[test]
+	fn uuid_generate() {
+		let uuid = Uuid::new_v4();
+		assert_eq!(uuid.version(), 4);
+		assert!(uuid.is_rfc_variant());
+		assert_eq!(uuid.timestamp(), None);
+		assert_ne!(uuid, Uuid::new_v4());
+
+		let before = Utc::now().timestamp_millis();
+		let uuid = Uuid::new_v7();
+		let after = Utc::now().timestamp_millis();
+		assert_eq!(uuid.version(), 7);
+		assert!(uuid.is_rfc_variant());
+		let timestamp = uuid.timestamp().unwrap().timestamp_millis();
+		assert!(before <= timestamp && timestamp <= after);
+
+		// example from RFC 9562 appendix A.6
+		let uuid = Uuid::from_parts_v7(0x017f22e279b0, 0x00000000_0000_0cc3_98c4_dc0c0c07398fu128.to_be_bytes());
+		assert_eq!(uuid.encode(), "017f22e2-79b0-7cc3-98c4-dc0c0c07398f");
+	}
+
+	#[test]
+	fn ulid() {
+		// example from the ULID spec
+		let ulid = "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse::<Ulid>().unwrap();
+		assert_eq!(ulid.timestamp_ms(), 1469922850259);
+		assert_eq!(ulid.to_u128(), 0x01563e3ab5d3d6764c61efb99302bd5b);
+		assert_eq!(ulid.encode(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+		assert_eq!(ulid.to_uuid().encode(), "01563e3a-b5d3-d676-4c61-efb99302bd5b");
+		assert_eq!(Ulid::from(ulid.to_uuid()), ulid);
+
+		assert_eq!("01arz3ndektsv4rrffq69g5fav".parse::<Ulid>().unwrap(), ulid);
+		assert_eq!("0LARZ3NDEKTSV4RRFFQ69G5FAV".parse::<Ulid>().unwrap(), "01ARZ3NDEKTSV4RRFFQ69G5FAV".parse::<Ulid>().unwrap());
+		assert_eq!("7ZZZZZZZZZZZZZZZZZZZZZZZZZ".parse::<Ulid>().unwrap().to_u128(), u128::MAX);
+
+		assert!(matches!(Ulid::parse(b"01ARZ3NDEKTSV4RRFFQ69G5FA"), Err(ParseError::InvalidLength)));
+		assert!(matches!(Ulid::parse(b"01ARZ3NDEKTSV4RRFFQ69G5FAU"), Err(ParseError::InvalidChar)));
+		assert!(matches!(Ulid::parse(b"80000000000000000000000000"), Err(ParseError::Overflow)));
+
+		let ulid = Ulid::new();
+		assert_eq!(Ulid::parse(ulid.encode().as_bytes()).unwrap(), ulid);
+		assert!((Utc::now() - ulid.timestamp()).num_seconds() < 10);
+	}
+
+	#[test]
+	fn ulid_monotonic() {
+		let mut generator = UlidGenerator::new();
+
+		let mut last = generator.generate_at(1000).unwrap();
+		for _ in 0..1000 {
+			let next = generator.generate_at(1000).unwrap();
+			assert_eq!(next.timestamp_ms(), 1000);
+			assert!(next > last);
+			assert!(next.encode() > last.encode());
+			last = next;
+		}
+
+		// clock going backwards keeps the last timestamp
+		let next = generator.generate_at(999).unwrap();
+		assert_eq!(next.timestamp_ms(), 1000);
+		assert!(next > last);
+
+		let next = generator.generate_at(1001).unwrap();
+		assert_eq!(next.timestamp_ms(), 1001);
+
+		let mut generator = UlidGenerator { last: Some(Ulid::from_parts(5, u128::MAX)) };
+		assert!(matches!(generator.generate_at(5), Err(GenerateError::MonotonicOverflow)));
+		assert!(generator.generate_at(6).is_ok());
+
+		// the top bit of the random part is always clear for a new millisecond
+		let mut generator = UlidGenerator::new();
+		for timestamp_ms in 0..1000 {
+			let ulid = generator.generate_at(timestamp_ms).unwrap();
+			assert_eq!(ulid.timestamp_ms(), timestamp_ms);
+			assert!(ulid.random() <= Ulid::RANDOM_MASK >> 1);
+		}
+
+		let mut generator = UlidGenerator::new();
+		assert_eq!(generator.generate_at(MAX_TIMESTAMP_MS).unwrap().timestamp_ms(), MAX_TIMESTAMP_MS);
+		assert!(matches!(generator.generate_at(MAX_TIMESTAMP_MS + 1), Err(GenerateError::TimestampOutOfRange)));
+
+		let mut generator = UlidGenerator::new();
+		let a = generator.generate().unwrap();
+		let b = generator.generate().unwrap();
+		assert!(b > a);
+	}
+}
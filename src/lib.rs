@@ -15,40 +15,76 @@ pub mod prelude;
 #[cfg(any(
 	feature = "base16",
 	feature = "base32",
+	feature = "base58",
 	feature = "base64",
 	feature = "hex",
 	feature = "z85"
 ))]
 mod encoding_utils;
 
+feature_cfg_compile_check!("checked-codecs", cfg of "base16", "base32", "base58", "base64", "hex", "z85");
+
 #[cfg(feature = "base16")]
 pub mod base16;
 
 #[cfg(feature = "base32")]
 pub mod base32;
 
+#[cfg(feature = "base58")]
+pub mod base58;
+
 #[cfg(feature = "base64")]
 pub mod base64;
 
+#[cfg(feature = "bech32")]
+pub mod bech32;
+
+#[cfg(feature = "checksum")]
+pub mod checksum;
+
 #[cfg(feature = "clock-timer")]
 pub mod clock_timer;
 
 #[cfg(feature = "clock-timer-2")]
 pub mod clock_timer_2;
 
+#[cfg(feature = "data-url")]
+pub mod data_url;
+
 #[cfg(feature = "debounce")]
 pub mod debounce;
 feature_cfg_compile_check!("debounce-dyn-fn", cfg of "debounce");
 
+#[cfg(feature = "encoding-detect")]
+pub mod encoding_detect;
+
+#[cfg(feature = "geohash")]
+pub mod geohash;
+
 #[cfg(feature = "h")]
 pub mod h;
 
 #[cfg(feature = "hex")]
 pub mod hex;
 
+#[cfg(feature = "id")]
+pub mod id;
+
 #[cfg(feature = "lazy-wrap")]
 pub mod lazy_wrap;
 
+#[cfg(feature = "nanoid")]
+pub mod nanoid;
+
+#[cfg(feature = "otp")]
+pub mod otp;
+
+#[cfg(feature = "percent-encoding")]
+pub mod percent_encoding;
+
+#[cfg(feature = "quoted-printable")]
+pub mod quoted_printable;
+
 #[cfg(feature = "string-pool")]
 pub mod string_pool;
 
@@ -99,13 +135,15 @@ use runtime_selection_compile_check;
 /// has to be run in this module and not in the feature modules themselves
 /// because then, if this *should have* triggered an error, it won't because
 /// the feature is off and module excluded from compilation lol
+///
+/// if more than one feature is given, enabling any one of them is enough
 macro_rules! feature_cfg_compile_check {
-	($cfgname:literal, cfg of $featname:literal) => {
+	($cfgname:literal, cfg of $featname:literal $(, $more:literal)*) => {
 		#[cfg(all(
 			feature = $cfgname,
-			not(feature = $featname)
+			not(any(feature = $featname $(, feature = $more)*))
 		))]
-		compile_error!(concat!("`", $cfgname, "` is a configuration feature of `", $featname, "`, and does nothing when enabled on its own"));
+		compile_error!(concat!("`", $cfgname, "` is a configuration feature of `", $featname, "`" $(, " or `", $more, "`")*, ", and does nothing when enabled on its own"));
 	}
 }
 use feature_cfg_compile_check;
@@ -0,0 +1,255 @@
+//! Short random IDs ([nanoid] style), made of characters picked uniformly
+//! from an alphabet.
+//!
+//! Random bytes are masked down to the smallest power of two that fits the
+//! alphabet, and any that land past the end of the alphabet are thrown away
+//! and replaced with more random bytes. This way, every character is equally
+//! likely, which taking the random bytes modulo the alphabet length would not
+//! give for alphabets with a length that isn't a power of two (ex. Z85).
+//!
+//! [nanoid]: https://github.com/ai/nanoid
+
+/// Alphabets can only contain ASCII characters, and can't have any duplicates,
+/// so there's at most 128 of them
+pub const MAX_ALPHABET_LEN: usize = 128;
+
+/// An alphabet of 2 to 128 unique ASCII characters to generate IDs with
+#[derive(Clone, Copy, Debug)]
+pub struct Alphabet<'h> {
+	chars: &'h [u8],
+	/// Random bytes get ANDed with this before checking they're in range
+	mask: u8
+}
+
+impl<'h> Alphabet<'h> {
+	/// The Z85 alphabet ([`z85::TABLE_ENCODER`](crate::z85::TABLE_ENCODER)).
+	/// Contains characters that aren't safe to put in URLs without encoding
+	/// them (ex. `?` and `&`).
+	#[cfg(feature = "z85")]
+	pub const Z85: Alphabet<'static> = Alphabet::new(&crate::z85::TABLE_ENCODER);
+
+	/// Crockford's base32 alphabet
+	/// ([`base32::TABLE_ENCODER_CROCKFORD`](crate::base32::TABLE_ENCODER_CROCKFORD)),
+	/// without the easily confused `I`, `L`, `O`, and `U`
+	#[cfg(feature = "base32")]
+	pub const BASE32_CROCKFORD: Alphabet<'static> = Alphabet::new(&crate::base32::TABLE_ENCODER_CROCKFORD);
+
+	/// The URL and filename safe base64 alphabet
+	/// ([`base64::TABLE_ENCODER_URL`](crate::base64::TABLE_ENCODER_URL)), the
+	/// same characters as nanoid's default alphabet
+	#[cfg(feature = "base64")]
+	pub const BASE64_URL: Alphabet<'static> = Alphabet::new(&crate::base64::TABLE_ENCODER_URL);
+
+	/// Creates a new alphabet, panicking if it has less than 2 or more than
+	/// 128 characters, or contains non-ASCII or duplicate characters. This is
+	/// a `const fn`, so if used to initialise a `static` or `const`, any such
+	/// panic happens at compile time.
+	pub const fn new(chars: &'h [u8]) -> Self {
+		assert!(chars.len() >= 2, "nanoid alphabet has less than 2 characters");
+		assert!(chars.len() <= MAX_ALPHABET_LEN, "nanoid alphabet has more than 128 characters");
+
+		let mut seen = [false; 256];
+		let mut i = 0;
+		while i < chars.len() {
+			let char = chars[i];
+			assert!(char.is_ascii(), "nanoid alphabet contains a non-ASCII character");
+			assert!(!seen[char as usize], "nanoid alphabet contains a duplicate character");
+
+			seen[char as usize] = true;
+			i += 1;
+		}
+
+		let mask = (chars.len().next_power_of_two() - 1) as u8;
+		Self { chars, mask }
+	}
+
+	#[inline]
+	pub const fn chars(&self) -> &'h [u8] {
+		self.chars
+	}
+
+	// an alphabet can't be empty
+	#[allow(clippy::len_without_is_empty)]
+	#[inline]
+	pub const fn len(&self) -> usize {
+		self.chars.len()
+	}
+}
+
+/// Generates an ID of `len` characters from `alphabet`, using random bytes
+/// from the OS
+pub fn generate(alphabet: &Alphabet, len: usize) -> String {
+	generate_with(alphabet, len, |bytes| {
+		::getrandom::getrandom(bytes).expect("failed to get random bytes from the OS")
+	})
+}
+
+/// Generates an ID of `len` characters from `alphabet`, using random bytes
+/// from `fill_random`, which should fill the whole slice passed to it with
+/// random bytes. With the `rand` crate, this would be
+/// `|bytes| rng.fill_bytes(bytes)`.
+pub fn generate_with<F>(alphabet: &Alphabet, len: usize, mut fill_random: F) -> String
+where
+	F: FnMut(&mut [u8])
+{
+	let chars = alphabet.chars;
+	let mut id = Vec::with_capacity(len);
+
+	// ask for somewhat more than the expected amount of random bytes
+	// needed, so it usually only takes one batch (same as nanoid does)
+	let masked_len = alphabet.mask as usize + 1;
+	let batch_len = (len * masked_len * 8 / 5).div_ceil(chars.len());
+	let mut random = vec![0u8; batch_len];
+
+	while id.len() < len {
+		fill_random(&mut random);
+
+		for byte in &random {
+			if let Some(char) = chars.get((byte & alphabet.mask) as usize) {
+				id.push(*char);
+				if id.len() == len { break }
+			}
+		}
+	}
+
+	debug_assert!(String::from_utf8(id.clone()).is_ok(), "output bytes are valid utf-8");
+	// SAFETY: alphabet chars are checked to be ASCII on creation
+	unsafe { String::from_utf8_unchecked(id) }
+}
+
+/// Probability that at least two out of `ids` IDs of `len` characters from an
+/// alphabet of `alphabet_len` characters are the same (approximately, using
+/// the birthday bound)
+pub fn collision_probability(alphabet_len: usize, len: usize, ids: u64) -> f64 {
+	let possible_ids = (alphabet_len as f64).powf(len as f64);
+	let pairs = ids as f64 * ids.saturating_sub(1) as f64 / 2.0;
+	-(-pairs / possible_ids).exp_m1()
+}
+
+/// Smallest length IDs from an alphabet of `alphabet_len` characters need to
+/// be, for the probability of any collisions out of `ids` IDs to be at most
+/// `probability` (which has to be between 0 and 1, exclusive). `alphabet_len`
+/// has to be at least 2, the same as for [`Alphabet`]s.
+pub fn len_for_collision_probability(alphabet_len: usize, ids: u64, probability: f64) -> usize {
+	assert!(alphabet_len >= 2, "nanoid alphabet has less than 2 characters");
+	assert!(probability > 0.0 && probability < 1.0, "probability is between 0 and 1 (exclusive)");
+	if ids < 2 { return 0 }
+
+	// inverse of `collision_probability`, done in log space
+	// so large amounts of IDs don't overflow
+	let ln_pairs = (ids as f64).ln() + ((ids - 1) as f64).ln() - 2f64.ln();
+	let ln_possible_ids = ln_pairs - (-(-probability).ln_1p()).ln();
+	let len = (ln_possible_ids / (alphabet_len as f64).ln()).ceil().max(0.0) as usize;
+
+	// floating point error can land us right next to the boundary,
+	// so make sure we're on the right side of it
+	if collision_probability(alphabet_len, len, ids) > probability { len + 1 } else { len }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ RngCore, thread_rng };
+
+	#[test]
+	fn rejection_sampling() {
+		// mask is 0b11, so 3 gets thrown out
+		let alphabet = Alphabet::new(b"abc");
+		let mut random = [3u8, 4, 7, 9, 255, 2, 3, 1].into_iter();
+		let id = generate_with(&alphabet, 5, |bytes| {
+			bytes.iter_mut().for_each(|b| *b = random.next().unwrap_or(0));
+		});
+		assert_eq!(id, "abcba");
+	}
+
+	#[test]
+	fn uniform() {
+		let alphabet = Alphabet::new(b"abcde");
+		let mut rng = thread_rng();
+		let id = generate_with(&alphabet, 100_000, |bytes| rng.fill_bytes(bytes));
+		assert_eq!(id.len(), 100_000);
+
+		for char in alphabet.chars() {
+			let count = id.bytes().filter(|b| b == char).count();
+			assert!((19_000..21_000).contains(&count), "{} appeared {count} times", *char as char);
+		}
+	}
+
+	#[test]
+	fn generate_from_os() {
+		let alphabet = Alphabet::new(b"0123456789");
+		for len in [0, 1, 21, 100] {
+			let id = generate(&alphabet, len);
+			assert_eq!(id.len(), len);
+			assert!(id.bytes().all(|b| b.is_ascii_digit()));
+		}
+		assert_ne!(generate(&alphabet, 32), generate(&alphabet, 32));
+	}
+
+	#[test]
+	fn presets() {
+		#[cfg(feature = "z85")]
+		assert_eq!(Alphabet::Z85.len(), 85);
+		#[cfg(feature = "base32")]
+		assert_eq!(Alphabet::BASE32_CROCKFORD.len(), 32);
+		#[cfg(feature = "base64")]
+		assert_eq!(Alphabet::BASE64_URL.len(), 64);
+	}
+
+	#[test]
+	#[should_panic = "duplicate character"]
+	fn duplicate_chars() {
+		Alphabet::new(b"abca");
+	}
+
+	#[test]
+	#[should_panic = "less than 2 characters"]
+	fn too_short() {
+		Alphabet::new(b"a");
+	}
+
+	#[test]
+	#[should_panic = "more than 128 characters"]
+	fn too_long() {
+		let chars = (0..=128).collect::<Vec<u8>>();
+		Alphabet::new(&chars);
+	}
+
+	#[test]
+	fn all_ascii() {
+		let chars = (0..128).collect::<Vec<u8>>();
+		let alphabet = Alphabet::new(&chars);
+		assert_eq!(alphabet.len(), MAX_ALPHABET_LEN);
+		assert_eq!(generate(&alphabet, 50).len(), 50);
+	}
+
+	#[test]
+	#[should_panic = "less than 2 characters"]
+	fn collision_len_one_char() {
+		len_for_collision_probability(1, 1000, 0.5);
+	}
+
+	#[test]
+	fn collision_len() {
+		// nanoid's default (21 chars of 64) has about a 6 in 10^21 chance
+		// of any collisions in a billion IDs
+		let p = collision_probability(64, 21, 1_000_000_000);
+		assert!(p > 5e-21 && p < 6e-21, "{p}");
+		assert_eq!(len_for_collision_probability(64, 1_000_000_000, 1e-15), 19);
+		assert_eq!(len_for_collision_probability(64, 1_000_000_000, 1e-20), 21);
+
+		for (alphabet_len, ids, probability) in [
+			(85, 1_000, 1e-6),
+			(32, 1_000_000, 0.01),
+			(64, u64::MAX, 1e-9),
+			(2, 10, 0.5)
+		] {
+			let len = len_for_collision_probability(alphabet_len, ids, probability);
+			assert!(collision_probability(alphabet_len, len, ids) <= probability);
+			assert!(collision_probability(alphabet_len, len - 1, ids) > probability);
+		}
+
+		assert_eq!(len_for_collision_probability(64, 1, 0.5), 0);
+		assert_eq!(collision_probability(64, 0, 1), 0.0);
+	}
+}
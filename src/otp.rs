@@ -0,0 +1,680 @@
+//! One-time passwords: HOTP ([RFC 4226]) and TOTP ([RFC 6238]), with base32
+//! secrets and `otpauth://` URIs as used by authenticator apps.
+//!
+//! [RFC 4226]: https://datatracker.ietf.org/doc/html/rfc4226
+//! [RFC 6238]: https://datatracker.ietf.org/doc/html/rfc6238
+
+use crate::base32::decode_base32;
+use crate::percent_encoding::decode_percent;
+use ::chrono::{ DateTime, TimeZone };
+use ::hmac::{ Hmac, Mac };
+use ::hmac::digest::KeyInit;
+use ::std::fmt;
+
+/// The default amount of digits in a code
+pub const DEFAULT_DIGITS: u8 = 6;
+/// The default TOTP period, in seconds
+pub const DEFAULT_PERIOD: u64 = 30;
+/// The longest TOTP period, in seconds
+pub const MAX_PERIOD: u64 = u32::MAX as u64;
+/// The default amount of periods before and after the current one that are
+/// also accepted when verifying TOTP codes
+pub const DEFAULT_SKEW: u64 = 1;
+
+/// Hash function used for the HMAC
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Algorithm {
+	#[default]
+	Sha1,
+	Sha256,
+	Sha512
+}
+
+impl Algorithm {
+	/// Name as used in `otpauth://` URIs
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Algorithm::Sha1 => { "SHA1" }
+			Algorithm::Sha256 => { "SHA256" }
+			Algorithm::Sha512 => { "SHA512" }
+		}
+	}
+
+	/// Parses a name as used in `otpauth://` URIs, ignoring case
+	pub fn from_name(name: &str) -> Option<Algorithm> {
+		let algorithm = match &*name.to_ascii_uppercase() {
+			"SHA1" => { Algorithm::Sha1 }
+			"SHA256" => { Algorithm::Sha256 }
+			"SHA512" => { Algorithm::Sha512 }
+			_ => { return None }
+		};
+		Some(algorithm)
+	}
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum SecretError {
+	#[error("secret is empty")]
+	Empty,
+	#[error("secret is not valid base32")]
+	InvalidBase32(#[from] crate::base32::DecodeError)
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum UriError {
+	#[error("not an otpauth:// URI")]
+	InvalidScheme,
+	#[error("unknown OTP type (expected hotp or totp)")]
+	InvalidType,
+	#[error("invalid percent-encoding or utf-8 in URI")]
+	InvalidEncoding,
+	#[error("missing secret")]
+	MissingSecret,
+	#[error("invalid secret: {0}")]
+	InvalidSecret(#[from] SecretError),
+	#[error("unknown algorithm")]
+	InvalidAlgorithm,
+	#[error("invalid amount of digits")]
+	InvalidDigits,
+	#[error("invalid period")]
+	InvalidPeriod,
+	#[error("missing or invalid counter")]
+	InvalidCounter
+}
+
+/// Decodes a base32 secret as given out by services (ex. `JBSW Y3DP EHPK 3PXP`).
+/// Unlike [`decode_base32`] this is lenient: case is ignored, spaces and
+/// hyphens are skipped, and padding is optional.
+pub fn decode_secret(secret: &[u8]) -> Result<Vec<u8>, SecretError> {
+	let mut normalised = secret.iter()
+		.filter(|b| !matches!(**b, b' ' | b'-' | b'='))
+		.map(|b| b.to_ascii_uppercase())
+		.collect::<Vec<_>>();
+	if normalised.is_empty() { return Err(SecretError::Empty) }
+
+	let padded_len = normalised.len().next_multiple_of(8);
+	normalised.resize(padded_len, b'=');
+
+	Ok(decode_base32(&normalised)?)
+}
+
+/// Computes the HOTP value for the given counter ([RFC 4226 section 5.3]),
+/// as an integer with at most `digits` digits.
+///
+/// # Panics
+///
+/// Panics if `digits` is not between 6 and 10 (inclusive).
+///
+/// [RFC 4226 section 5.3]: https://datatracker.ietf.org/doc/html/rfc4226#section-5.3
+pub fn hotp(secret: &[u8], counter: u64, algorithm: Algorithm, digits: u8) -> u32 {
+	assert_valid_digits(digits);
+
+	macro_rules! hmac {
+		($digest:ty) => {{
+			let mut mac = <Hmac<$digest> as KeyInit>::new_from_slice(secret)
+				.expect("HMAC accepts keys of any length");
+			mac.update(&counter.to_be_bytes());
+			mac.finalize().into_bytes().to_vec()
+		}}
+	}
+
+	let hash = match algorithm {
+		Algorithm::Sha1 => { hmac!(::sha1::Sha1) }
+		Algorithm::Sha256 => { hmac!(::sha2::Sha256) }
+		Algorithm::Sha512 => { hmac!(::sha2::Sha512) }
+	};
+
+	// dynamic truncation
+	let offset = (hash[hash.len() - 1] & 0xf) as usize;
+	let bytes = [hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]];
+	let truncated = u32::from_be_bytes(bytes) & 0x7fff_ffff;
+
+	// 10 digits is more than 31 bits can hold, so this can't overflow
+	(truncated as u64 % 10u64.pow(digits as u32)) as u32
+}
+
+const fn assert_valid_digits(digits: u8) {
+	assert!(6 <= digits && digits <= 10, "amount of digits must be between 6 and 10");
+}
+
+/// Formats a code, zero padded to `digits` digits
+#[inline]
+fn format_code(code: u32, digits: u8) -> String {
+	format!("{code:0width$}", width = digits as usize)
+}
+
+/// Parses a code given by a user. It must be exactly `digits` ASCII digits.
+fn parse_code(code: &str, digits: u8) -> Option<u32> {
+	let code = code.as_bytes();
+	if code.len() != digits as usize || !code.iter().all(u8::is_ascii_digit) { return None }
+	// 10 digit codes can be too big for a u32, but those can't match anyway
+	code.iter().try_fold(0u32, |int, b| int.checked_mul(10)?.checked_add((b - b'0') as u32))
+}
+
+/// Compares two codes of `digits` digits in constant time, so the time taken
+/// doesn't leak how many leading digits of a guess were right
+fn codes_eq(a: u32, b: u32, digits: u8) -> bool {
+	let (mut a, mut b, mut diff) = (a, b, 0);
+	for _ in 0..digits {
+		diff |= (a % 10) ^ (b % 10);
+		a /= 10;
+		b /= 10;
+	}
+	diff == 0
+}
+
+/// Counter based one-time passwords ([RFC 4226])
+///
+/// [RFC 4226]: https://datatracker.ietf.org/doc/html/rfc4226
+#[derive(Clone, PartialEq, Eq)]
+pub struct Hotp {
+	secret: Vec<u8>,
+	algorithm: Algorithm,
+	digits: u8
+}
+
+impl Hotp {
+	/// Creates a new HOTP generator with the given (raw, already decoded) secret,
+	/// using SHA-1 and 6 digits
+	#[inline]
+	pub fn new(secret: Vec<u8>) -> Self {
+		Self { secret, algorithm: Algorithm::Sha1, digits: DEFAULT_DIGITS }
+	}
+
+	/// Creates a new HOTP generator with a base32 secret (see [`decode_secret`])
+	#[inline]
+	pub fn from_base32(secret: &[u8]) -> Result<Self, SecretError> {
+		decode_secret(secret).map(Self::new)
+	}
+
+	#[inline]
+	pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+		self.algorithm = algorithm;
+		self
+	}
+
+	/// # Panics
+	///
+	/// Panics if `digits` is not between 6 and 10 (inclusive).
+	#[inline]
+	pub fn with_digits(mut self, digits: u8) -> Self {
+		assert_valid_digits(digits);
+		self.digits = digits;
+		self
+	}
+
+	#[inline]
+	pub fn secret(&self) -> &[u8] {
+		&self.secret
+	}
+
+	#[inline]
+	pub fn algorithm(&self) -> Algorithm {
+		self.algorithm
+	}
+
+	#[inline]
+	pub fn digits(&self) -> u8 {
+		self.digits
+	}
+
+	/// Generates the code for the given counter
+	#[inline]
+	pub fn generate(&self, counter: u64) -> String {
+		format_code(self.generate_int(counter), self.digits)
+	}
+
+	/// Generates the code for the given counter, as an integer
+	#[inline]
+	pub fn generate_int(&self, counter: u64) -> u32 {
+		hotp(&self.secret, counter, self.algorithm, self.digits)
+	}
+
+	/// Checks `code` against the codes for `counter` and the `look_ahead`
+	/// counters after it, returning the counter that matched. The caller should
+	/// store the matched counter plus one as the next counter, so the code
+	/// can't be reused.
+	pub fn verify(&self, code: &str, counter: u64, look_ahead: u64) -> Option<u64> {
+		let code = parse_code(code, self.digits)?;
+		(counter..=counter.saturating_add(look_ahead))
+			.find(|counter| codes_eq(self.generate_int(*counter), code, self.digits))
+	}
+}
+
+impl fmt::Debug for Hotp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Hotp")
+			.field("secret", &"<redacted>")
+			.field("algorithm", &self.algorithm)
+			.field("digits", &self.digits)
+			.finish()
+	}
+}
+
+/// Time based one-time passwords ([RFC 6238]). Codes rotate every period,
+/// with periods counted from the unix epoch.
+///
+/// [RFC 6238]: https://datatracker.ietf.org/doc/html/rfc6238
+#[derive(Clone, PartialEq, Eq)]
+pub struct Totp {
+	hotp: Hotp,
+	period: u64,
+	skew: u64
+}
+
+impl Totp {
+	/// Creates a new TOTP generator with the given (raw, already decoded) secret,
+	/// using SHA-1, 6 digits, a period of 30 seconds, and accepting codes from
+	/// one period before and after the current one when verifying
+	#[inline]
+	pub fn new(secret: Vec<u8>) -> Self {
+		Self { hotp: Hotp::new(secret), period: DEFAULT_PERIOD, skew: DEFAULT_SKEW }
+	}
+
+	/// Creates a new TOTP generator with a base32 secret (see [`decode_secret`])
+	#[inline]
+	pub fn from_base32(secret: &[u8]) -> Result<Self, SecretError> {
+		decode_secret(secret).map(Self::new)
+	}
+
+	#[inline]
+	pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+		self.hotp = self.hotp.with_algorithm(algorithm);
+		self
+	}
+
+	/// # Panics
+	///
+	/// Panics if `digits` is not between 6 and 10 (inclusive).
+	#[inline]
+	pub fn with_digits(mut self, digits: u8) -> Self {
+		self.hotp = self.hotp.with_digits(digits);
+		self
+	}
+
+	/// Sets the period in seconds
+	///
+	/// # Panics
+	///
+	/// Panics if `period` is 0 or greater than [`MAX_PERIOD`].
+	#[inline]
+	pub fn with_period(mut self, period: u64) -> Self {
+		assert!(period > 0, "period must be at least 1 second");
+		assert!(period <= MAX_PERIOD, "period must be at most {MAX_PERIOD} seconds");
+		self.period = period;
+		self
+	}
+
+	/// Sets the amount of periods before and after the current one that are
+	/// also accepted when verifying, to allow for clock drift and the time it
+	/// takes the user to type the code
+	#[inline]
+	pub fn with_skew(mut self, skew: u64) -> Self {
+		self.skew = skew;
+		self
+	}
+
+	#[inline]
+	pub fn secret(&self) -> &[u8] {
+		self.hotp.secret()
+	}
+
+	#[inline]
+	pub fn algorithm(&self) -> Algorithm {
+		self.hotp.algorithm()
+	}
+
+	#[inline]
+	pub fn digits(&self) -> u8 {
+		self.hotp.digits()
+	}
+
+	/// The period in seconds
+	#[inline]
+	pub fn period(&self) -> u64 {
+		self.period
+	}
+
+	#[inline]
+	pub fn skew(&self) -> u64 {
+		self.skew
+	}
+
+	/// The counter (amount of whole periods since the unix epoch) at `time`.
+	/// Times before the epoch are treated as the epoch.
+	#[inline]
+	pub fn counter_at<TZ: TimeZone>(&self, time: &DateTime<TZ>) -> u64 {
+		time.timestamp().max(0) as u64 / self.period
+	}
+
+	/// Start of the period `time` is in, as a unix timestamp in seconds
+	#[inline]
+	pub fn period_start_at<TZ: TimeZone>(&self, time: &DateTime<TZ>) -> u64 {
+		self.counter_at(time) * self.period
+	}
+
+	/// Generates the code for the current time
+	#[inline]
+	pub fn generate(&self) -> String {
+		self.generate_at(&::chrono::Utc::now())
+	}
+
+	/// Generates the code for the given time
+	#[inline]
+	pub fn generate_at<TZ: TimeZone>(&self, time: &DateTime<TZ>) -> String {
+		self.hotp.generate(self.counter_at(time))
+	}
+
+	/// Checks `code` against the codes for the current time, allowing for the
+	/// configured skew
+	#[inline]
+	pub fn verify(&self, code: &str) -> bool {
+		self.verify_at(code, &::chrono::Utc::now())
+	}
+
+	/// Checks `code` against the codes for the given time, allowing for the
+	/// configured skew
+	pub fn verify_at<TZ: TimeZone>(&self, code: &str, time: &DateTime<TZ>) -> bool {
+		let counter = self.counter_at(time);
+		let first = counter.saturating_sub(self.skew);
+		let look_ahead = (counter - first).saturating_add(self.skew);
+		self.hotp.verify(code, first, look_ahead).is_some()
+	}
+}
+
+impl fmt::Debug for Totp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Totp")
+			.field("secret", &"<redacted>")
+			.field("algorithm", &self.hotp.algorithm)
+			.field("digits", &self.hotp.digits)
+			.field("period", &self.period)
+			.field("skew", &self.skew)
+			.finish()
+	}
+}
+
+#[cfg(all(feature = "clock-timer-2", feature = "tokio"))]
+impl Totp {
+	/// Creates a [`ClockTimer`](crate::clock_timer_2::ClockTimer) that ticks at
+	/// the start of every period, for `duration` from now. The first tick is at
+	/// the start of the current period (so it's returned immediately), and the
+	/// time of each tick can be passed to [`generate_at`](Totp::generate_at)
+	/// to get the code for that period.
+	pub fn rotation_timer(&self, duration: ::chrono::TimeDelta) -> crate::clock_timer_2::ClockTimer {
+		use ::chrono::{ TimeDelta, Utc };
+
+		let now = Utc::now();
+		let start = Utc.timestamp_opt(self.period_start_at(&now) as i64, 0)
+			.single()
+			.expect("period start is a valid timestamp");
+
+		crate::clock_timer_2::ClockTimer::builder()
+			.with_start_datetime(start)
+			.with_end_datetime(now + duration)
+			.with_interval(TimeDelta::try_seconds(self.period as i64).expect("period fits in a TimeDelta"))
+			.build()
+	}
+}
+
+/// Type of OTP in an `otpauth://` URI, and its parameters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OtpKind {
+	Hotp {
+		hotp: Hotp,
+		/// Initial counter value
+		counter: u64
+	},
+	Totp(Totp)
+}
+
+/// A parsed `otpauth://` URI, in the [key URI format] used by authenticator
+/// apps (ex. `otpauth://totp/Example:alice@example.com?secret=JBSWY3DPEHPK3PXP&issuer=Example`)
+///
+/// [key URI format]: https://github.com/google/google-authenticator/wiki/Key-Uri-Format
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OtpAuthUri {
+	pub kind: OtpKind,
+	/// Account name from the label
+	pub account: String,
+	/// Issuer from the `issuer` parameter, or else from the label prefix
+	pub issuer: Option<String>
+}
+
+impl OtpAuthUri {
+	pub fn parse(uri: &str) -> Result<Self, UriError> {
+		const SCHEME: &str = "otpauth://";
+
+		let scheme = uri.get(..SCHEME.len()).ok_or(UriError::InvalidScheme)?;
+		if !scheme.eq_ignore_ascii_case(SCHEME) { return Err(UriError::InvalidScheme) }
+		let rest = &uri[SCHEME.len()..];
+
+		let (kind, rest) = rest.split_once('/').ok_or(UriError::InvalidType)?;
+		let is_totp = match &*kind.to_ascii_lowercase() {
+			"hotp" => { false }
+			"totp" => { true }
+			_ => { return Err(UriError::InvalidType) }
+		};
+
+		let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+		let label = percent_decode(label)?;
+		let (label_issuer, account) = match label.split_once(':') {
+			Some((issuer, account)) => { (Some(issuer.trim().into()), account.trim().into()) }
+			None => { (None, label.trim().into()) }
+		};
+
+		let mut secret = None;
+		let mut issuer = None;
+		let mut algorithm = Algorithm::Sha1;
+		let mut digits = DEFAULT_DIGITS;
+		let mut period = DEFAULT_PERIOD;
+		let mut counter = None;
+
+		for param in query.split('&').filter(|p| !p.is_empty()) {
+			let (key, value) = param.split_once('=').unwrap_or((param, ""));
+			let value = percent_decode(value)?;
+
+			match key {
+				"secret" => { secret = Some(decode_secret(value.as_bytes())?) }
+				"issuer" => { issuer = Some(value) }
+				"algorithm" => {
+					algorithm = Algorithm::from_name(&value).ok_or(UriError::InvalidAlgorithm)?;
+				}
+				"digits" => {
+					digits = value.parse().ok()
+						.filter(|d| (6..=10).contains(d))
+						.ok_or(UriError::InvalidDigits)?;
+				}
+				"period" => {
+					period = value.parse().ok()
+						.filter(|p| (1..=MAX_PERIOD).contains(p))
+						.ok_or(UriError::InvalidPeriod)?;
+				}
+				"counter" => {
+					counter = Some(value.parse().map_err(|_| UriError::InvalidCounter)?);
+				}
+				// unknown parameters (ex. `image`) are ignored
+				_ => {}
+			}
+		}
+
+		let secret = secret.ok_or(UriError::MissingSecret)?;
+		let hotp = Hotp::new(secret)
+			.with_algorithm(algorithm)
+			.with_digits(digits);
+
+		let kind = if is_totp {
+			OtpKind::Totp(Totp { hotp, period, skew: DEFAULT_SKEW })
+		} else {
+			let counter = counter.ok_or(UriError::InvalidCounter)?;
+			OtpKind::Hotp { hotp, counter }
+		};
+
+		Ok(Self { kind, account, issuer: issuer.or(label_issuer) })
+	}
+}
+
+fn percent_decode(s: &str) -> Result<String, UriError> {
+	let bytes = decode_percent(s.as_bytes()).map_err(|_| UriError::InvalidEncoding)?;
+	String::from_utf8(bytes).map_err(|_| UriError::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::chrono::Utc;
+
+	const SECRET_SHA1: &[u8] = b"12345678901234567890";
+	const SECRET_SHA256: &[u8] = b"12345678901234567890123456789012";
+	const SECRET_SHA512: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+	#[test]
+	fn rfc_4226() {
+		let expected = [755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489];
+
+		let hotp = Hotp::new(SECRET_SHA1.into());
+		for (counter, expected) in expected.into_iter().enumerate() {
+			assert_eq!(hotp.generate_int(counter as u64), expected);
+			assert_eq!(hotp.generate(counter as u64), format!("{expected:06}"));
+		}
+
+		assert_eq!(hotp.verify("969429", 0, 5), Some(3));
+		assert_eq!(hotp.verify("969429", 0, 2), None);
+		assert_eq!(hotp.verify("96942", 0, 5), None);
+		assert_eq!(hotp.verify("96942a", 0, 5), None);
+
+		let hotp = hotp.with_digits(10);
+		assert_eq!(hotp.verify(&hotp.generate(7), 0, 9), Some(7));
+		assert_eq!(hotp.verify("9999999999", 0, 9), None);
+	}
+
+	#[test]
+	fn rfc_6238() {
+		let cases = [
+			(59, "94287082", "46119246", "90693936"),
+			(1111111109, "07081804", "68084774", "25091201"),
+			(1111111111, "14050471", "67062674", "99943326"),
+			(1234567890, "89005924", "91819424", "93441116"),
+			(2000000000, "69279037", "90698825", "38618901"),
+			(20000000000, "65353130", "77737706", "47863826")
+		];
+
+		let sha1 = Totp::new(SECRET_SHA1.into()).with_digits(8);
+		let sha256 = Totp::new(SECRET_SHA256.into()).with_digits(8).with_algorithm(Algorithm::Sha256);
+		let sha512 = Totp::new(SECRET_SHA512.into()).with_digits(8).with_algorithm(Algorithm::Sha512);
+
+		for (timestamp, code_sha1, code_sha256, code_sha512) in cases {
+			let time = Utc.timestamp_opt(timestamp, 0).unwrap();
+			assert_eq!(sha1.generate_at(&time), code_sha1);
+			assert_eq!(sha256.generate_at(&time), code_sha256);
+			assert_eq!(sha512.generate_at(&time), code_sha512);
+			assert!(sha1.verify_at(code_sha1, &time));
+		}
+	}
+
+	#[test]
+	fn skew() {
+		let totp = Totp::new(SECRET_SHA1.into());
+		let time = Utc.timestamp_opt(1_000_000_020, 0).unwrap();
+		let code = totp.generate_at(&time);
+
+		for (offset, valid) in [(-60, false), (-30, true), (0, true), (30, true), (60, false)] {
+			let other = time + ::chrono::TimeDelta::try_seconds(offset).unwrap();
+			assert_eq!(totp.verify_at(&code, &other), valid, "offset {offset}");
+		}
+
+		let strict = totp.clone().with_skew(0);
+		assert!(strict.verify_at(&code, &time));
+		assert!(!strict.verify_at(&code, &(time + ::chrono::TimeDelta::try_seconds(30).unwrap())));
+
+		// skew near the epoch doesn't underflow
+		let epoch = Utc.timestamp_opt(0, 0).unwrap();
+		assert!(totp.verify_at(&totp.generate_at(&epoch), &epoch));
+
+		assert!(totp.verify(&totp.generate()));
+	}
+
+	#[test]
+	fn secrets() {
+		let expected = b"Hello!\xde\xad\xbe\xef";
+		for secret in ["JBSWY3DPEHPK3PXP", "jbswy3dpehpk3pxp", "JBSW Y3DP EHPK 3PXP", "JBSW-Y3DP-EHPK-3PXP"] {
+			assert_eq!(decode_secret(secret.as_bytes()).unwrap(), expected);
+		}
+		assert_eq!(decode_secret(b"GEZDGNBV").unwrap(), b"12345");
+		assert_eq!(decode_secret(b"GEZDGNBVGY").unwrap(), b"123456");
+		assert_eq!(decode_secret(b"GEZDGNBVGY======").unwrap(), b"123456");
+		assert!(matches!(decode_secret(b""), Err(SecretError::Empty)));
+		assert!(matches!(decode_secret(b"JBSWY3DP1"), Err(SecretError::InvalidBase32(_))));
+	}
+
+	#[test]
+	fn uris() {
+		let uri = OtpAuthUri::parse("otpauth://totp/Example:alice@example.com?secret=JBSWY3DPEHPK3PXP&issuer=Example").unwrap();
+		assert_eq!(uri.account, "alice@example.com");
+		assert_eq!(uri.issuer.as_deref(), Some("Example"));
+		let OtpKind::Totp(totp) = uri.kind else { panic!("expected totp") };
+		assert_eq!(totp.secret(), b"Hello!\xde\xad\xbe\xef");
+		assert_eq!(totp.algorithm(), Algorithm::Sha1);
+		assert_eq!(totp.digits(), 6);
+		assert_eq!(totp.period(), 30);
+
+		let uri = OtpAuthUri::parse("otpauth://totp/ACME%20Co:john.doe%40email.com?secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&algorithm=SHA256&digits=8&period=60").unwrap();
+		assert_eq!(uri.account, "john.doe@email.com");
+		assert_eq!(uri.issuer.as_deref(), Some("ACME Co"));
+		let OtpKind::Totp(totp) = uri.kind else { panic!("expected totp") };
+		assert_eq!(totp.algorithm(), Algorithm::Sha256);
+		assert_eq!(totp.digits(), 8);
+		assert_eq!(totp.period(), 60);
+
+		let uri = OtpAuthUri::parse("otpauth://hotp/alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&counter=3").unwrap();
+		assert_eq!(uri.account, "alice");
+		assert_eq!(uri.issuer, None);
+		let OtpKind::Hotp { hotp, counter } = uri.kind else { panic!("expected hotp") };
+		assert_eq!(counter, 3);
+		assert_eq!(hotp.generate(counter), "969429");
+
+		let invalid = [
+			("https://totp/a?secret=JBSWY3DP", "scheme"),
+			("otpauth://motp/a?secret=JBSWY3DP", "type"),
+			("otpauth://totp/a?issuer=a", "missing secret"),
+			("otpauth://totp/a?secret=JBSWY3D1", "secret"),
+			("otpauth://totp/a?secret=JBSWY3DP&algorithm=MD5", "algorithm"),
+			("otpauth://totp/a?secret=JBSWY3DP&digits=4", "digits"),
+			("otpauth://totp/a?secret=JBSWY3DP&period=0", "period"),
+			("otpauth://totp/a?secret=JBSWY3DP&period=4294967296", "period"),
+			("otpauth://hotp/a?secret=JBSWY3DP", "counter"),
+			("otpauth://totp/a%2?secret=JBSWY3DP", "encoding")
+		];
+		for (uri, reason) in invalid {
+			assert!(OtpAuthUri::parse(uri).is_err(), "{reason}");
+		}
+	}
+
+	#[test]
+	fn debug_redacts_secret() {
+		let debug = format!("{:?}", Totp::new(b"very secret".to_vec()));
+		assert!(debug.contains("<redacted>"));
+		assert!(!debug.contains('['));
+	}
+
+	#[cfg(all(feature = "clock-timer-2", feature = "tokio"))]
+	#[::tokio::test(crate = "::tokio")]
+	async fn rotation() {
+		let totp = Totp::new(SECRET_SHA1.into()).with_period(1);
+		let mut timer = totp.rotation_timer(::chrono::TimeDelta::try_milliseconds(1500).unwrap());
+
+		let mut times = Vec::new();
+		while let Some(tick) = timer.tick().await {
+			let time = tick.time();
+			assert_eq!(time.timestamp_subsec_nanos(), 0, "ticks are aligned to the period");
+			assert!(time <= ::chrono::Local::now());
+			times.push(time);
+		}
+
+		assert!(times.len() >= 2);
+		for pair in times.windows(2) {
+			assert_eq!(totp.counter_at(&pair[1]), totp.counter_at(&pair[0]) + 1);
+			assert_ne!(totp.generate_at(&pair[1]), totp.generate_at(&pair[0]));
+		}
+	}
+}
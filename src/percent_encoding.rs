@@ -0,0 +1,393 @@
+//! Percent-encoding (also known as URL encoding), with the percent-encode
+//! sets defined in the [WHATWG URL standard].
+//!
+//! Escapes are always encoded using upper case hex, as recommended by
+//! [RFC 3986], and decoding accepts both upper and lower case hex.
+//!
+//! [WHATWG URL standard]: https://url.spec.whatwg.org/#percent-encoded-bytes
+//! [RFC 3986]: https://datatracker.ietf.org/doc/html/rfc3986#section-2.1
+
+use crate::encoding_utils::{ UnsafeBufWrite, UnsafeBufWriteGuard };
+use crate::hex::{ TABLE_DECODER, TABLE_ENCODER_UPPER };
+
+/// A set of ASCII bytes to percent-encode. Non-ASCII bytes (`0x80` and above)
+/// are always encoded, regardless of the set.
+///
+/// Sets are built in `const` contexts by starting from another set, and
+/// calling [`add`](AsciiSet::add) and [`remove`](AsciiSet::remove).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AsciiSet {
+	/// bit n set means byte n should be encoded
+	mask: u128
+}
+
+impl AsciiSet {
+	/// Empty set, where only non-ASCII bytes are encoded
+	pub const EMPTY: Self = Self { mask: 0 };
+
+	/// C0 control percent-encode set: C0 controls (`0x00..=0x1f`) and `0x7f`
+	pub const C0_CONTROL: Self = Self { mask: 0xffff_ffff | (1 << 0x7f) };
+
+	/// Fragment percent-encode set: [`C0_CONTROL`](Self::C0_CONTROL),
+	/// plus space, `"`, `<`, `>`, and `` ` ``
+	pub const FRAGMENT: Self = Self::C0_CONTROL
+		.add(b' ')
+		.add(b'"')
+		.add(b'<')
+		.add(b'>')
+		.add(b'`');
+
+	/// Query percent-encode set: [`C0_CONTROL`](Self::C0_CONTROL),
+	/// plus space, `"`, `#`, `<`, and `>`
+	pub const QUERY: Self = Self::C0_CONTROL
+		.add(b' ')
+		.add(b'"')
+		.add(b'#')
+		.add(b'<')
+		.add(b'>');
+
+	/// Special-query percent-encode set (for queries of special schemes, like
+	/// `http` and `https`): [`QUERY`](Self::QUERY), plus `'`
+	pub const SPECIAL_QUERY: Self = Self::QUERY.add(b'\'');
+
+	/// Path percent-encode set: [`QUERY`](Self::QUERY), plus `?`, `` ` ``,
+	/// `{`, and `}`
+	pub const PATH: Self = Self::QUERY
+		.add(b'?')
+		.add(b'`')
+		.add(b'{')
+		.add(b'}');
+
+	/// Userinfo percent-encode set: [`PATH`](Self::PATH), plus `/`, `:`,
+	/// `;`, `=`, `@`, `[`, `\`, `]`, `^`, and `|`
+	pub const USERINFO: Self = Self::PATH
+		.add(b'/')
+		.add(b':')
+		.add(b';')
+		.add(b'=')
+		.add(b'@')
+		.add(b'[')
+		.add(b'\\')
+		.add(b']')
+		.add(b'^')
+		.add(b'|');
+
+	/// Component percent-encode set (what JavaScript's `encodeURIComponent`
+	/// encodes): [`USERINFO`](Self::USERINFO), plus `$`, `%`, `&`, `+`, and `,`
+	pub const COMPONENT: Self = Self::USERINFO
+		.add(b'$')
+		.add(b'%')
+		.add(b'&')
+		.add(b'+')
+		.add(b',');
+
+	/// `application/x-www-form-urlencoded` percent-encode set:
+	/// [`COMPONENT`](Self::COMPONENT), plus `!`, `'`, `(`, `)`, and `~`.
+	///
+	/// When encoding with [`encode_form_urlencoded`], space is encoded as `+`
+	/// instead of `%20`.
+	pub const FORM_URLENCODED: Self = Self::COMPONENT
+		.add(b'!')
+		.add(b'\'')
+		.add(b'(')
+		.add(b')')
+		.add(b'~');
+
+	/// Every byte that isn't an ASCII alphanumeric
+	pub const NON_ALPHANUMERIC: Self = Self {
+		mask: !((0x3ff << b'0') | (0x3ff_ffff << b'A') | (0x3ff_ffff << b'a'))
+	};
+
+	/// Returns a new set with the provided byte added to it. Panics if the
+	/// byte isn't ASCII.
+	#[inline]
+	pub const fn add(self, byte: u8) -> Self {
+		assert!(byte.is_ascii(), "non-ASCII bytes are always encoded");
+		Self { mask: self.mask | (1 << byte) }
+	}
+
+	/// Returns a new set with the provided byte removed from it. Panics if
+	/// the byte isn't ASCII.
+	#[inline]
+	pub const fn remove(self, byte: u8) -> Self {
+		assert!(byte.is_ascii(), "non-ASCII bytes are always encoded");
+		Self { mask: self.mask & !(1 << byte) }
+	}
+
+	/// Returns the union of two sets
+	#[inline]
+	pub const fn union(self, other: Self) -> Self {
+		Self { mask: self.mask | other.mask }
+	}
+
+	/// Whether this byte should be percent-encoded with this set
+	#[inline]
+	pub const fn should_encode(&self, byte: u8) -> bool {
+		!byte.is_ascii() || (self.mask >> byte) & 1 != 0
+	}
+}
+
+/// Percent-encodes the given bytes, escaping every byte in `set` as well as
+/// every non-ASCII byte
+#[inline]
+pub fn encode_percent(bytes: &[u8], set: &AsciiSet) -> String {
+	_encode::<false>(bytes, set)
+}
+
+/// Encodes the given bytes as `application/x-www-form-urlencoded`, using
+/// [`AsciiSet::FORM_URLENCODED`], and encoding space as `+`
+#[inline]
+pub fn encode_form_urlencoded(bytes: &[u8]) -> String {
+	_encode::<true>(bytes, &AsciiSet::FORM_URLENCODED)
+}
+
+/// Decodes percent-encoded bytes, returning an error if a `%` is not followed
+/// by two hex digits
+#[inline]
+pub fn decode_percent(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode::<true, false>(bytes)
+}
+
+/// Decodes percent-encoded bytes. Malformed escapes (a `%` not followed by
+/// two hex digits) are left in the output as is, like specified by the
+/// WHATWG URL standard.
+#[inline]
+pub fn decode_percent_lossy(bytes: &[u8]) -> Vec<u8> {
+	match _decode::<false, false>(bytes) {
+		Ok(vec) => { vec }
+		Err(_) => { unreachable!("lossy decoding doesn't fail") }
+	}
+}
+
+/// Decodes `application/x-www-form-urlencoded` bytes, decoding `+` as space.
+/// Returns an error if a `%` is not followed by two hex digits.
+#[inline]
+pub fn decode_form_urlencoded(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode::<true, true>(bytes)
+}
+
+/// Decodes `application/x-www-form-urlencoded` bytes, decoding `+` as space.
+/// Malformed escapes are left in the output as is.
+#[inline]
+pub fn decode_form_urlencoded_lossy(bytes: &[u8]) -> Vec<u8> {
+	match _decode::<false, true>(bytes) {
+		Ok(vec) => { vec }
+		Err(_) => { unreachable!("lossy decoding doesn't fail") }
+	}
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError {
+	#[error("invalid percent escape at position {position}")]
+	InvalidEscape {
+		/// Position of the `%` that starts the invalid escape
+		position: usize
+	}
+}
+
+fn _encode<const PLUS_FOR_SPACE: bool>(bytes: &[u8], set: &AsciiSet) -> String {
+	// every encoded byte takes 2 extra bytes (`%` and another hex digit)
+	let escaped = bytes.iter()
+		.filter(|b| set.should_encode(**b) && !(PLUS_FOR_SPACE && **b == b' '))
+		.count();
+	let capacity = bytes.len() + (escaped << 1);
+
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	for byte in bytes {
+		let byte = *byte;
+
+		unsafe {
+			// SAFETY: we counted the exact amount of escapes above, so capacity
+			// is correct. Shifting right by 4 and masking with 0xf both keep
+			// the index in range of the 16 long encoder table
+			if PLUS_FOR_SPACE && byte == b' ' {
+				dest.write_bytes_const::<1>(&b'+');
+			} else if set.should_encode(byte) {
+				let escape = [
+					b'%',
+					*TABLE_ENCODER_UPPER.get_unchecked((byte >> 4) as usize),
+					*TABLE_ENCODER_UPPER.get_unchecked((byte & 0xf) as usize)
+				];
+				dest.write_bytes_const::<3>(&escape as *const u8);
+			} else {
+				dest.write_bytes_const::<1>(&byte);
+			}
+		}
+	}
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+
+	// SAFETY: every non-ASCII byte is escaped, and escapes are ASCII
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Returns the decoded byte if there is a valid escape starting at `i`
+/// (`bytes[i]` is assumed to be `%`)
+#[inline]
+fn decode_escape(bytes: &[u8], i: usize) -> Option<u8> {
+	let &[high, low] = bytes.get(i + 1..i + 3)? else { return None };
+
+	let high = TABLE_DECODER[high as usize]?;
+	let low = TABLE_DECODER[low as usize]?;
+	Some((high << 4) | low)
+}
+
+fn _decode<const STRICT: bool, const PLUS_AS_SPACE: bool>(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	// first pass to find exact length, by finding the valid escapes
+	// (and erroring on invalid ones, if strict)
+	let mut escapes = 0usize;
+	let mut i = 0usize;
+
+	while i < bytes.len() {
+		if bytes[i] == b'%' {
+			if decode_escape(bytes, i).is_some() {
+				escapes += 1;
+				i += 3;
+				continue
+			}
+
+			if STRICT { return Err(DecodeError::InvalidEscape { position: i }) }
+		}
+
+		i += 1;
+	}
+
+	let capacity = bytes.len() - (escapes << 1);
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+	let mut i = 0usize;
+
+	while i < bytes.len() {
+		let byte = bytes[i];
+
+		unsafe {
+			// SAFETY: the same escapes are found as the first pass, so capacity
+			// is correct
+			if byte == b'%' {
+				if let Some(decoded) = decode_escape(bytes, i) {
+					dest.write_bytes_const::<1>(&decoded);
+					i += 3;
+					continue
+				}
+			}
+
+			if PLUS_AS_SPACE && byte == b'+' {
+				dest.write_bytes_const::<1>(&b' ');
+			} else {
+				dest.write_bytes_const::<1>(&byte);
+			}
+		}
+
+		i += 1;
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn encode_sets() {
+		let input = "a b\"#<>?`{}/:;=@[\\]^|$%&+,!'()~é\x00\x7f";
+		let examples = [
+			(AsciiSet::EMPTY, "a b\"#<>?`{}/:;=@[\\]^|$%&+,!'()~%C3%A9\x00\x7f"),
+			(AsciiSet::C0_CONTROL, "a b\"#<>?`{}/:;=@[\\]^|$%&+,!'()~%C3%A9%00%7F"),
+			(AsciiSet::FRAGMENT, "a%20b%22#%3C%3E?%60{}/:;=@[\\]^|$%&+,!'()~%C3%A9%00%7F"),
+			(AsciiSet::QUERY, "a%20b%22%23%3C%3E?`{}/:;=@[\\]^|$%&+,!'()~%C3%A9%00%7F"),
+			(AsciiSet::SPECIAL_QUERY, "a%20b%22%23%3C%3E?`{}/:;=@[\\]^|$%&+,!%27()~%C3%A9%00%7F"),
+			(AsciiSet::PATH, "a%20b%22%23%3C%3E%3F%60%7B%7D/:;=@[\\]^|$%&+,!'()~%C3%A9%00%7F"),
+			(AsciiSet::USERINFO, "a%20b%22%23%3C%3E%3F%60%7B%7D%2F%3A%3B%3D%40%5B%5C%5D%5E%7C$%&+,!'()~%C3%A9%00%7F"),
+			(AsciiSet::COMPONENT, "a%20b%22%23%3C%3E%3F%60%7B%7D%2F%3A%3B%3D%40%5B%5C%5D%5E%7C%24%25%26%2B%2C!'()~%C3%A9%00%7F"),
+			(AsciiSet::FORM_URLENCODED, "a%20b%22%23%3C%3E%3F%60%7B%7D%2F%3A%3B%3D%40%5B%5C%5D%5E%7C%24%25%26%2B%2C%21%27%28%29%7E%C3%A9%00%7F"),
+			(AsciiSet::NON_ALPHANUMERIC, "a%20b%22%23%3C%3E%3F%60%7B%7D%2F%3A%3B%3D%40%5B%5C%5D%5E%7C%24%25%26%2B%2C%21%27%28%29%7E%C3%A9%00%7F")
+		];
+
+		for (set, encoded) in examples {
+			assert_eq!(encoded, encode_percent(input.as_bytes(), &set));
+			// `%` isn't in most of the sets, but `%&+` isn't a valid escape anyways
+			assert_eq!(input.as_bytes(), decode_percent_lossy(encoded.as_bytes()));
+		}
+	}
+
+	#[test]
+	fn form_urlencoded() {
+		let input = "name=wiwi rocks&emoji=😃+1";
+		let encoded = "name%3Dwiwi+rocks%26emoji%3D%F0%9F%98%83%2B1";
+
+		assert_eq!(encoded, encode_form_urlencoded(input.as_bytes()));
+		assert_eq!(input.as_bytes(), decode_form_urlencoded(encoded.as_bytes()).unwrap());
+
+		// + is only a space in form decoding
+		assert_eq!(b"a+b", &*decode_percent(b"a+b").unwrap());
+		assert_eq!(b"a b", &*decode_form_urlencoded(b"a+b").unwrap());
+	}
+
+	#[test]
+	fn malformed_escapes() {
+		let examples = [
+			("%", 0),
+			("abc%", 3),
+			("abc%4", 3),
+			("%4g", 0),
+			("%%41", 0),
+			("%41%zz", 3)
+		];
+
+		for (input, position) in examples {
+			let err = decode_percent(input.as_bytes()).unwrap_err();
+			assert!(matches!(err, DecodeError::InvalidEscape { position: p } if p == position));
+		}
+
+		let lossy = [
+			("%", "%"),
+			("abc%4", "abc%4"),
+			("%4g", "%4g"),
+			("%%41", "%A"),
+			("%41%zz", "A%zz"),
+			("%e2%9C%93", "✓")
+		];
+
+		for (input, decoded) in lossy {
+			assert_eq!(decoded.as_bytes(), decode_percent_lossy(input.as_bytes()));
+		}
+
+		assert_eq!(b"a b%", &*decode_form_urlencoded_lossy(b"a+b%"));
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 2, 3, 10, 100, 1000, 100_000] {
+			for _ in 0usize..5 {
+				let mut original_input = vec![0u8; len];
+				rng.fill(&mut *original_input);
+
+				// sets need to include `%` to be able to round trip arbitrary input
+				for encoded in [
+					encode_percent(&original_input, &AsciiSet::EMPTY.add(b'%')),
+					encode_percent(&original_input, &AsciiSet::PATH.add(b'%')),
+					encode_percent(&original_input, &AsciiSet::COMPONENT)
+				] {
+					let decoded = decode_percent(encoded.as_bytes())
+						.expect("can round trip decode just encoded data");
+					assert_eq!(original_input, decoded);
+
+					// this is enforced by debug_assert! in the code, so this already
+					// is validated if tests are run in debug, but still,
+					assert_eq!(encoded.len(), encoded.capacity());
+					assert_eq!(decoded.len(), decoded.capacity());
+				}
+
+				let encoded = encode_form_urlencoded(&original_input);
+				let decoded = decode_form_urlencoded(encoded.as_bytes())
+					.expect("can round trip decode just encoded data");
+				assert_eq!(original_input, decoded);
+			}
+		}
+	}
+}
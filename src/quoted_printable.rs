@@ -0,0 +1,357 @@
+//! Quoted-printable encoding, as specified in [RFC 2045].
+//!
+//! Encoded lines are at most 76 characters long (not counting the line break),
+//! using soft line breaks (`=` at the end of a line) to wrap longer lines.
+//! Escapes (`=XX`) use upper case hex, and are never split across lines.
+//!
+//! [RFC 2045]: https://datatracker.ietf.org/doc/html/rfc2045#section-6.7
+
+use crate::encoding_utils::{ UnsafeBufWrite, UnsafeBufWriteGuard };
+use crate::hex::{ TABLE_DECODER, TABLE_ENCODER_UPPER };
+
+/// Max length of an encoded line, not including the line break
+pub const MAX_LINE_LEN: usize = 76;
+
+/// How line breaks in the input are treated when encoding
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+	/// Line breaks (`\r\n` or `\n`) in the input are encoded as hard line
+	/// breaks (always `\r\n`, as required by RFC 2045). Whitespace at the end
+	/// of a line is escaped so that it survives transport.
+	Text,
+	/// Every `\r` and `\n` is escaped, so the encoded output only ever contains
+	/// soft line breaks and the input is reproduced byte for byte when decoded.
+	Binary
+}
+
+/// Encodes the given bytes into a quoted-printable [`String`]
+pub fn encode_quoted_printable(bytes: &[u8], mode: Mode) -> String {
+	let mut counter = Counter(0);
+	encode_into(bytes, mode, &mut counter);
+
+	let mut writer = Writer(UnsafeBufWriteGuard::with_capacity(counter.0));
+	encode_into(bytes, mode, &mut writer);
+
+	let vec = writer.0.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+
+	// SAFETY: every byte outside of printable ASCII is escaped, and escapes are ASCII
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Decodes quoted-printable bytes back into the source bytes.
+///
+/// Soft line breaks are removed, and whitespace at the end of lines is
+/// removed as required by RFC 2045. Hard line breaks are kept as they are
+/// (`\r\n` or `\n`). Returns an error on a `=` not followed by two hex digits
+/// or a line break, and on control characters other than tab and line breaks.
+#[inline]
+pub fn decode_quoted_printable(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode::<true>(bytes)
+}
+
+/// Decodes quoted-printable bytes back into the source bytes, like
+/// [`decode_quoted_printable`], except invalid escapes and characters are
+/// left in the output as is, as suggested by RFC 2045.
+#[inline]
+pub fn decode_quoted_printable_lossy(bytes: &[u8]) -> Vec<u8> {
+	match _decode::<false>(bytes) {
+		Ok(vec) => { vec }
+		Err(_) => { unreachable!("lossy decoding doesn't fail") }
+	}
+}
+
+#[derive(Debug, ::thiserror::Error)]
+pub enum DecodeError {
+	#[error("invalid escape at position {position}")]
+	InvalidEscape {
+		/// Position of the `=` that starts the invalid escape
+		position: usize
+	},
+	#[error("invalid character at position {position}")]
+	InvalidChar { position: usize }
+}
+
+/// Encoding and decoding both run twice, once to count the exact amount of
+/// bytes needed, and another time to actually write the bytes into a buffer
+/// of that exact size.
+trait Sink {
+	fn push(&mut self, bytes: &[u8]);
+}
+
+struct Counter(usize);
+
+impl Sink for Counter {
+	#[inline]
+	fn push(&mut self, bytes: &[u8]) {
+		self.0 += bytes.len();
+	}
+}
+
+struct Writer(UnsafeBufWriteGuard);
+
+impl Sink for Writer {
+	#[inline]
+	fn push(&mut self, bytes: &[u8]) {
+		// SAFETY: the buffer was allocated with the length counted by running
+		// the same function with `Counter`
+		unsafe { self.0.write_bytes(bytes as *const [u8] as *const u8, bytes.len()) }
+	}
+}
+
+/// Returns the length of the line break starting at `i`, if there is one
+#[inline]
+fn line_break_len(bytes: &[u8], i: usize) -> Option<usize> {
+	match bytes.get(i..) {
+		Some([b'\r', b'\n', ..]) => { Some(2) }
+		Some([b'\n', ..]) => { Some(1) }
+		_ => { None }
+	}
+}
+
+fn encode_into<S: Sink>(bytes: &[u8], mode: Mode, sink: &mut S) {
+	let mut line_len = 0usize;
+	let mut i = 0usize;
+
+	while i < bytes.len() {
+		if mode == Mode::Text {
+			if let Some(len) = line_break_len(bytes, i) {
+				sink.push(b"\r\n");
+				line_len = 0;
+				i += len;
+				continue
+			}
+		}
+
+		let byte = bytes[i];
+		let next = i + 1;
+
+		let at_line_end = next == bytes.len()
+			|| (mode == Mode::Text && line_break_len(bytes, next).is_some());
+
+		let literal = match byte {
+			b'=' => { false }
+			33..=126 => { true }
+			// whitespace is only literal if it isn't trailing
+			b' ' | b'\t' => { !at_line_end }
+			_ => { false }
+		};
+
+		let escape = [
+			b'=',
+			TABLE_ENCODER_UPPER[(byte >> 4) as usize],
+			TABLE_ENCODER_UPPER[(byte & 0xf) as usize]
+		];
+		let token: &[u8] = if literal { &bytes[i..next] } else { &escape };
+
+		// the last token on a line can take up the spot that
+		// would have otherwise been used by the soft line break
+		let max = if at_line_end { MAX_LINE_LEN } else { MAX_LINE_LEN - 1 };
+		if line_len + token.len() > max {
+			sink.push(b"=\r\n");
+			line_len = 0;
+		}
+
+		sink.push(token);
+		line_len += token.len();
+		i = next;
+	}
+}
+
+fn _decode<const STRICT: bool>(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	let mut counter = Counter(0);
+	decode_into::<STRICT, _>(bytes, &mut counter)?;
+
+	let mut writer = Writer(UnsafeBufWriteGuard::with_capacity(counter.0));
+	decode_into::<STRICT, _>(bytes, &mut writer)?;
+
+	Ok(writer.0.into_full_vec())
+}
+
+fn decode_into<const STRICT: bool, S: Sink>(bytes: &[u8], sink: &mut S) -> Result<(), DecodeError> {
+	let mut i = 0usize;
+
+	while i < bytes.len() {
+		let byte = bytes[i];
+
+		match byte {
+			b'=' => {
+				if let Some(&[high, low]) = bytes.get(i + 1..i + 3) {
+					if let (Some(high), Some(low)) = (TABLE_DECODER[high as usize], TABLE_DECODER[low as usize]) {
+						sink.push(&[(high << 4) | low]);
+						i += 3;
+						continue
+					}
+				}
+
+				// soft line break, possibly with (trailing) whitespace between
+				// the `=` and the line break
+				let after_whitespace = skip_whitespace(bytes, i + 1);
+				if after_whitespace == bytes.len() {
+					i = after_whitespace;
+					continue
+				}
+				if let Some(len) = line_break_len(bytes, after_whitespace) {
+					i = after_whitespace + len;
+					continue
+				}
+
+				if STRICT { return Err(DecodeError::InvalidEscape { position: i }) }
+				sink.push(b"=");
+				i += 1;
+			}
+			b' ' | b'\t' => {
+				let after_whitespace = skip_whitespace(bytes, i);
+				let trailing = after_whitespace == bytes.len()
+					|| line_break_len(bytes, after_whitespace).is_some();
+
+				if !trailing { sink.push(&bytes[i..after_whitespace]) }
+				i = after_whitespace;
+			}
+			b'\r' | b'\n' => {
+				if let Some(len) = line_break_len(bytes, i) {
+					sink.push(&bytes[i..i + len]);
+					i += len;
+					continue
+				}
+
+				// lone \r
+				if STRICT { return Err(DecodeError::InvalidChar { position: i }) }
+				sink.push(b"\r");
+				i += 1;
+			}
+			33..=126 => {
+				sink.push(&[byte]);
+				i += 1;
+			}
+			_ => {
+				if STRICT { return Err(DecodeError::InvalidChar { position: i }) }
+				sink.push(&[byte]);
+				i += 1;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+#[inline]
+fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+	while let Some(b' ' | b'\t') = bytes.get(i) {
+		i += 1;
+	}
+	i
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::rand::{ Rng, thread_rng };
+
+	#[test]
+	fn basic() {
+		let examples = [
+			("", ""),
+			("hello world", "hello world"),
+			("1 + 1 = 2", "1 + 1 =3D 2"),
+			("café", "caf=C3=A9"),
+			("trailing space ", "trailing space=20"),
+			("trailing tab\t", "trailing tab=09"),
+			("line one \r\nline two", "line one=20\r\nline two"),
+			("line one\nline two", "line one\r\nline two")
+		];
+
+		for (bytes, encoded) in examples {
+			assert_eq!(encoded, encode_quoted_printable(bytes.as_bytes(), Mode::Text));
+		}
+
+		assert_eq!("a=0D=0Ab=0A", encode_quoted_printable(b"a\r\nb\n", Mode::Binary));
+	}
+
+	#[test]
+	fn soft_line_breaks() {
+		let input = "a".repeat(200);
+		let encoded = encode_quoted_printable(input.as_bytes(), Mode::Text);
+		let lines = encoded.split("\r\n").collect::<Vec<_>>();
+
+		assert_eq!(lines, [
+			format!("{}=", "a".repeat(75)),
+			format!("{}=", "a".repeat(75)),
+			"a".repeat(50)
+		]);
+
+		// exactly 76 chars fits on one line without a soft break
+		let input = "a".repeat(76);
+		assert_eq!(input, encode_quoted_printable(input.as_bytes(), Mode::Text));
+
+		// escapes aren't split
+		let input = format!("{}é", "a".repeat(72));
+		let encoded = encode_quoted_printable(input.as_bytes(), Mode::Text);
+		assert_eq!(encoded, format!("{}=C3=\r\n=A9", "a".repeat(72)));
+	}
+
+	#[test]
+	fn decoding() {
+		let examples = [
+			("hello=20world", "hello world"),
+			("soft=\r\nbreak", "softbreak"),
+			("soft=  \t\r\nbreak with padding", "softbreak with padding"),
+			("soft=\nbreak bare lf", "softbreak bare lf"),
+			("trailing   \r\nwhitespace\t \nremoved  ", "trailing\r\nwhitespace\nremoved"),
+			("lower=c3=a9", "loweré"),
+			("ends with soft break=", "ends with soft break")
+		];
+
+		for (encoded, decoded) in examples {
+			assert_eq!(decoded.as_bytes(), decode_quoted_printable(encoded.as_bytes()).unwrap());
+		}
+
+		assert!(matches!(decode_quoted_printable(b"a=zz"), Err(DecodeError::InvalidEscape { position: 1 })));
+		assert!(matches!(decode_quoted_printable(b"a=4"), Err(DecodeError::InvalidEscape { position: 1 })));
+		assert!(matches!(decode_quoted_printable(b"a\rb"), Err(DecodeError::InvalidChar { position: 1 })));
+		assert!(matches!(decode_quoted_printable(b"a\x00b"), Err(DecodeError::InvalidChar { position: 1 })));
+
+		assert_eq!(b"a=zz b=4", &*decode_quoted_printable_lossy(b"a=zz b=4"));
+		assert_eq!(b"a\rb\x00", &*decode_quoted_printable_lossy(b"a\rb\x00"));
+	}
+
+	#[test]
+	fn randomised() {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 2, 3, 10, 75, 76, 77, 100, 1000, 100_000] {
+			for _ in 0usize..5 {
+				let mut original_input = vec![0u8; len];
+				rng.fill(&mut *original_input);
+
+				let encoded = encode_quoted_printable(&original_input, Mode::Binary);
+				assert!(encoded.split("\r\n").all(|line| line.len() <= MAX_LINE_LEN));
+
+				let decoded = decode_quoted_printable(encoded.as_bytes())
+					.expect("can round trip decode just encoded data");
+				assert_eq!(original_input, decoded);
+
+				// this is enforced by debug_assert! in the code, so this already
+				// is validated if tests are run in debug, but still,
+				assert_eq!(encoded.len(), encoded.capacity());
+				assert_eq!(decoded.len(), decoded.capacity());
+
+				// text with only CRLF line breaks round trips exactly in text mode
+				let text = original_input.iter()
+					.map(|b| match b % 8 { 0 => { b' ' } 1 => { b'\t' } _ => { *b } })
+					.filter(|b| *b != b'\r' && *b != b'\n')
+					.collect::<Vec<_>>()
+					.chunks(50)
+					.collect::<Vec<_>>()
+					.join(&b"\r\n"[..]);
+
+				let encoded = encode_quoted_printable(&text, Mode::Text);
+				assert!(encoded.split("\r\n").all(|line| line.len() <= MAX_LINE_LEN));
+
+				let decoded = decode_quoted_printable(encoded.as_bytes())
+					.expect("can round trip decode just encoded data");
+				assert_eq!(text, decoded);
+			}
+		}
+	}
+}
@@ -160,8 +160,6 @@ mod tests {
 	}
 
 	fn hash_item<T: Hash>(hash_builder: &DefaultHashBuilder, item: &T) -> u64 {
-		let mut hasher = hash_builder.build_hasher();
-		item.hash(&mut hasher);
-		hasher.finish()
+		hash_builder.hash_one(item)
 	}
 }
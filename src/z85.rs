@@ -18,8 +18,8 @@
 //!
 //! Original Z85 spec: https://rfc.zeromq.org/spec/32
 
-use crate::encoding_utils::{ ChunkedSlice, UnsafeBufWriteGuard};
-use ::std::slice;
+use crate::encoding_utils::{ available_threads, for_each_frame_run, parallel_chunks, ChunkedSlice, UnsafeBufWrite, UnsafeBufWriteDiscard, UnsafeBufWriteGuard, UnsafeBufWriteSlice };
+use ::std::{ ptr, slice };
 
 pub const TABLE_ENCODER_LEN: usize = 85;
 pub const TABLE_ENCODER: [u8; TABLE_ENCODER_LEN] = *b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
@@ -51,6 +51,8 @@ pub const TABLE_DECODER: [Option<u8>; TABLE_DECODER_LEN] = [
 pub const BINARY_FRAME_LEN: usize = 4;
 pub const STRING_FRAME_LEN: usize = 5;
 
+pub use crate::encoding_utils::{ ArrayString, VectoredInput };
+
 /// Encodes a slice of bytes into a Z85 string, adding padding if necessary
 pub fn encode_z85(bytes: &[u8]) -> String {
 	// we *don't* fast path out on zero bytes, because in like, 99% of situations,
@@ -60,14 +62,128 @@ pub fn encode_z85(bytes: &[u8]) -> String {
 	// created from empty Vec (empty string, no allocation too). so all is good
 	// functionality wise, and its still a fairly fast exit too, I think.
 
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(bytes.len()));
+
+	// SAFETY: we preallocated exact amount of memory needed
+	unsafe { encode_into(bytes, &mut dest) }
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+
+	// SAFETY: we only are pushing in chars in the table, which are all ASCII chars
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Encodes a slice of bytes into Z85 (adding padding if necessary), writing it
+/// to the start of `dest` instead of allocating, and returning the amount of
+/// bytes written.
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than the encoded length (5 chars for every 4
+/// bytes, and if there's a padded frame, a whole frame and 1 more char for it).
+pub fn encode_z85_to_slice(bytes: &[u8], dest: &mut [u8]) -> usize {
+	let len = encoded_len(bytes.len());
+	assert!(dest.len() >= len, "dest is too short for the encoded bytes");
+
+	unsafe {
+		// SAFETY: we checked dest has enough space
+		let mut dest = UnsafeBufWriteSlice::new(dest.as_mut_ptr(), len);
+		encode_into(bytes, &mut dest);
+		dest.assert_is_full();
+	}
+
+	len
+}
+
+/// Encodes the concatenation of several slices (ex. `&[IoSlice]`) into one Z85
+/// string without joining them first, adding padding if necessary
+pub fn encode_z85_vectored<V: VectoredInput + ?Sized>(input: &V) -> String {
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(input.total_len()));
+
+	// SAFETY: we preallocated exact amount of memory needed. Only the last
+	// run can have a partial frame, so padding is only added at the very end
+	for_each_frame_run::<BINARY_FRAME_LEN, _, _>(input, |run| unsafe { encode_into(run, &mut dest) });
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+
+	// SAFETY: we only are pushing in chars in the table, which are all ASCII chars
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Encodes a slice of bytes into a Z85 string, splitting the work across
+/// multiple threads for large inputs. Smaller inputs are encoded on the
+/// calling thread, the same as [`encode_z85`].
+#[inline]
+pub fn encode_z85_parallel(bytes: &[u8]) -> String {
+	_encode_parallel(bytes, available_threads())
+}
+
+fn _encode_parallel(bytes: &[u8], threads: usize) -> String {
+	let Some(chunks) = parallel_chunks(bytes, BINARY_FRAME_LEN, threads) else {
+		return encode_z85(bytes)
+	};
+
+	let mut dest = UnsafeBufWriteGuard::with_capacity(encoded_len(bytes.len()));
+
+	let result = unsafe {
+		// only the last chunk can have a partial frame, so the
+		// padding byte is still only added at the very end
+		dest.write_parallel(&chunks, |chunk| encoded_len(chunk.len()), |chunk, dest| {
+			encode_into(chunk, dest);
+			Ok::<_, ()>(())
+		})
+	};
+	debug_assert!(result.is_ok(), "encoding doesn't fail");
+
+	let vec = dest.into_full_vec();
+	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+
+	// SAFETY: we only are pushing in chars in the table, which are all ASCII chars
+	unsafe { String::from_utf8_unchecked(vec) }
+}
+
+/// Encodes a fixed size array of bytes into a Z85 string (adding padding if
+/// necessary), returning it as an [`ArrayString`] on the stack instead of
+/// allocating.
+///
+/// `M` must be exactly the encoded length of `N` bytes, which is checked at
+/// compile time; usually it can be inferred from a type annotation. For
+/// multiples of 4 that's `N / 4 * 5`; otherwise, it's rounded up to a whole
+/// frame, plus one for the padding marker.
+pub fn encode_z85_array<const N: usize, const M: usize>(bytes: &[u8; N]) -> ArrayString<M> {
+	let () = ArrayLens::<N, M>::CHECK;
+
+	let mut array = [0u8; M];
+	unsafe {
+		// SAFETY: array has exactly the space needed
+		let mut dest = UnsafeBufWriteSlice::new(array.as_mut_ptr(), M);
+		encode_into(bytes, &mut dest);
+		dest.assert_is_full();
+
+		// SAFETY: we only are pushing in chars in the table, which are all ASCII chars
+		ArrayString::from_utf8_unchecked(array)
+	}
+}
+
+/// Compile time check that an encoded array length `M` matches decoded
+/// array length `N`
+struct ArrayLens<const N: usize, const M: usize>;
+
+impl<const N: usize, const M: usize> ArrayLens<N, M> {
+	const CHECK: () = assert!(encoded_len(N) == M, "z85 string length must be the encoded length of the bytes");
+}
+
+#[inline]
+const fn encoded_len(bytes_len: usize) -> usize {
 	// right shift 2 is same as integer divide by 4 (BINARY_FRAME_LEN)
-	let frames = bytes.len() >> 2;
+	let frames = bytes_len >> 2;
 
 	// binary AND with 0b11 (3) is the same as modulo 4 (BINARY_FRAME_LEN)
-	let remainder = bytes.len() & 0b11;
+	let remainder = bytes_len & 0b11;
 
-	// preallocate exact amount of memory needed
-	let capacity = if remainder == 0 {
+	if remainder == 0 {
 		frames * STRING_FRAME_LEN
 	} else {
 		// frames is number of *whole* frames so the remainder is not included by default.
@@ -75,51 +191,260 @@ pub fn encode_z85(bytes: &[u8]) -> String {
 		let capacity = (frames + 1) * STRING_FRAME_LEN;
 		// don't forget that last byte that encodes amount of padding
 		capacity + 1
-	};
+	}
+}
+
+/// # Safety
+///
+/// `dest` must have `encoded_len(bytes.len())` bytes of capacity left
+unsafe fn encode_into<W: UnsafeBufWrite>(bytes: &[u8], dest: &mut W) {
+	let frames = bytes.len() >> 2;
+	let remainder = bytes.len() & 0b11;
 
 	let mut frames_iter = ChunkedSlice::<BINARY_FRAME_LEN>::new(bytes);
-	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
 
 	for _ in 0..frames {
-		unsafe {
-			// SAFETY: everything has been calculated:
-			// - amount of frames, so we'll have enough bytes left for next full frame
-			// - dest, we preallocated enough memory up front
-			let frame = frames_iter.next_frame_unchecked();
-			encode_frame(frame, &mut dest);
-		}
+		// SAFETY: everything has been calculated:
+		// - amount of frames, so we'll have enough bytes left for next full frame
+		// - dest, caller preallocated enough memory up front
+		let frame = frames_iter.next_frame_unchecked();
+		encode_frame(frame, dest);
 	}
 
 	if remainder > 0 {
-		unsafe {
-			frames_iter.with_remainder_unchecked(|remainder| {
-				// SAFETY: everything has been calculated:
-				// - remainder, so 0 < remainder < N will be true
-				//   (which is what `with_remainder_unchecked` requires)
-				// - dest, we preallocated enough memory up front
-				encode_frame(remainder, &mut dest);
-			});
-			let padding_len = BINARY_FRAME_LEN - remainder;
-
-			// SAFETY: 0 < padding_len < 4 will always be true, which
-			// fits in TABLE_ENCODER_LEN
-			let padding_char = *TABLE_ENCODER.get_unchecked(padding_len);
-			dest.write_bytes_const::<1>(&padding_char);
+		frames_iter.with_remainder_unchecked(|remainder| {
+			// SAFETY: everything has been calculated:
+			// - remainder, so 0 < remainder < N will be true
+			//   (which is what `with_remainder_unchecked` requires)
+			// - dest, caller preallocated enough memory up front
+			encode_frame(remainder, dest);
+		});
+		let padding_len = BINARY_FRAME_LEN - remainder;
+
+		// SAFETY: 0 < padding_len < 4 will always be true, which
+		// fits in TABLE_ENCODER_LEN
+		let padding_char = *TABLE_ENCODER.get_unchecked(padding_len);
+		dest.write_bytes_const::<1>(&padding_char);
+	}
+}
+
+/// Decodes a slice of of a Z85 string back into the source bytes
+pub fn decode_z85(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	let Some((frames, capacity, added_padding)) = decode_info(bytes)? else {
+		return Ok(Vec::new())
+	};
+
+	// because frames >= 1, this will be >= 0 (ie. will not underflow).
+	let (excluding_last_frame, last_frame) = frames.split_at(frames.len() - STRING_FRAME_LEN);
+
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe {
+		// SAFETY: we preallocated all the capacity we need up front
+		decode_frames(excluding_last_frame, &mut dest)?;
+		decode_last_frame(last_frame, added_padding, &mut dest)?;
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+/// Decodes a slice of of a Z85 string back into the source bytes, splitting
+/// the work across multiple threads for large inputs. Smaller inputs are
+/// decoded on the calling thread, the same as [`decode_z85`].
+#[inline]
+pub fn decode_z85_parallel(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	_decode_parallel(bytes, available_threads())
+}
+
+fn _decode_parallel(bytes: &[u8], threads: usize) -> Result<Vec<u8>, DecodeError> {
+	let Some((frames, capacity, added_padding)) = decode_info(bytes)? else {
+		return Ok(Vec::new())
+	};
+
+	let (excluding_last_frame, last_frame) = frames.split_at(frames.len() - STRING_FRAME_LEN);
+	let Some(chunks) = parallel_chunks(excluding_last_frame, STRING_FRAME_LEN, threads) else {
+		return decode_z85(bytes)
+	};
+
+	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+
+	unsafe {
+		// SAFETY: we preallocated all the capacity we need up front
+		let output_len = |chunk: &[u8]| chunk.len() / STRING_FRAME_LEN * BINARY_FRAME_LEN;
+		dest.write_parallel(&chunks, output_len, |chunk, dest| decode_frames(chunk, dest))?;
+		decode_last_frame(last_frame, added_padding, &mut dest)?;
+	}
+
+	Ok(dest.into_full_vec())
+}
+
+/// Decodes a Z85 string into a fixed size array, returning
+/// [`DecodeError::InvalidLength`] if it doesn't decode to exactly `N` bytes.
+pub fn decode_z85_array<const N: usize>(bytes: &[u8]) -> Result<[u8; N], DecodeError> {
+	let Some((frames, capacity, added_padding)) = decode_info(bytes)? else {
+		return if N == 0 { Ok([0u8; N]) } else { Err(DecodeError::InvalidLength) }
+	};
+	if capacity != N { return Err(DecodeError::InvalidLength) }
+
+	let (excluding_last_frame, last_frame) = frames.split_at(frames.len() - STRING_FRAME_LEN);
+	let mut array = [0u8; N];
+
+	unsafe {
+		// SAFETY: array has exactly the space needed
+		let mut dest = UnsafeBufWriteSlice::new(array.as_mut_ptr(), N);
+		decode_frames(excluding_last_frame, &mut dest)?;
+		decode_last_frame(last_frame, added_padding, &mut dest)?;
+		dest.assert_is_full();
+	}
+
+	Ok(array)
+}
+
+/// Decodes a Z85 string in place, writing the decoded bytes over the start of
+/// the input buffer, and returning the part of the buffer they were written to.
+///
+/// If an error is returned, the contents of the buffer are unspecified (part
+/// of it may have already been overwritten).
+pub fn decode_z85_in_place(bytes: &mut [u8]) -> Result<&mut [u8], DecodeError> {
+	let Some((frames, capacity, added_padding)) = decode_info(bytes)? else {
+		return Ok(bytes)
+	};
+	let frames = frames.len() / STRING_FRAME_LEN;
+
+	let bytes_ptr = bytes.as_mut_ptr();
+
+	unsafe {
+		// SAFETY: frame `n` is decoded to index `n * 4`, which is always before
+		// the start of the next frame at `(n + 1) * 5`. Each frame is copied out
+		// before it's decoded, since the output of the first frame overlaps
+		// itself. All reads and writes go through `bytes_ptr`.
+		let mut dest = UnsafeBufWriteSlice::new(bytes_ptr, capacity);
+
+		// because frames >= 1, this will be >= 0 (ie. will not underflow).
+		for i in 0..frames - 1 {
+			let frame = ptr::read(bytes_ptr.add(i * STRING_FRAME_LEN) as *const [u8; STRING_FRAME_LEN]);
+			decode_frame(&frame, |frame| dest.write_bytes_const::<BINARY_FRAME_LEN>(frame as *const u8))?;
 		}
+
+		let last_frame = ptr::read(bytes_ptr.add((frames - 1) * STRING_FRAME_LEN) as *const [u8; STRING_FRAME_LEN]);
+		decode_last_frame(&last_frame, added_padding, &mut dest)?;
+
+		dest.assert_is_full();
 	}
 
-	let vec = unsafe { dest.into_full_vec() };
-	debug_assert!(String::from_utf8(vec.clone()).is_ok(), "output bytes are valid utf-8");
+	Ok(&mut bytes[..capacity])
+}
 
-	// SAFETY: we only are pushing in chars in the table, which are all ASCII chars
-	unsafe { String::from_utf8_unchecked(vec) }
+/// Amount of bytes the given Z85 string decodes to. Only the length and
+/// padding marker of the input are checked, not its other chars, so this
+/// doesn't go through the whole input.
+#[inline]
+pub fn decoded_len_z85(bytes: &[u8]) -> Result<usize, DecodeError> {
+	Ok(decode_info(bytes)?.map(|(_, capacity, _)| capacity).unwrap_or(0))
 }
 
-/// Decodes a slice of of a Z85 string back into the source bytes
-pub fn decode_z85(mut bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+/// Decodes a Z85 string, writing the decoded bytes to the start of `dest`
+/// instead of allocating, and returning the amount of bytes written.
+///
+/// If an error is returned, the contents of `dest` are unspecified (part of it
+/// may have already been written to).
+///
+/// # Panics
+///
+/// Panics if `dest` is shorter than [`decoded_len_z85`].
+pub fn decode_z85_to_slice(bytes: &[u8], dest: &mut [u8]) -> Result<usize, DecodeError> {
+	let Some((frames, capacity, added_padding)) = decode_info(bytes)? else {
+		return Ok(0)
+	};
+	assert!(dest.len() >= capacity, "dest is too short for the decoded bytes");
+
+	let (excluding_last_frame, last_frame) = frames.split_at(frames.len() - STRING_FRAME_LEN);
+
+	unsafe {
+		// SAFETY: we checked dest has enough space
+		let mut dest = UnsafeBufWriteSlice::new(dest.as_mut_ptr(), capacity);
+		decode_frames(excluding_last_frame, &mut dest)?;
+		decode_last_frame(last_frame, added_padding, &mut dest)?;
+		dest.assert_is_full();
+	}
+
+	Ok(capacity)
+}
+
+/// Checks that the given bytes are valid Z85 (including the nonstandard
+/// padding), returning the same error [`decode_z85`] would, but without
+/// allocating anywhere to put the decoded bytes.
+///
+/// Like [`decode_z85`], this doesn't check that the padding bytes in the last
+/// frame are zero. To also check that, use [`is_canonical_z85`].
+pub fn validate_z85(bytes: &[u8]) -> Result<(), DecodeError> {
+	let Some((frames, _, added_padding)) = decode_info(bytes)? else {
+		return Ok(())
+	};
+
+	let (excluding_last_frame, last_frame) = frames.split_at(frames.len() - STRING_FRAME_LEN);
+
+	unsafe {
+		// SAFETY: the discarding writer doesn't need any capacity
+		decode_frames(excluding_last_frame, &mut UnsafeBufWriteDiscard)?;
+		decode_last_frame(last_frame, added_padding, &mut UnsafeBufWriteDiscard)
+	}
+}
+
+/// Decodes a Z85 string back into the source bytes, only accepting the
+/// canonical encoding. Input that [`decode_z85`] would accept, but that isn't
+/// what [`encode_z85`] would produce for the same bytes (the padding bytes in
+/// the last frame aren't zero, or there's a padding marker for 0 bytes of
+/// padding), is rejected with [`DecodeError::NonCanonical`].
+#[inline]
+pub fn decode_z85_strict(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+	check_canonical(bytes)?;
+	decode_z85(bytes)
+}
+
+/// Whether the given bytes are exactly what [`encode_z85`] produces for the
+/// bytes they decode to. Returns `false` for invalid Z85.
+#[inline]
+pub fn is_canonical_z85(bytes: &[u8]) -> bool {
+	check_canonical(bytes).is_ok() && validate_z85(bytes).is_ok()
+}
+
+/// Checks the padding of the input is what [`encode_z85`] would write. Input
+/// that isn't valid Z85 otherwise is let through, for the decoder to reject.
+fn check_canonical(bytes: &[u8]) -> Result<(), DecodeError> {
+	let Ok(Some((frames, _, added_padding))) = decode_info(bytes) else {
+		return Ok(())
+	};
+
+	if added_padding == 0 {
+		// a marker for 0 bytes of padding is never written
+		return if frames.len() == bytes.len() { Ok(()) } else { Err(DecodeError::NonCanonical) }
+	}
+
+	let last_frame = &frames[frames.len() - STRING_FRAME_LEN..];
+	let mut padding_is_zero = true;
+
+	// an error here is left for the decoder to return
+	let _ = unsafe {
+		// SAFETY: last_frame is one whole frame
+		let frame = &*(last_frame as *const [u8] as *const [u8; STRING_FRAME_LEN]);
+		decode_frame(frame, |frame| {
+			padding_is_zero = frame[BINARY_FRAME_LEN - added_padding..].iter().all(|b| *b == 0);
+		})
+	};
+
+	if padding_is_zero { Ok(()) } else { Err(DecodeError::NonCanonical) }
+}
+
+/// Checks the length of the input, and decodes the padding byte if there is
+/// one. Returns the input without the padding byte (which is a whole number of
+/// frames), the capacity needed for the output, and the amount of padding
+/// added. Returns `None` if the input is empty.
+#[allow(clippy::type_complexity)]
+fn decode_info(mut bytes: &[u8]) -> Result<Option<(&[u8], usize, usize)>, DecodeError> {
 	if bytes.len() < STRING_FRAME_LEN {
 		return if bytes.is_empty() {
-			Ok(Vec::new())
+			Ok(None)
 		} else {
 			// in here, bytes len is 0 < n < STRING_FRAME_LEN. we already returned
 			// on empty input. (empty bytes <-> empty string) at lengths 1-3, the
@@ -179,46 +504,58 @@ pub fn decode_z85(mut bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
 		_n => { return Err(DecodeError::InvalidLength) }
 	};
 
-	// because frames >= 1, this will be >= 0 (ie. will not underflow).
-	let excluding_last_frame = frames - 1;
-
-	let mut frames_iter = ChunkedSlice::<STRING_FRAME_LEN>::new(bytes);
-	let mut dest = UnsafeBufWriteGuard::with_capacity(capacity);
+	Ok(Some((bytes, capacity, added_padding)))
+}
 
-	for _ in 0..excluding_last_frame {
-		unsafe {
-			// SAFETY: everything has been calculated:
-			// - excluding_last_frame, so we'll always be in bounds of bytes
-			//   as well as not touch the last frame
-			// - dest, we preallocated all the capacity we need up front
+/// # Safety
+///
+/// `bytes` must only contain whole frames, and `dest` must have enough
+/// capacity left to hold all of them decoded
+unsafe fn decode_frames<W: UnsafeBufWrite>(bytes: &[u8], dest: &mut W) -> Result<(), DecodeError> {
+	let frames = bytes.len() / STRING_FRAME_LEN;
 
-			let frame = frames_iter.next_frame_unchecked();
-			decode_frame(frame, |frame| dest.write_bytes_const::<BINARY_FRAME_LEN>(frame as *const u8))?;
-		}
-	}
+	let mut frames_iter = ChunkedSlice::<STRING_FRAME_LEN>::new(bytes);
 
-	// the last frame, this is where the padding is handled
-	unsafe {
+	for _ in 0..frames {
+		// SAFETY: everything has been calculated:
+		// - frames, so we'll always be in bounds of bytes
+		// - dest, caller preallocated all the capacity we need up front
 		let frame = frames_iter.next_frame_unchecked();
-		decode_frame(frame, |frame| {
-			// - if 0 bytes of padding were added, this is whole frame and
-			//   added_padding would be 0
-			// - if 0 < n < 4 bytes of padding were added, this is correct
-			// - if 4 <= n bytes of "padding" were added, this should have been
-			//   either be 0 or 0 < n < 4
-			// this is checked up at the top, where the padding amount is decoded
-
-			// so because of all that, this will also be in range of 0 <= n < BINARY_FRAME_LEN
-			let non_padding_bytes = BINARY_FRAME_LEN - added_padding;
-			debug_assert!(non_padding_bytes <= BINARY_FRAME_LEN, "added padding is less than one full frame");
-
-			// SAFETY: as explained above, this is safe
-			dest.write_bytes(frame as *const u8, non_padding_bytes);
-		})?;
+		decode_frame(frame, |frame| dest.write_bytes_const::<BINARY_FRAME_LEN>(frame as *const u8))?;
 	}
 
 	frames_iter.debug_assert_is_empty();
-	Ok(unsafe { dest.into_full_vec() })
+	Ok(())
+}
+
+/// The last frame, this is where the padding is handled
+///
+/// # Safety
+///
+/// `last_frame` must be one whole frame, `added_padding` must be less than
+/// [`BINARY_FRAME_LEN`], and `dest` must have enough capacity left for the
+/// frame decoded, minus the padding
+unsafe fn decode_last_frame<W: UnsafeBufWrite>(
+	last_frame: &[u8],
+	added_padding: usize,
+	dest: &mut W
+) -> Result<(), DecodeError> {
+	let frame = &*(last_frame as *const [u8] as *const [u8; STRING_FRAME_LEN]);
+	decode_frame(frame, |frame| {
+		// - if 0 bytes of padding were added, this is whole frame and
+		//   added_padding would be 0
+		// - if 0 < n < 4 bytes of padding were added, this is correct
+		// - if 4 <= n bytes of "padding" were added, this should have been
+		//   either be 0 or 0 < n < 4
+		// this is checked up at the top, where the padding amount is decoded
+
+		// so because of all that, this will also be in range of 0 <= n < BINARY_FRAME_LEN
+		let non_padding_bytes = BINARY_FRAME_LEN - added_padding;
+		debug_assert!(non_padding_bytes <= BINARY_FRAME_LEN, "added padding is less than one full frame");
+
+		// SAFETY: as explained above, this is safe
+		dest.write_bytes(frame as *const u8, non_padding_bytes);
+	})
 }
 
 #[derive(Debug, ::thiserror::Error)]
@@ -226,10 +563,110 @@ pub enum DecodeError {
 	#[error("invalid length")]
 	InvalidLength,
 	#[error("invalid character")]
-	InvalidChar
+	InvalidChar,
+	#[error("frame value does not fit in 4 bytes")]
+	FrameOverflow,
+	#[error("non canonical encoding (padding is not zero)")]
+	NonCanonical
+}
+
+#[cfg(feature = "bytes")]
+pub use self::bytes::*;
+
+/// Encoding and decoding from any [`Buf`](::bytes::Buf) (which doesn't have
+/// to be contiguous), appending the output to a [`BytesMut`](::bytes::BytesMut)
+#[cfg(feature = "bytes")]
+mod bytes {
+	use crate::encoding_utils::bytes::{ decode_buf, encode_buf, BufCodec };
+	use ::bytes::{ Buf, BytesMut };
+	use super::*;
+
+	/// Z85 encodes everything remaining in `src` (adding padding if necessary),
+	/// appending it to `dest`
+	#[inline]
+	pub fn encode_z85_buf<B: Buf>(src: B, dest: &mut BytesMut) {
+		encode_buf(&Z85BufCodec, src, dest)
+	}
+
+	/// Decodes the Z85 remaining in `src`, appending the decoded bytes to
+	/// `dest`. If an error is returned, `dest` is left as it was, but an
+	/// unspecified amount of `src` will have been consumed.
+	#[inline]
+	pub fn decode_z85_buf<B: Buf>(src: B, dest: &mut BytesMut) -> Result<(), DecodeError> {
+		decode_buf(&Z85BufCodec, src, dest)
+	}
+
+	struct Z85BufCodec;
+
+	impl BufCodec for Z85BufCodec {
+		type Error = DecodeError;
+		const BINARY_FRAME_LEN: usize = BINARY_FRAME_LEN;
+		const STRING_FRAME_LEN: usize = STRING_FRAME_LEN;
+
+		#[inline]
+		fn encoded_len(&self, bytes_len: usize) -> usize {
+			encoded_len(bytes_len)
+		}
+
+		#[inline]
+		unsafe fn encode_into(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) {
+			encode_into(bytes, dest)
+		}
+
+		#[inline]
+		unsafe fn decode_frames(&self, bytes: &[u8], dest: &mut UnsafeBufWriteSlice) -> Result<(), DecodeError> {
+			decode_frames(bytes, dest)
+		}
+
+		#[inline]
+		fn decode_end(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			decode_z85(bytes)
+		}
+	}
+}
+
+#[cfg(feature = "tokio")]
+pub use self::tokio::*;
+
+/// Adapters for tokio's [`AsyncRead`](::tokio::io::AsyncRead) and
+/// [`AsyncWrite`](::tokio::io::AsyncWrite) that Z85 encode or decode data as
+/// it passes through. Decode errors are returned as [`io::Error`](::std::io::Error)s
+/// of kind [`InvalidData`](::std::io::ErrorKind::InvalidData), wrapping a [`DecodeError`].
+#[cfg(feature = "tokio")]
+mod tokio {
+	use crate::encoding_utils::stream::{ DecodeReader, DecodeWriter, EncodeReader, EncodeWriter, StreamCodec };
+	use super::*;
+
+	pub type Z85EncodeReader<R> = EncodeReader<R, Z85Codec>;
+	pub type Z85DecodeReader<R> = DecodeReader<R, Z85Codec>;
+	pub type Z85EncodeWriter<W> = EncodeWriter<W, Z85Codec>;
+	pub type Z85DecodeWriter<W> = DecodeWriter<W, Z85Codec>;
+
+	/// Z85 codec for the stream adapters, with the same nonstandard padding
+	/// as [`encode_z85`] and [`decode_z85`]
+	#[derive(Clone, Copy, Debug, Default)]
+	pub struct Z85Codec;
+
+	impl StreamCodec for Z85Codec {
+		type Error = DecodeError;
+		const BINARY_FRAME_LEN: usize = BINARY_FRAME_LEN;
+		const STRING_FRAME_LEN: usize = STRING_FRAME_LEN;
+		// a padding marker can come after the last frame
+		const HOLD_LAST_FRAME: bool = true;
+
+		#[inline]
+		fn encode(&self, bytes: &[u8]) -> String {
+			encode_z85(bytes)
+		}
+
+		#[inline]
+		fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+			decode_z85(bytes)
+		}
+	}
 }
 
-unsafe fn encode_frame(frame: &[u8; BINARY_FRAME_LEN], dest: &mut UnsafeBufWriteGuard) {
+unsafe fn encode_frame<W: UnsafeBufWrite>(frame: &[u8; BINARY_FRAME_LEN], dest: &mut W) {
 	let mut int = u32::from_be_bytes(*frame) as usize;
 
 	let byte5 = int % TABLE_ENCODER_LEN;
@@ -299,8 +736,12 @@ where
 	int *= TABLE_ENCODER_LEN as u32;
 	int += byte4 as u32;
 
-	int *= TABLE_ENCODER_LEN as u32;
-	int += byte5 as u32;
+	// 85^5 doesn't fit in a u32, so this last step can overflow on input that
+	// we would never have encoded (ex. "#####")
+	let int = (int as u64 * TABLE_ENCODER_LEN as u64) + byte5 as u64;
+	let Ok(int) = u32::try_from(int) else {
+		return Err(DecodeError::FrameOverflow)
+	};
 
 	let decoded_frame = u32::to_be_bytes(int);
 	f(&decoded_frame);
@@ -431,4 +872,268 @@ mod tests {
 			assert_eq!(str1, str2);
 		}
 	}
+
+	#[test]
+	fn frame_overflow() {
+		// "%nSc0" is u32::MAX, the largest value that fits
+		assert_eq!(decode_z85(b"%nSc0").unwrap(), [0xff; 4]);
+		assert!(matches!(decode_z85(b"%nSc1"), Err(DecodeError::FrameOverflow)));
+		assert!(matches!(decode_z85(b"#####"), Err(DecodeError::FrameOverflow)));
+		assert!(matches!(decode_z85_parallel(b"#####"), Err(DecodeError::FrameOverflow)));
+	}
+
+	#[test]
+	fn arrays() {
+		let mut rng = thread_rng();
+
+		let bytes = rng.gen::<[u8; 16]>();
+		let encoded: ArrayString<20> = encode_z85_array(&bytes);
+		assert_eq!(encoded, &*encode_z85(&bytes));
+		assert_eq!(decode_z85_array::<16>(encoded.as_bytes()).unwrap(), bytes);
+
+		let bytes = rng.gen::<[u8; 30]>();
+		let encoded: ArrayString<41> = encode_z85_array(&bytes);
+		assert_eq!(encoded, &*encode_z85(&bytes));
+		assert_eq!(decode_z85_array::<30>(encoded.as_bytes()).unwrap(), bytes);
+
+		let mut bytes = [0u8; 64];
+		rng.fill(&mut bytes);
+		let encoded: ArrayString<80> = encode_z85_array(&bytes);
+		assert_eq!(decode_z85_array::<64>(encoded.as_bytes()).unwrap(), bytes);
+
+		let encoded: ArrayString<0> = encode_z85_array(&[]);
+		assert_eq!(encoded, "");
+		assert_eq!(decode_z85_array::<0>(b"").unwrap(), []);
+
+		assert!(matches!(decode_z85_array::<8>(b"Hello"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_z85_array::<7>(b"HelloWorld"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_z85_array::<4>(b""), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_z85_array::<8>(b"Hello~orld"), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn in_place() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..20).chain([1000, 1001]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let mut encoded = encode_z85(&bytes).into_bytes();
+			let decoded = decode_z85_in_place(&mut encoded)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+		}
+
+		assert!(matches!(decode_z85_in_place(&mut b"Hell".to_vec()), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_z85_in_place(&mut b"Hello~orld".to_vec()), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn validate() {
+		let mut rng = thread_rng();
+
+		for len in 0usize..50 {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+			let encoded = encode_z85(&bytes);
+			assert!(validate_z85(encoded.as_bytes()).is_ok());
+		}
+
+		assert!(validate_z85(b"HelloWorld").is_ok());
+		assert!(validate_z85(b"HelloWorld0").is_ok());
+		assert!(matches!(validate_z85(b"Hell"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(validate_z85(b"HelloWorld12"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(validate_z85(b"HelloWorld4"), Err(DecodeError::InvalidChar)));
+		assert!(matches!(validate_z85(b"Hello~orld"), Err(DecodeError::InvalidChar)));
+		// overflows a u32
+		assert!(matches!(validate_z85(b"#####"), Err(DecodeError::FrameOverflow)));
+	}
+
+	#[test]
+	fn to_slice() {
+		let mut rng = thread_rng();
+
+		for len in (0usize..20).chain([1000, 1001]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let mut encoded = vec![0u8; encoded_len(len) + 3];
+			assert_eq!(encode_z85_to_slice(&bytes, &mut encoded), encoded_len(len));
+			assert_eq!(&encoded[..encoded_len(len)], encode_z85(&bytes).as_bytes());
+			assert_eq!(encoded[encoded_len(len)..], [0, 0, 0]);
+
+			let encoded = &encoded[..encoded_len(len)];
+			assert_eq!(decoded_len_z85(encoded).unwrap(), len);
+			let mut decoded = vec![0u8; len];
+			assert_eq!(decode_z85_to_slice(encoded, &mut decoded).unwrap(), len);
+			assert_eq!(decoded, bytes);
+		}
+
+		assert!(matches!(decoded_len_z85(b"Hell"), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decoded_len_z85(b"HelloWorld4"), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_z85_to_slice(b"Hello~orld", &mut [0u8; 8]), Err(DecodeError::InvalidChar)));
+	}
+
+	#[test]
+	fn canonical() {
+		let mut rng = thread_rng();
+
+		for len in 0usize..50 {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+			let encoded = encode_z85(&bytes);
+			assert!(is_canonical_z85(encoded.as_bytes()));
+			assert_eq!(decode_z85_strict(encoded.as_bytes()).unwrap(), bytes);
+		}
+
+		// "ab" is padded with two zero bytes, "ab\x01\x02" would have
+		// been encoded as the same frame with nonzero padding
+		let encoded = encode_z85(b"ab\x01\x02");
+		let non_canonical = format!("{encoded}2");
+		assert_eq!(decode_z85(non_canonical.as_bytes()).unwrap(), b"ab");
+		assert!(!is_canonical_z85(non_canonical.as_bytes()));
+		assert!(matches!(decode_z85_strict(non_canonical.as_bytes()), Err(DecodeError::NonCanonical)));
+
+		// a marker for 0 bytes of padding
+		assert!(decode_z85(b"HelloWorld0").is_ok());
+		assert!(!is_canonical_z85(b"HelloWorld0"));
+		assert!(matches!(decode_z85_strict(b"HelloWorld0"), Err(DecodeError::NonCanonical)));
+
+		// invalid input still gets the decoder's error
+		assert!(!is_canonical_z85(b"Hello~orld"));
+		assert!(matches!(decode_z85_strict(b"Hello~orld"), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_z85_strict(b"#####"), Err(DecodeError::FrameOverflow)));
+	}
+
+	#[test]
+	fn parallel() {
+		let mut rng = thread_rng();
+
+		for len in [0usize, 10, 5 * 1024 * 1024, 5 * 1024 * 1024 + 3] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			// always split as if there are 4 cores, so the parallel
+			// path gets exercised on single core machines too
+			let encoded = _encode_parallel(&bytes, 4);
+			assert_eq!(encoded, encode_z85(&bytes));
+			assert_eq!(encoded, encode_z85_parallel(&bytes));
+			assert_eq!(encoded.len(), encoded.capacity());
+
+			let decoded = _decode_parallel(encoded.as_bytes(), 4)
+				.expect("can round trip decode just encoded data");
+			assert_eq!(bytes, decoded);
+			assert_eq!(bytes, decode_z85_parallel(encoded.as_bytes()).unwrap());
+			assert_eq!(decoded.len(), decoded.capacity());
+		}
+
+		let mut invalid = encode_z85(&vec![0u8; 5 * 1024 * 1024]).into_bytes();
+		invalid[3 * 1024 * 1024] = b'~';
+		assert!(matches!(_decode_parallel(&invalid, 4), Err(DecodeError::InvalidChar)));
+		assert!(matches!(decode_z85_parallel(&invalid), Err(DecodeError::InvalidChar)));
+	}
+
+	#[cfg(feature = "tokio")]
+	#[::tokio::test(crate = "::tokio")]
+	async fn stream() {
+		use crate::encoding_utils::stream::test_utils::Trickle;
+		use ::tokio::io::{ AsyncReadExt, AsyncWriteExt };
+
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 2, 3, 4, 5, 100_000, 100_003] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+			let encoded = encode_z85(&bytes);
+
+			for n in [1, 3, 7, 10_000] {
+				let mut reader = Z85EncodeReader::new(Trickle::new(&bytes, n));
+				let mut out = String::new();
+				reader.read_to_string(&mut out).await.unwrap();
+				assert_eq!(encoded, out);
+
+				let mut reader = Z85DecodeReader::new(Trickle::new(encoded.as_bytes(), n));
+				let mut out = Vec::new();
+				reader.read_to_end(&mut out).await.unwrap();
+				assert_eq!(bytes, out);
+
+				let mut writer = Z85EncodeWriter::new(Vec::new());
+				for chunk in bytes.chunks(n) {
+					writer.write_all(chunk).await.unwrap();
+				}
+				writer.shutdown().await.unwrap();
+				assert_eq!(encoded.as_bytes(), writer.into_inner());
+
+				let mut writer = Z85DecodeWriter::new(Vec::new());
+				for chunk in encoded.as_bytes().chunks(n) {
+					writer.write_all(chunk).await.unwrap();
+				}
+				writer.shutdown().await.unwrap();
+				assert_eq!(bytes, writer.into_inner());
+			}
+		}
+
+		let mut reader = Z85DecodeReader::new(Trickle::new(b"HelloWorld~", 3));
+		let err = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+		assert_eq!(err.kind(), ::std::io::ErrorKind::InvalidData);
+		assert!(matches!(err.get_ref().and_then(|e| e.downcast_ref()), Some(DecodeError::InvalidChar)));
+
+		let mut reader = Z85DecodeReader::new(Trickle::new(b"HelloWorld12", 3));
+		let err = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+		assert!(matches!(err.get_ref().and_then(|e| e.downcast_ref()), Some(DecodeError::InvalidLength)));
+	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn bytes() {
+		use crate::encoding_utils::bytes::test_utils::Chunked;
+		use ::bytes::BytesMut;
+
+		let mut rng = thread_rng();
+
+		for len in [0usize, 1, 4, 7, 1000, 1001] {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+			let encoded = encode_z85(&bytes);
+
+			for n in [1, 3, 7, 1000] {
+				let mut dest = BytesMut::from(&b"prefix"[..]);
+				encode_z85_buf(Chunked::new(&bytes, n), &mut dest);
+				assert_eq!(&dest[..6], b"prefix");
+				assert_eq!(&dest[6..], encoded.as_bytes());
+
+				let mut dest = BytesMut::from(&b"prefix"[..]);
+				decode_z85_buf(Chunked::new(encoded.as_bytes(), n), &mut dest).unwrap();
+				assert_eq!(&dest[..6], b"prefix");
+				assert_eq!(&dest[6..], &*bytes);
+			}
+		}
+
+		let mut dest = BytesMut::from(&b"prefix"[..]);
+		assert!(matches!(decode_z85_buf(Chunked::new(b"0000000", 3), &mut dest), Err(DecodeError::InvalidLength)));
+		assert!(matches!(decode_z85_buf(Chunked::new(b"00000 0000", 3), &mut dest), Err(DecodeError::InvalidChar)));
+		assert_eq!(&dest[..], b"prefix");
+	}
+
+	#[test]
+	fn vectored() {
+		use crate::encoding_utils::test_utils::random_slices;
+		use ::std::io::IoSlice;
+
+		let mut rng = thread_rng();
+
+		for len in (0usize..40).chain([1000]) {
+			let mut bytes = vec![0u8; len];
+			rng.fill(&mut *bytes);
+
+			let slices = random_slices(&bytes, &mut rng);
+			let io_slices = slices.iter().map(|s| IoSlice::new(s)).collect::<Vec<_>>();
+
+			let encoded = encode_z85_vectored(&*io_slices);
+			assert_eq!(encoded, encode_z85(&bytes));
+			assert_eq!(encoded.len(), encoded.capacity());
+			assert_eq!(encode_z85_vectored(&*slices), encoded);
+		}
+	}
 }